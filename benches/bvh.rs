@@ -0,0 +1,116 @@
+//! Compares the median-split `BVHNode` with the SAH-built flat `BVH` on a
+//! mesh-like scene. Run with `cargo bench --bench bvh`.
+
+use raytracer::color::Color;
+use raytracer::hittable::bvh::BVH;
+use raytracer::hittable::bvh_node::BVHNode;
+use raytracer::hittable::triangle::Triangle;
+use raytracer::hittable::{HitRecord, Hittable, HittableList};
+use raytracer::interval::Interval;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::vector::Point3;
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const GRID: usize = 300;
+const RAYS: usize = 200_000;
+
+/// A rippled height field of 2 * GRID * GRID triangles
+fn height_field() -> HittableList {
+    let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let height = |i: usize, j: usize| {
+        let (x, z) = (i as f64 / GRID as f64, j as f64 / GRID as f64);
+        Point3::new(
+            x * 10.0 - 5.0,
+            (x * 20.0).sin() * (z * 15.0).cos() * 0.5,
+            z * 10.0 - 5.0,
+        )
+    };
+
+    let mut world = HittableList::new();
+    for i in 0..GRID {
+        for j in 0..GRID {
+            let (a, b, c, d) = (
+                height(i, j),
+                height(i + 1, j),
+                height(i + 1, j + 1),
+                height(i, j + 1),
+            );
+            world.add(Arc::new(Triangle::new(a, b, c, mat.clone())));
+            world.add(Arc::new(Triangle::new(a, c, d, mat.clone())));
+        }
+    }
+
+    world
+}
+
+fn camera_rays() -> Vec<Ray> {
+    let mut rng = Rng::new(1);
+    let origin = Point3::new(0.0, 6.0, -9.0);
+
+    (0..RAYS)
+        .map(|_| {
+            let target = Point3::new(
+                rng.random_range(-5.0, 5.0),
+                0.0,
+                rng.random_range(-5.0, 5.0),
+            );
+            Ray::new(origin, target - origin)
+        })
+        .collect()
+}
+
+fn trace(object: &dyn Hittable, rays: &[Ray]) -> (Duration, usize) {
+    let mut rng = Rng::new(0);
+    let start = Instant::now();
+    let mut hits = 0;
+
+    for ray in rays {
+        let mut rec = HitRecord::new();
+        let mut t = Interval::new(0.001, f64::INFINITY);
+        if object.hit(ray, &mut t, &mut rec, &mut rng) {
+            hits += 1;
+        }
+    }
+
+    (start.elapsed(), black_box(hits))
+}
+
+fn main() {
+    let world = height_field();
+    let rays = camera_rays();
+    println!("{} triangles, {} rays\n", world.objects.len(), rays.len());
+
+    let start = Instant::now();
+    let bvh_node = BVHNode::new(&world);
+    let bvh_node_build = start.elapsed();
+    let (bvh_node_trace, bvh_node_hits) = trace(&bvh_node, &rays);
+
+    let start = Instant::now();
+    let bvh = BVH::new(&world);
+    let bvh_build = start.elapsed();
+    let (bvh_trace, bvh_hits) = trace(&bvh, &rays);
+
+    assert_eq!(bvh_hits, bvh_node_hits);
+
+    println!(
+        "{:<16} {:>12} {:>12} {:>14}",
+        "", "build", "trace", "Mrays/s"
+    );
+    for (name, build, trace) in [
+        ("BVHNode", bvh_node_build, bvh_node_trace),
+        ("BVH (SAH, flat)", bvh_build, bvh_trace),
+    ] {
+        println!(
+            "{:<16} {:>10.1}ms {:>10.1}ms {:>14.2}",
+            name,
+            build.as_secs_f64() * 1000.0,
+            trace.as_secs_f64() * 1000.0,
+            rays.len() as f64 / trace.as_secs_f64() / 1e6
+        );
+    }
+    println!("\nBVH nodes: {}", bvh.node_count());
+}
@@ -3,6 +3,7 @@ use crate::ray::Ray;
 use crate::vector::{Point3, Vector3};
 use std::ops::Add;
 
+#[derive(Clone, Copy)]
 pub struct AABB {
     x: Interval,
     y: Interval,
@@ -39,7 +40,7 @@ impl AABB {
             Interval::new(b.z(), a.z())
         };
 
-        Self { x, y, z }
+        Self::new(x, y, z)
     }
 
     pub fn new_from_aabbs(box0: &AABB, box1: &AABB) -> Self {
@@ -118,6 +119,38 @@ impl AABB {
         }
     }
 
+    /// Slab test against a ray whose direction has already been inverted per axis.
+    /// Cheaper than `hit` when many boxes are tested against the same ray.
+    pub fn hit_inverse(&self, origin: &Point3, inv_direction: &Vector3, t: Interval) -> bool {
+        let mut t = t;
+
+        for axis in 0..3 {
+            let interval = self.axis_interval(axis as i32);
+            let t0 = (interval.min - origin[axis]) * inv_direction[axis];
+            let t1 = (interval.max - origin[axis]) * inv_direction[axis];
+
+            t.min = t.min.max(t0.min(t1));
+            t.max = t.max.min(t0.max(t1));
+
+            if t.max <= t.min {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let x = self.x.size();
+        let y = self.y.size();
+        let z = self.z.size();
+        2.0 * (x * y + y * z + z * x)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min() + self.max())
+    }
+
     pub fn min(&self) -> Point3 {
         Point3::new(self.x.min, self.y.min, self.z.min)
     }
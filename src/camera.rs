@@ -1,27 +1,32 @@
-use crate::color::Color;
-use crate::hittable::{HitRecord, HittableList};
+use crate::color::{Color, luminance};
+use crate::environment::{Environment, EnvironmentLight};
+use crate::hittable::bvh::BVH;
+use crate::hittable::{HitRecord, Hittable, HittableList};
 use crate::interval::Interval;
+use crate::light::LightList;
+use crate::output::{self, BitDepth, Framebuffer, ImageFormat};
+use crate::pdf::{HittablePdf, Pdf, power_heuristic};
 use crate::ray::Ray;
+use crate::sampler::{Sampler, SamplerKind};
+use crate::tonemap::ToneMapper;
 use crate::vector::{Point3, Vector3};
 use anyhow::Result;
-use rand::random_range;
-use std::fs::File;
-use std::io::BufWriter;
 use std::io::Write;
 use std::io::stdout;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::sync::Mutex;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::thread;
 use std::time::{Duration, Instant};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct CameraData {
-    image_width: i32,
-    image_height: i32,
     samples_per_pixel: i32,
+    min_samples_per_pixel: i32,
+    adaptive_threshold: f64,
     max_depth: i32,
-    pixel_samples_scale: f64,
+    roulette_depth: i32,
     center: Point3,
     pixel00_loc: Point3,
     pixel_delta_u: Vector3,
@@ -29,25 +34,57 @@ struct CameraData {
     defocus_angle: f64,
     defocus_disk_u: Vector3,
     defocus_disk_v: Vector3,
+    shutter_open: f64,
+    shutter_close: f64,
     background: Color,
+    environment: Option<Arc<dyn Environment>>,
+    sampler: SamplerKind,
+    seed: u64,
+}
+
+impl CameraData {
+    /// Radiance reaching the scene along a ray that escapes it
+    fn background(&self, ray: &Ray) -> Color {
+        match &self.environment {
+            Some(environment) => environment.radiance(&ray.get_direction()),
+            None => self.background,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Camera {
     pub aspect_ratio: f64,
     pub image_width: i32,
-    pub samples_per_pixel: i32,
+    pub samples_per_pixel: i32,     // Most samples a pixel takes
+    pub min_samples_per_pixel: i32, // Samples a pixel takes before it may stop early
+    pub adaptive_threshold: f64,    // Relative error that stops a pixel, 0 samples all evenly
     pub max_depth: i32,
-    pub vfov: f64,          // Vertical field of view in degrees
-    pub lookfrom: Point3,   // Point camera is looking from
-    pub lookat: Point3,     // Point camera is looking at
-    pub vup: Vector3,       // Camera-relative "up" direction
-    pub defocus_angle: f64, // Variation angle of rays through each pixel
-    pub focus_dist: f64,    // Distance to perfect focus plane
+    pub roulette_depth: i32, // Bounces before Russian roulette may end a path
+    pub vfov: f64,           // Vertical field of view in degrees
+    pub lookfrom: Point3,    // Point camera is looking from
+    pub lookat: Point3,      // Point camera is looking at
+    pub vup: Vector3,        // Camera-relative "up" direction
+    pub defocus_angle: f64,  // Variation angle of rays through each pixel
+    pub focus_dist: f64,     // Distance to perfect focus plane
+    pub shutter_open: f64,   // Start of the exposure, objects move from time 0 to 1
+    pub shutter_close: f64,  // End of the exposure, equal to the start for no motion blur
     pub background: Color,
+    /// Surroundings that light the scene and are seen by escaped rays in
+    /// place of the background colour
+    pub environment: Option<Arc<dyn Environment>>,
+    pub threads: usize,           // Worker threads, 0 uses every available core
+    pub sampler: SamplerKind,     // Source of pixel, lens and scattering samples
+    pub seed: u64,                // Seed for the random number generators
+    pub output: PathBuf,          // Path the rendered image is written to
+    pub bit_depth: BitDepth,      // Bits per channel of PNG output
+    pub tone_mapper: ToneMapper,  // Exposure and curve applied to PNG and PPM output
+    pub tile_size: i32,           // Edge length of the square tiles handed to threads
+    pub use_bvh: bool,            // Build a BVH over the world before rendering
+    pub heatmap: Option<PathBuf>, // Image of the samples every pixel took
 
     image_height: i32,
-    pixel_samples_scale: f64,
+    min_samples: i32, // Minimum actually taken, at most samples_per_pixel
     center: Point3,
     pixel00_loc: Point3,
     pixel_delta_u: Vector3,
@@ -65,18 +102,33 @@ impl Camera {
             aspect_ratio: 1.0,
             image_width: 100,
             samples_per_pixel: 10,
+            min_samples_per_pixel: 8,
+            adaptive_threshold: 0.0,
             max_depth: 10,
+            roulette_depth: 3,
             vfov: 90.0,
             lookfrom: Point3::new(0.0, 0.0, 0.0),
             lookat: Point3::new(0.0, 0.0, -1.0),
             vup: Vector3::new(0.0, 1.0, 0.0),
             defocus_angle: 0.0,
             focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
             background: Color::new(1.0, 1.0, 1.0),
+            environment: None,
+            threads: 0,
+            sampler: SamplerKind::Independent,
+            seed: 0,
+            output: PathBuf::from("image.png"),
+            bit_depth: BitDepth::Eight,
+            tone_mapper: ToneMapper::new(),
+            tile_size: 32,
+            use_bvh: true,
+            heatmap: None,
 
             // These will be calculated in initialize()
             image_height: 0,
-            pixel_samples_scale: 0.0,
+            min_samples: 0,
             center: Point3::new(0.0, 0.0, 0.0),
             pixel00_loc: Point3::new(0.0, 0.0, 0.0),
             pixel_delta_u: Vector3::new(0.0, 0.0, 0.0),
@@ -89,6 +141,17 @@ impl Camera {
         }
     }
 
+    /// Height of the rendered image, valid once the camera has been initialized
+    pub fn image_height(&self) -> i32 {
+        self.image_height
+    }
+
+    /// Samples every pixel takes before adaptive sampling may stop it, never
+    /// more than `samples_per_pixel`; valid once the camera has been initialized
+    pub fn min_samples(&self) -> i32 {
+        self.min_samples
+    }
+
     pub fn initialize(&mut self) {
         self.image_height = (self.image_width as f64 / self.aspect_ratio) as i32;
         self.image_height = if self.image_height < 1 {
@@ -96,8 +159,9 @@ impl Camera {
         } else {
             self.image_height
         };
-
-        self.pixel_samples_scale = 1.0 / self.samples_per_pixel as f64;
+        self.min_samples = self
+            .min_samples_per_pixel
+            .clamp(1, self.samples_per_pixel.max(1));
 
         self.center = self.lookfrom;
 
@@ -130,336 +194,620 @@ impl Camera {
         self.defocus_v = self.v * defocus_radius;
     }
 
-    fn color(&self, r: &Ray, world: &HittableList, depth: i32) -> Color {
-        // If we've exceeded the ray bounce limit, no more light is gathered
-        if depth <= 0 {
-            return Color::new(0.0, 0.0, 0.0);
+    /// Wraps the world in a BVH unless that has been disabled, in which case
+    /// every ray is tested against every object in turn
+    fn build_acceleration(&self, world: HittableList) -> Box<dyn Hittable> {
+        if !self.use_bvh {
+            println!(
+                "BVH disabled, intersecting {} objects linearly",
+                world.objects.len()
+            );
+            return Box::new(world);
         }
 
-        let mut rec = HitRecord::new();
+        let start = Instant::now();
+        let bvh = BVH::new(&world);
+        println!(
+            "Built BVH over {} objects with {} nodes in {:.1?}",
+            world.objects.len(),
+            bvh.node_count(),
+            start.elapsed()
+        );
 
-        if !world.hit(r, Interval::new(0.001, f64::INFINITY), &mut rec) {
-            return self.background;
-        }
+        Box::new(bvh)
+    }
 
-        let mut scattered = Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
-        let mut attenuation = Color::new(0.0, 0.0, 0.0);
-        let color_from_emission = rec.mat.emitted(rec.u, rec.v, &rec.p);
+    /// Renders `world` and writes the image to `output`, in the format given
+    /// by its extension
+    pub fn render(
+        &mut self,
+        world: HittableList,
+        lights: HittableList,
+        analytic_lights: LightList,
+    ) -> Result<()> {
+        // Fail before spending time on a render that can't be saved
+        ImageFormat::from_path(&self.output)?;
 
-        if !rec.mat.scatter(r, &rec, &mut attenuation, &mut scattered) {
-            return color_from_emission;
+        if let Some(heatmap) = &self.heatmap {
+            ImageFormat::from_path(heatmap)?;
         }
 
-        let color_from_scatter = attenuation * self.color(&scattered, world, depth - 1);
+        let (framebuffer, sample_counts) =
+            self.render_with_sample_counts(world, lights, analytic_lights)?;
+
+        println!("\nWriting image to {}...", self.output.display());
+        output::write(
+            &framebuffer,
+            &self.output,
+            self.bit_depth,
+            &self.tone_mapper,
+        )?;
+
+        if let Some(heatmap) = &self.heatmap {
+            println!("Writing sample heatmap to {}...", heatmap.display());
+            let image = output::heatmap(
+                &sample_counts,
+                framebuffer.width(),
+                framebuffer.height(),
+                self.samples_per_pixel as u32,
+            );
+            output::write(&image, heatmap, self.bit_depth, &ToneMapper::new())?;
+        }
+
+        println!("Done!");
+        Ok(())
+    }
 
-        color_from_emission + color_from_scatter
+    /// Renders `world`, sampling the emitters in `lights` directly at every
+    /// diffuse bounce, along with the environment if there is one and every
+    /// analytic light. With no lights only the material's own sampling is used.
+    pub fn render_to_framebuffer(
+        &mut self,
+        world: HittableList,
+        lights: HittableList,
+        analytic_lights: LightList,
+    ) -> Result<Framebuffer> {
+        let (framebuffer, _) = self.render_with_sample_counts(world, lights, analytic_lights)?;
+        Ok(framebuffer)
     }
 
-    pub fn render(&mut self, world: HittableList) -> Result<()> {
+    /// Like [`Camera::render_to_framebuffer`], also returning the number of
+    /// samples every pixel took, row by row
+    pub fn render_with_sample_counts(
+        &mut self,
+        world: HittableList,
+        mut lights: HittableList,
+        analytic_lights: LightList,
+    ) -> Result<(Framebuffer, Vec<u32>)> {
         self.initialize();
 
-        let cores = num_cpus::get() as i32;
+        if let Some(environment) = &self.environment {
+            lights.add(Arc::new(EnvironmentLight::new(environment.clone())));
+        }
+
+        let tiles = TileQueue::new(self.image_width, self.image_height, self.tile_size);
+        let threads = self.thread_count().min(tiles.len());
 
+        let samples = if self.adaptive_threshold > 0.0 {
+            format!(
+                "{} to {} samples per pixel",
+                self.min_samples, self.samples_per_pixel
+            )
+        } else {
+            format!("{} samples per pixel", self.samples_per_pixel)
+        };
         println!(
-            "Rendering a {}x{} image with {} samples per pixel using {} cores",
-            self.image_width, self.image_height, self.samples_per_pixel, cores
+            "Rendering a {}x{} image with {} in {} tiles using {} threads",
+            self.image_width,
+            self.image_height,
+            samples,
+            tiles.len(),
+            threads
         );
         println!();
 
-        let pixels = Arc::new(Mutex::new(vec![
-            Color::new(0.0, 0.0, 0.0);
-            (self.image_width * self.image_height)
-                as usize
-        ]));
-        let world = Arc::new(world);
-
-        let mut task_ranges = Vec::new();
-        let rows_per_task = (self.image_height + cores - 1) / cores;
-        let mut actual_task_count = 0;
-
-        for task_id in 0..cores {
-            let start_row = task_id * rows_per_task;
-            let end_row = ((task_id + 1) * rows_per_task).min(self.image_height);
+        let world = self.build_acceleration(world);
+        let camera_data = CameraData {
+            samples_per_pixel: self.samples_per_pixel,
+            min_samples_per_pixel: self.min_samples,
+            adaptive_threshold: self.adaptive_threshold,
+            max_depth: self.max_depth,
+            roulette_depth: self.roulette_depth,
+            center: self.center,
+            pixel00_loc: self.pixel00_loc,
+            pixel_delta_u: self.pixel_delta_u,
+            pixel_delta_v: self.pixel_delta_v,
+            defocus_angle: self.defocus_angle,
+            defocus_disk_u: self.defocus_u,
+            defocus_disk_v: self.defocus_v,
+            shutter_open: self.shutter_open,
+            shutter_close: self.shutter_close,
+            background: self.background,
+            environment: self.environment.clone(),
+            sampler: self.sampler,
+            seed: self.seed,
+        };
 
-            if start_row >= self.image_height {
-                break;
+        let mut framebuffer = Framebuffer::new(self.image_width as u32, self.image_height as u32);
+        let mut sample_counts = vec![0; (self.image_width * self.image_height) as usize];
+        let progress = ProgressTracker::new(&tiles, self.image_width * self.image_height);
+        let (sender, receiver) = mpsc::channel::<(Tile, Vec<(Color, u32)>)>();
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                let sender = sender.clone();
+                let (camera_data, tiles, progress) = (&camera_data, &tiles, &progress);
+                let (world, lights, analytic_lights) = (&world, &lights, &analytic_lights);
+
+                scope.spawn(move || {
+                    while let Some(tile) = tiles.next() {
+                        let buffer = Self::render_tile(
+                            camera_data,
+                            world.as_ref(),
+                            lights,
+                            analytic_lights,
+                            tile,
+                            progress,
+                        );
+                        if sender.send((tile, buffer)).is_err() {
+                            break;
+                        }
+                    }
+                });
             }
+            drop(sender);
+
+            // Workers hand over finished tiles, so only this thread ever
+            // touches the framebuffer
+            let mut last_print = Instant::now();
+            loop {
+                match receiver.recv_timeout(Duration::from_millis(500)) {
+                    Ok((tile, buffer)) => {
+                        tile.copy_into(&mut framebuffer, &mut sample_counts, &buffer);
+                        progress.finish_tile();
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
 
-            task_ranges.push((start_row, end_row));
-            actual_task_count += 1;
-        }
-
-        let actual_rows_per_task: Vec<i32> =
-            task_ranges.iter().map(|(start, end)| end - start).collect();
-        let progress_tracker = Arc::new(ProgressTracker::new(
-            actual_task_count as usize,
-            actual_rows_per_task,
-            self.image_width,
-        ));
-
-        let mut handles = Vec::new();
-
-        for (task_id, (start_row, end_row)) in task_ranges.into_iter().enumerate() {
-            let pixels_clone = Arc::clone(&pixels);
-            let world_clone = Arc::clone(&world);
-            let progress_clone = Arc::clone(&progress_tracker);
-
-            let camera_data = CameraData {
-                image_width: self.image_width,
-                image_height: self.image_height,
-                samples_per_pixel: self.samples_per_pixel,
-                max_depth: self.max_depth,
-                pixel_samples_scale: self.pixel_samples_scale,
-                center: self.center,
-                pixel00_loc: self.pixel00_loc,
-                pixel_delta_u: self.pixel_delta_u,
-                pixel_delta_v: self.pixel_delta_v,
-                defocus_angle: self.defocus_angle,
-                defocus_disk_u: self.defocus_u,
-                defocus_disk_v: self.defocus_v,
-                background: self.background,
-            };
+                if last_print.elapsed() >= Duration::from_millis(200) {
+                    progress.print_progress();
+                    last_print = Instant::now();
+                }
+            }
+        });
 
-            let handle = thread::spawn(move || {
-                Self::render_slice(
-                    camera_data,
-                    world_clone,
-                    pixels_clone,
-                    start_row,
-                    end_row,
-                    task_id,
-                    progress_clone,
-                )
-            });
-
-            handles.push(handle);
-        }
+        progress.print_progress();
+        progress.print_final();
 
-        // Progress monitoring in the main thread
-        let progress_monitor = {
-            let tracker_clone = Arc::clone(&progress_tracker);
-            thread::spawn(move || {
-                loop {
-                    tracker_clone.print_progress();
-                    thread::sleep(Duration::from_millis(500));
-                }
-            })
-        };
+        Ok((framebuffer, sample_counts))
+    }
 
-        for handle in handles {
-            handle.join().unwrap()?;
+    /// Worker threads to render with, every available core when unset
+    fn thread_count(&self) -> usize {
+        if self.threads > 0 {
+            self.threads
+        } else {
+            num_cpus::get()
         }
+    }
 
-        progress_monitor.thread().unpark(); // Stop the progress monitor
-
-        progress_tracker.print_final();
+    fn sample_square(sampler: &mut dyn Sampler) -> Vector3 {
+        // Returns the vector to a random point in the [-.5,-.5]-[+.5,+.5] unit square
+        let (x, y) = sampler.get_2d();
+        Vector3::new(x - 0.5, y - 0.5, 0.0)
+    }
 
-        println!("\nWriting image to file...");
-        let file = File::create("image.ppm")?;
-        let mut writer = BufWriter::new(file);
-        writer
-            .write(format!("P3\n{} {}\n255\n", self.image_width, self.image_height).as_bytes())?;
+    /// Renders every pixel of `tile` into a buffer of its own, row by row,
+    /// along with the number of samples each pixel took
+    fn render_tile(
+        camera_data: &CameraData,
+        world: &dyn Hittable,
+        lights: &HittableList,
+        analytic_lights: &LightList,
+        tile: Tile,
+        progress: &ProgressTracker,
+    ) -> Vec<(Color, u32)> {
+        let mut buffer = Vec::with_capacity((tile.width * tile.height) as usize);
+        let mut sampler = camera_data
+            .sampler
+            .build(camera_data.samples_per_pixel as u32, camera_data.seed);
+
+        for j in tile.y..tile.y + tile.height {
+            let mut row_samples = 0;
+
+            for i in tile.x..tile.x + tile.width {
+                let mut stats = PixelStats::new();
+
+                for sample in 0..camera_data.samples_per_pixel {
+                    sampler.start_pixel_sample(i as u32, j as u32, sample as u32);
+                    let r = Self::get_ray_static(camera_data, i as f64, j as f64, sampler.as_mut());
+                    stats.add(Self::color_static(
+                        camera_data,
+                        r,
+                        world,
+                        lights,
+                        analytic_lights,
+                        sampler.as_mut(),
+                    ));
+
+                    if camera_data.adaptive_threshold > 0.0
+                        && stats.count >= camera_data.min_samples_per_pixel as u32
+                        && stats.relative_error() < camera_data.adaptive_threshold
+                    {
+                        break;
+                    }
+                }
 
-        let pixels_guard = pixels.lock().unwrap();
-        for j in 0..self.image_height {
-            for i in 0..self.image_width {
-                let pixel_index = (j * self.image_width + i) as usize;
-                pixels_guard[pixel_index].write_color(&mut writer)?;
+                row_samples += stats.count as usize;
+                buffer.push((stats.mean, stats.count));
             }
+
+            progress.add_pixels(tile.width, row_samples);
         }
 
-        println!("Done!");
-        Ok(())
+        buffer
     }
 
-    fn get_ray(&self, i: f64, j: f64) -> Ray {
-        let offset = Self::sample_square();
-        let pixel_sample = self.pixel00_loc
-            + ((i + offset.x()) * self.pixel_delta_u)
-            + ((j + offset.y()) * self.pixel_delta_v);
+    // Static helper methods for parallel rendering
+    fn get_ray_static(camera_data: &CameraData, i: f64, j: f64, sampler: &mut dyn Sampler) -> Ray {
+        let offset = Self::sample_square(sampler);
+        let pixel_sample = camera_data.pixel00_loc
+            + ((i + offset.x()) * camera_data.pixel_delta_u)
+            + ((j + offset.y()) * camera_data.pixel_delta_v);
 
-        let ray_origin = if self.defocus_angle <= 0. {
-            self.center
+        let ray_origin = if camera_data.defocus_angle <= 0.0 {
+            camera_data.center
         } else {
-            self.defocus_disk_sample()
+            Self::defocus_disk_sample_static(camera_data, sampler)
         };
         let ray_direction = pixel_sample - ray_origin;
 
-        Ray::new(ray_origin, ray_direction)
-    }
+        // A closed shutter takes no time sample, so still scenes consume the
+        // same dimensions whether or not motion blur exists
+        let ray_time = if camera_data.shutter_close > camera_data.shutter_open {
+            camera_data.shutter_open
+                + (camera_data.shutter_close - camera_data.shutter_open) * sampler.get_1d()
+        } else {
+            camera_data.shutter_open
+        };
 
-    fn sample_square() -> Vector3 {
-        // Returns the vector to a random point in the [-.5,-.5]-[+.5,+.5] unit square
-        Vector3::new(
-            random_range(0.0..1.0) - 0.5,
-            random_range(0.0..1.0) - 0.5,
-            0.0,
-        )
+        Ray::new_at_time(ray_origin, ray_direction, ray_time)
     }
 
-    fn defocus_disk_sample(&self) -> Point3 {
-        let p = Vector3::random_in_unit_disk();
-        self.center + p.x() * self.defocus_u + p.y() * self.defocus_v
+    fn defocus_disk_sample_static(camera_data: &CameraData, sampler: &mut dyn Sampler) -> Point3 {
+        let p = Vector3::random_in_unit_disk(sampler);
+        camera_data.center + p.x() * camera_data.defocus_disk_u + p.y() * camera_data.defocus_disk_v
     }
 
-    fn render_slice(
-        camera_data: CameraData,
-        world: Arc<HittableList>,
-        pixels: Arc<Mutex<Vec<Color>>>,
-        start_row: i32,
-        end_row: i32,
-        task_id: usize,
-        progress_tracker: Arc<ProgressTracker>,
-    ) -> Result<()> {
-        let mut pixels_completed = 0;
+    /// Radiance arriving along `ray`, following the path one bounce at a time
+    fn color_static(
+        camera_data: &CameraData,
+        mut ray: Ray,
+        world: &dyn Hittable,
+        lights: &HittableList,
+        analytic_lights: &LightList,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        // Density with which the previous diffuse bounce picked `ray`, used to
+        // weight emission it hits against the light sample taken there
+        let mut bsdf_pdf: Option<f64> = None;
+
+        // Once the ray bounce limit is reached, no more light is gathered
+        for depth in 0..camera_data.max_depth {
+            let mut rec = HitRecord::new();
+
+            if !world.hit(
+                &ray,
+                &mut Interval::new(0.001, f64::INFINITY),
+                &mut rec,
+                sampler,
+            ) {
+                let mut background = camera_data.background(&ray);
+                if let Some(bsdf_pdf) = bsdf_pdf {
+                    let light_pdf = lights.pdf_value(&ray.get_origin(), &ray.get_direction());
+                    background = power_heuristic(bsdf_pdf, light_pdf) * background;
+                }
+                radiance += throughput * background;
+                break;
+            }
 
-        for j in start_row..end_row {
-            for i in 0..camera_data.image_width {
-                let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+            let mut scattered = Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
+            let mut attenuation = Color::new(0.0, 0.0, 0.0);
+            let mut color_from_emission = rec.mat.emitted(rec.u, rec.v, &rec.p);
 
-                for _ in 0..camera_data.samples_per_pixel {
-                    let r = Self::get_ray_static(&camera_data, i as f64, j as f64);
-                    pixel_color +=
-                        Self::color_static(&camera_data, &r, &world, camera_data.max_depth);
-                }
+            if let Some(bsdf_pdf) = bsdf_pdf {
+                let light_pdf = lights.pdf_value(&ray.get_origin(), &ray.get_direction());
+                color_from_emission = power_heuristic(bsdf_pdf, light_pdf) * color_from_emission;
+            }
+            radiance += throughput * color_from_emission;
 
-                let final_color = camera_data.pixel_samples_scale * pixel_color;
-                let pixel_index = (j * camera_data.image_width + i) as usize;
+            if !rec
+                .mat
+                .scatter(&ray, &rec, &mut attenuation, &mut scattered, sampler)
+            {
+                break;
+            }
 
-                // Write to shared pixel buffer
-                {
-                    let mut pixels_guard = pixels.lock().unwrap();
-                    pixels_guard[pixel_index] = final_color;
-                }
+            let scattering_pdf = rec.mat.scattering_pdf(&ray, &rec, &scattered);
+            let sample_lights = scattering_pdf > 0.0 && !lights.objects.is_empty();
 
-                // Update progress after every 10 pixels to reduce overhead
-                pixels_completed += 1;
-                if pixels_completed % 10 == 0 {
-                    progress_tracker.update_progress(task_id, pixels_completed);
+            if sample_lights {
+                radiance += throughput
+                    * Self::sample_light(camera_data, &ray, &rec, world, lights, sampler);
+            }
+            if scattering_pdf > 0.0 && !analytic_lights.is_empty() {
+                radiance += throughput
+                    * Self::sample_analytic_lights(&ray, &rec, world, analytic_lights, sampler);
+            }
+
+            throughput = throughput * attenuation;
+            bsdf_pdf = sample_lights.then_some(scattering_pdf);
+            ray = scattered;
+
+            // Russian roulette: end dim paths early, and boost the survivors
+            // by the same factor so the estimate stays unbiased
+            if depth + 1 >= camera_data.roulette_depth {
+                let survival = throughput
+                    .x()
+                    .max(throughput.y())
+                    .max(throughput.z())
+                    .min(0.95);
+                if sampler.get_1d() >= survival {
+                    break;
                 }
+                throughput /= survival;
             }
         }
 
-        // Final progress update
-        progress_tracker.update_progress(task_id, pixels_completed);
-        Ok(())
+        radiance
     }
 
-    // Static helper methods for parallel rendering
-    fn get_ray_static(camera_data: &CameraData, i: f64, j: f64) -> Ray {
-        let offset = Self::sample_square();
-        let pixel_sample = camera_data.pixel00_loc
-            + ((i + offset.x()) * camera_data.pixel_delta_u)
-            + ((j + offset.y()) * camera_data.pixel_delta_v);
+    /// Next event estimation: traces a shadow ray towards a point picked on
+    /// one of the lights and weights what it reaches against the chance of
+    /// the material having scattered in that direction itself
+    fn sample_light(
+        camera_data: &CameraData,
+        r: &Ray,
+        rec: &HitRecord,
+        world: &dyn Hittable,
+        lights: &HittableList,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let light_pdf = HittablePdf::new(lights, rec.p);
+        let direction = light_pdf.generate(sampler);
+        let pdf = light_pdf.value(&direction);
+        if pdf <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
 
-        let ray_origin = if camera_data.defocus_angle <= 0.0 {
-            camera_data.center
+        let shadow_ray = Ray::new_at_time(rec.p, direction, r.get_time());
+        let f = rec.mat.eval(r, rec, &shadow_ray);
+        if f.near_zero() {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        // Whatever the shadow ray reaches first, an occluder emits nothing,
+        // and one that escapes sees the background
+        let mut light_rec = HitRecord::new();
+        let emitted = if world.hit(
+            &shadow_ray,
+            &mut Interval::new(0.001, f64::INFINITY),
+            &mut light_rec,
+            sampler,
+        ) {
+            light_rec
+                .mat
+                .emitted(light_rec.u, light_rec.v, &light_rec.p)
         } else {
-            Self::defocus_disk_sample_static(camera_data)
+            camera_data.background(&shadow_ray)
         };
-        let ray_direction = pixel_sample - ray_origin;
+        let weight = power_heuristic(pdf, rec.mat.scattering_pdf(r, rec, &shadow_ray));
 
-        Ray::new(ray_origin, ray_direction)
+        weight / pdf * f * emitted
     }
 
-    fn defocus_disk_sample_static(camera_data: &CameraData) -> Point3 {
-        let p = Vector3::random_in_unit_disk();
-        camera_data.center + p.x() * camera_data.defocus_disk_u + p.y() * camera_data.defocus_disk_v
+    /// Light reaching a hit from each analytic light, dimmed by whatever lies
+    /// in between. Scattered rays never find these lights, so there is
+    /// nothing to weight against.
+    fn sample_analytic_lights(
+        r: &Ray,
+        rec: &HitRecord,
+        world: &dyn Hittable,
+        analytic_lights: &LightList,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+
+        for light in &analytic_lights.lights {
+            let Some(sample) = light.sample(&rec.p, sampler) else {
+                continue;
+            };
+            let shadow_ray = Ray::new_at_time(rec.p, sample.direction, r.get_time());
+            let f = rec.mat.eval(r, rec, &shadow_ray);
+            if f.near_zero() {
+                continue;
+            }
+
+            let transmittance = world.transmittance(
+                &shadow_ray,
+                &Interval::new(0.001, sample.distance - 0.001),
+                sampler,
+            );
+            radiance += transmittance * f * sample.radiance;
+        }
+
+        radiance
     }
+}
 
-    fn color_static(camera_data: &CameraData, r: &Ray, world: &HittableList, depth: i32) -> Color {
-        // If we've exceeded the ray bounce limit, no more light is gathered
-        if depth <= 0 {
-            return Color::new(0.0, 0.0, 0.0);
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rectangle of pixels rendered as one unit of work
+#[derive(Clone, Copy)]
+struct Tile {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Tile {
+    fn copy_into(
+        &self,
+        framebuffer: &mut Framebuffer,
+        sample_counts: &mut [u32],
+        buffer: &[(Color, u32)],
+    ) {
+        for (index, &(color, count)) in buffer.iter().enumerate() {
+            let x = (self.x + index as i32 % self.width) as u32;
+            let y = (self.y + index as i32 / self.width) as u32;
+            framebuffer.set(x, y, color);
+            sample_counts[(y * framebuffer.width() + x) as usize] = count;
         }
+    }
+}
 
-        let mut rec = HitRecord::new();
+/// Running mean of the samples of a pixel, and the variance of their
+/// luminance (Welford's algorithm)
+struct PixelStats {
+    count: u32,
+    mean: Color,
+    luminance_mean: f64,
+    luminance_m2: f64,
+}
 
-        if !world.hit(r, Interval::new(0.001, f64::INFINITY), &mut rec) {
-            return camera_data.background;
+impl PixelStats {
+    fn new() -> Self {
+        Self {
+            count: 0,
+            mean: Color::new(0.0, 0.0, 0.0),
+            luminance_mean: 0.0,
+            luminance_m2: 0.0,
         }
+    }
 
-        let mut scattered = Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
-        let mut attenuation = Color::new(0.0, 0.0, 0.0);
-        let color_from_emission = rec.mat.emitted(rec.u, rec.v, &rec.p);
+    fn add(&mut self, sample: Color) {
+        self.count += 1;
+        let n = self.count as f64;
+        self.mean += (sample - self.mean) / n;
+
+        let luminance = luminance(&sample);
+        let delta = luminance - self.luminance_mean;
+        self.luminance_mean += delta / n;
+        self.luminance_m2 += delta * (luminance - self.luminance_mean);
+    }
 
-        if !rec.mat.scatter(r, &rec, &mut attenuation, &mut scattered) {
-            return color_from_emission;
+    /// Standard error of the mean luminance relative to the luminance. Dark
+    /// pixels are measured against a floor so they aren't held to an
+    /// absolute error nobody could see.
+    fn relative_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
         }
 
-        let color_from_scatter =
-            attenuation * Self::color_static(camera_data, &scattered, world, depth - 1);
+        let n = self.count as f64;
+        let variance = self.luminance_m2 / (n - 1.0);
+        (variance / n).sqrt() / self.luminance_mean.max(0.01)
+    }
+}
+
+/// Tiles covering the image, handed out to whichever worker asks next
+struct TileQueue {
+    tiles: Vec<Tile>,
+    next: AtomicUsize,
+}
+
+impl TileQueue {
+    fn new(image_width: i32, image_height: i32, tile_size: i32) -> Self {
+        let tile_size = tile_size.max(1);
+        let mut tiles = Vec::new();
+
+        for y in (0..image_height).step_by(tile_size as usize) {
+            for x in (0..image_width).step_by(tile_size as usize) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: tile_size.min(image_width - x),
+                    height: tile_size.min(image_height - y),
+                });
+            }
+        }
+
+        Self {
+            tiles,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    fn next(&self) -> Option<Tile> {
+        self.tiles
+            .get(self.next.fetch_add(1, Ordering::Relaxed))
+            .copied()
+    }
 
-        color_from_emission + color_from_scatter
+    /// Number of tiles handed out so far
+    fn started(&self) -> usize {
+        self.next.load(Ordering::Relaxed).min(self.tiles.len())
     }
 }
 
 // Progress tracking structure for parallel rendering
-#[derive(Clone)]
-struct ProgressTracker {
-    task_progress: Arc<Vec<AtomicUsize>>,
-    total_pixels_per_task: Vec<usize>,
-    task_count: usize,
+struct ProgressTracker<'a> {
+    tiles: &'a TileQueue,
+    tiles_done: AtomicUsize,
+    pixels_done: AtomicUsize,
+    samples_done: AtomicUsize,
+    total_pixels: usize,
     start_time: Instant,
 }
 
-impl ProgressTracker {
-    fn new(task_count: usize, rows_per_task: Vec<i32>, image_width: i32) -> Self {
-        let task_progress = Arc::new(
-            (0..task_count)
-                .map(|_| AtomicUsize::new(0))
-                .collect::<Vec<_>>(),
-        );
-        let total_pixels_per_task = rows_per_task
-            .iter()
-            .map(|&rows| (rows * image_width) as usize)
-            .collect();
-
+impl<'a> ProgressTracker<'a> {
+    fn new(tiles: &'a TileQueue, total_pixels: i32) -> Self {
         Self {
-            task_progress,
-            total_pixels_per_task,
-            task_count,
+            tiles,
+            tiles_done: AtomicUsize::new(0),
+            pixels_done: AtomicUsize::new(0),
+            samples_done: AtomicUsize::new(0),
+            total_pixels: total_pixels as usize,
             start_time: Instant::now(),
         }
     }
 
-    fn update_progress(&self, task_id: usize, pixels_completed: usize) {
-        self.task_progress[task_id].store(pixels_completed, Ordering::Relaxed);
+    fn add_pixels(&self, pixels: i32, samples: usize) {
+        self.pixels_done
+            .fetch_add(pixels as usize, Ordering::Relaxed);
+        self.samples_done.fetch_add(samples, Ordering::Relaxed);
+    }
+
+    fn finish_tile(&self) {
+        self.tiles_done.fetch_add(1, Ordering::Relaxed);
     }
 
     fn print_progress(&self) {
         let elapsed = self.start_time.elapsed();
-        let mut total_completed = 0;
-        let mut total_pixels = 0;
-
-        print!("\r");
-
-        for (task_id, progress) in self.task_progress.iter().enumerate() {
-            let completed = progress.load(Ordering::Relaxed);
-            let total = self.total_pixels_per_task[task_id];
-            let percentage = if total > 0 {
-                (completed * 100) / total
-            } else {
-                0
-            };
+        let tiles_done = self.tiles_done.load(Ordering::Relaxed);
+        let in_progress = self.tiles.started() - tiles_done.min(self.tiles.started());
+        let percentage = (self.pixels_done.load(Ordering::Relaxed) * 100)
+            .checked_div(self.total_pixels)
+            .unwrap_or(0);
 
-            total_completed += completed;
-            total_pixels += total;
-
-            if task_id > 0 {
-                print!(" | ");
-            }
-            print!("T{}: {:3}%", task_id + 1, percentage);
-        }
-
-        let overall_percentage = if total_pixels > 0 {
-            (total_completed * 100) / total_pixels
-        } else {
-            0
-        };
         print!(
-            " | Overall: {:3}% | {:02}:{:02}",
-            overall_percentage,
+            "\rTiles: {}/{} ({} in progress) | Overall: {:3}% | {:02}:{:02}",
+            tiles_done,
+            self.tiles.len(),
+            in_progress,
+            percentage,
             elapsed.as_secs() / 60,
             elapsed.as_secs() % 60
         );
@@ -469,16 +817,15 @@ impl ProgressTracker {
 
     fn print_final(&self) {
         let total_time = self.start_time.elapsed();
-        let total_pixels: usize = self.total_pixels_per_task.iter().sum();
-        let total_completed: usize = self
-            .task_progress
-            .iter()
-            .map(|p| p.load(Ordering::Relaxed))
-            .sum();
 
         println!("\nRendering complete!");
-        println!("Total pixels: {}", total_pixels);
-        println!("Pixels rendered: {}", total_completed);
+        println!("Total pixels: {}", self.total_pixels);
+        let pixels_done = self.pixels_done.load(Ordering::Relaxed);
+        println!("Pixels rendered: {}", pixels_done);
+        println!(
+            "Average samples per pixel: {:.1}",
+            self.samples_done.load(Ordering::Relaxed) as f64 / pixels_done.max(1) as f64
+        );
         println!(
             "Elapsed time: {:02}:{:02}",
             total_time.as_secs() / 60,
@@ -0,0 +1,119 @@
+//! Piecewise-constant distributions, for sampling tabulated functions such as
+//! the brightness of an image in proportion to their value.
+
+/// Distribution over [0, 1) made of equally wide steps
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    /// Builds the distribution of the non-negative step heights `func`. When
+    /// they are all zero every step is equally likely.
+    pub fn new(func: Vec<f64>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i].max(0.0) / n as f64;
+        }
+
+        let integral = cdf[n];
+        for (i, value) in cdf.iter_mut().enumerate() {
+            *value = if integral > 0.0 {
+                *value / integral
+            } else {
+                i as f64 / n as f64
+            };
+        }
+
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    /// Mean step height, the integral of the function over [0, 1)
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps `u` to a point, returning it with its density and step index
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        let n = self.len();
+        let index = (self.cdf.partition_point(|&c| c <= u).max(1) - 1).min(n - 1);
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            (u - self.cdf[index]) / width
+        } else {
+            0.0
+        };
+        let x = ((index as f64 + offset) / n as f64).min(1.0 - f64::EPSILON);
+        (x, self.step_pdf(index), index)
+    }
+
+    /// Density of `sample` returning `x`
+    pub fn pdf(&self, x: f64) -> f64 {
+        let n = self.len();
+        self.step_pdf(((x * n as f64) as usize).min(n - 1))
+    }
+
+    fn step_pdf(&self, index: usize) -> f64 {
+        if self.integral > 0.0 {
+            self.func[index].max(0.0) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Distribution over the unit square from a grid of values, sampled by
+/// picking a row from the marginal distribution and a column within it
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// Builds the distribution of `height` rows of `width` values each,
+    /// stored row after row
+    pub fn new(func: &[f64], width: usize, height: usize) -> Self {
+        assert_eq!(func.len(), width * height, "grid is not {width}x{height}");
+
+        let rows: Vec<_> = func
+            .chunks(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(|row| row.integral()).collect());
+        Self { rows, marginal }
+    }
+
+    /// Mean value of the grid, its integral over the unit square
+    pub fn integral(&self) -> f64 {
+        self.marginal.integral()
+    }
+
+    /// Maps `(u1, u2)` to a point `(x, y)`, x across a row and y down the
+    /// rows, returning it with its density
+    pub fn sample(&self, (u1, u2): (f64, f64)) -> ((f64, f64), f64) {
+        let (y, row_pdf, row) = self.marginal.sample(u2);
+        let (x, column_pdf, _) = self.rows[row].sample(u1);
+        ((x, y), row_pdf * column_pdf)
+    }
+
+    /// Density of `sample` returning `(x, y)`
+    pub fn pdf(&self, x: f64, y: f64) -> f64 {
+        let row = ((y * self.rows.len() as f64) as usize).min(self.rows.len() - 1);
+        self.marginal.pdf(y) * self.rows[row].pdf(x)
+    }
+}
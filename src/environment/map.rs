@@ -0,0 +1,148 @@
+use crate::color::{Color, luminance};
+use crate::distribution::Distribution2D;
+use crate::environment::Environment;
+use crate::sampler::Sampler;
+use crate::tonemap::srgb_decode;
+use crate::vector::Vector3;
+use anyhow::{Context, Result, bail};
+use image::DynamicImage;
+use std::f64::consts::PI;
+use std::path::Path;
+
+/// Environment from an equirectangular image, the top row looking straight up
+/// and the middle of the image looking along +x. Directions are sampled in
+/// proportion to the brightness of the pixel they see.
+pub struct EnvironmentMap {
+    pub rotation: f64,  // Turn about the y axis, in degrees
+    pub intensity: f64, // Scale applied to every pixel
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentMap {
+    /// Map over `width` by `height` linear radiance values, row after row
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width * height, "map is not {width}x{height}");
+
+        // Rows near the poles cover less of the sphere than those near the
+        // horizon, so their pixels are picked less often
+        let weights: Vec<f64> = pixels
+            .iter()
+            .enumerate()
+            .map(|(i, pixel)| {
+                let theta = PI * ((i / width) as f64 + 0.5) / height as f64;
+                luminance(pixel) * theta.sin()
+            })
+            .collect();
+
+        Self {
+            rotation: 0.0,
+            intensity: 1.0,
+            width,
+            height,
+            distribution: Distribution2D::new(&weights, width, height),
+            pixels,
+        }
+    }
+
+    /// Map tabulating `radiance` at the middle of every pixel
+    pub fn from_fn(width: usize, height: usize, radiance: impl Fn(&Vector3) -> Color) -> Self {
+        let pixels = (0..width * height)
+            .map(|i| {
+                let x = ((i % width) as f64 + 0.5) / width as f64;
+                let y = ((i / width) as f64 + 0.5) / height as f64;
+                radiance(&unrotated_direction_at(x, y))
+            })
+            .collect();
+        Self::new(width, height, pixels)
+    }
+
+    /// Loads a map from an image file. HDR and EXR images hold radiance as
+    /// it is, other formats are taken to be sRGB encoded.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let image = image::open(path)
+            .with_context(|| format!("could not read environment map '{}'", path.display()))?;
+        let is_linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+        let image = image.to_rgb32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        if width == 0 || height == 0 {
+            bail!("environment map '{}' is empty", path.display());
+        }
+
+        let decode = |value: f32| {
+            let value = value as f64;
+            if is_linear {
+                value.max(0.0)
+            } else {
+                srgb_decode(value)
+            }
+        };
+        let pixels = image
+            .pixels()
+            .map(|pixel| Color::new(decode(pixel[0]), decode(pixel[1]), decode(pixel[2])))
+            .collect();
+        Ok(Self::new(width, height, pixels))
+    }
+
+    /// Luminance arriving from the whole sphere, ignoring `intensity`
+    pub fn power(&self) -> f64 {
+        // The image spans 2 pi by pi radians
+        2.0 * PI * PI * self.distribution.integral()
+    }
+
+    /// Position in the image, across and down, that `direction` sees
+    fn image_position(&self, direction: &Vector3) -> (f64, f64) {
+        let d = rotate_y(&direction.unit_vector(), -self.rotation);
+        let theta = d.y().clamp(-1.0, 1.0).acos();
+        let phi = (-d.z()).atan2(d.x()) + PI;
+        ((phi / (2.0 * PI)).clamp(0.0, 1.0), theta / PI)
+    }
+
+    /// Direction seeing a position in the image, the inverse of `image_position`
+    fn direction_at(&self, x: f64, y: f64) -> Vector3 {
+        rotate_y(&unrotated_direction_at(x, y), self.rotation)
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: &Vector3) -> Color {
+        let (x, y) = self.image_position(direction);
+        let column = ((x * self.width as f64) as usize).min(self.width - 1);
+        let row = ((y * self.height as f64) as usize).min(self.height - 1);
+        self.intensity * self.pixels[row * self.width + column]
+    }
+
+    fn pdf(&self, direction: &Vector3) -> f64 {
+        let (x, y) = self.image_position(direction);
+        let sin_theta = (PI * y).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        // The image spans 2 pi by pi radians
+        self.distribution.pdf(x, y) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Vector3 {
+        let ((x, y), _) = self.distribution.sample(sampler.get_2d());
+        self.direction_at(x, y)
+    }
+}
+
+/// Direction seeing a position in an image that has not been turned
+fn unrotated_direction_at(x: f64, y: f64) -> Vector3 {
+    let (sin_theta, cos_theta) = (PI * y).sin_cos();
+    let (sin_phi, cos_phi) = (2.0 * PI * x).sin_cos();
+    Vector3::new(-sin_theta * cos_phi, cos_theta, sin_theta * sin_phi)
+}
+
+/// Turns `d` about the y axis by `degrees`
+fn rotate_y(d: &Vector3, degrees: f64) -> Vector3 {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    Vector3::new(cos * d.x() + sin * d.z(), d.y(), -sin * d.x() + cos * d.z())
+}
@@ -0,0 +1,68 @@
+//! Light arriving from infinitely far away, seen by rays that leave the
+//! scene without hitting anything.
+
+pub mod map;
+pub mod sky;
+
+use crate::aabb::AABB;
+use crate::color::Color;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+/// Radiance surrounding the scene, importance sampled so that it can be
+/// treated as a light
+pub trait Environment: Send + Sync {
+    /// Radiance arriving from `direction`, which need not be unit length
+    fn radiance(&self, direction: &Vector3) -> Color;
+
+    /// Density, in solid angle, of `sample` returning `direction`
+    fn pdf(&self, direction: &Vector3) -> f64;
+
+    /// Unit direction picked roughly in proportion to the radiance from it
+    fn sample(&self, sampler: &mut dyn Sampler) -> Vector3;
+}
+
+/// An environment in a light list. It is never hit, so it only takes part
+/// through `pdf_value` and `random`, and the camera adds its radiance to
+/// shadow rays that escape.
+pub struct EnvironmentLight {
+    environment: Arc<dyn Environment>,
+    bbox: AABB,
+}
+
+impl EnvironmentLight {
+    pub fn new(environment: Arc<dyn Environment>) -> Self {
+        Self {
+            environment,
+            bbox: AABB::new_empty(),
+        }
+    }
+}
+
+impl Hittable for EnvironmentLight {
+    fn hit(
+        &self,
+        _ray: &Ray,
+        _t: &mut Interval,
+        _rec: &mut HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
+        false
+    }
+
+    fn bbox(&self) -> &AABB {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, _origin: &Point3, direction: &Vector3) -> f64 {
+        self.environment.pdf(direction)
+    }
+
+    fn random(&self, _origin: &Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        self.environment.sample(sampler)
+    }
+}
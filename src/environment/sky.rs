@@ -0,0 +1,251 @@
+use crate::color::{Color, luminance};
+use crate::environment::Environment;
+use crate::environment::map::EnvironmentMap;
+use crate::onb::Onb;
+use crate::sampler::Sampler;
+use crate::vector::Vector3;
+use std::f64::consts::PI;
+
+/// Turns luminance in kilocandela per square metre into the renderer's
+/// units, so that a sun overhead gives an irradiance of about 1
+const LUMINANCE_SCALE: f64 = 0.01;
+
+/// Luminance of the sun above the atmosphere, in kilocandela per square metre
+const SUN_LUMINANCE: f64 = 2.0e6;
+
+/// Angular radius of the sun as seen from the earth, in degrees
+const SUN_RADIUS: f64 = 0.2665;
+
+/// Wavelengths standing in for the red, green and blue channels, in
+/// micrometres
+const WAVELENGTHS: [f64; 3] = [0.68, 0.55, 0.44];
+
+/// Resolution of the table the sky is sampled from
+const TABLE_WIDTH: usize = 128;
+const TABLE_HEIGHT: usize = 64;
+
+/// Clear daylight sky after Preetham, Shirley and Smits, "A Practical
+/// Analytic Model for Daylight", with the sun as a small bright disk in it.
+/// Turbidity measures the haze, from 2 for a clear sky to 10 for a hazy one.
+/// Below the horizon it is black, leaving the ground to the scene.
+pub struct Sky {
+    pub intensity: f64,   // Scale applied to the sky and the sun
+    sun: Onb,             // Frame around the direction of the sun
+    cos_sun_radius: f64,  // Cosine of the angular radius of the sun
+    sun_radiance: Color,  // Sunlight left after crossing the atmosphere
+    sky: [Perez; 3],      // Luminance and chromaticity, in that order
+    sun_probability: f64, // Chance of sampling the sun rather than the sky
+    table: EnvironmentMap,
+}
+
+impl Sky {
+    /// Sky with the sun `elevation` degrees above the horizon and turned
+    /// `azimuth` degrees from +x towards +z
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let elevation = elevation.clamp(0.0, 90.0).to_radians();
+        let azimuth = azimuth.to_radians();
+        let sun_direction = Vector3::new(
+            elevation.cos() * azimuth.cos(),
+            elevation.sin(),
+            elevation.cos() * azimuth.sin(),
+        );
+        let theta_sun = PI / 2.0 - elevation;
+        let t = turbidity;
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let zenith_x = zenith_chromaticity(
+            t,
+            theta_sun,
+            [
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ],
+        );
+        let zenith_y = zenith_chromaticity(
+            t,
+            theta_sun,
+            [
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ],
+        );
+
+        let sky = [
+            Perez::new(
+                [
+                    0.1787 * t - 1.4630,
+                    -0.3554 * t + 0.4275,
+                    -0.0227 * t + 5.3251,
+                    0.1206 * t - 2.5771,
+                    -0.0670 * t + 0.3703,
+                ],
+                zenith_luminance,
+                theta_sun,
+            ),
+            Perez::new(
+                [
+                    -0.0193 * t - 0.2592,
+                    -0.0665 * t + 0.0008,
+                    -0.0004 * t + 0.2125,
+                    -0.0641 * t - 0.8989,
+                    -0.0033 * t + 0.0452,
+                ],
+                zenith_x,
+                theta_sun,
+            ),
+            Perez::new(
+                [
+                    -0.0167 * t - 0.2608,
+                    -0.0950 * t + 0.0092,
+                    -0.0079 * t + 0.2102,
+                    -0.0441 * t - 1.6537,
+                    -0.0109 * t + 0.0529,
+                ],
+                zenith_y,
+                theta_sun,
+            ),
+        ];
+
+        // Rayleigh scattering and haze dim and redden the sun as it sinks
+        // through more air
+        let theta_degrees = theta_sun.to_degrees();
+        let air_mass = 1.0 / (theta_sun.cos() + 0.15 * (93.885 - theta_degrees).powf(-1.253));
+        let beta = 0.04608 * t - 0.04586;
+        let [r, g, b] = WAVELENGTHS.map(|lambda: f64| {
+            let rayleigh = (-0.008735 * air_mass * lambda.powf(-4.08)).exp();
+            let aerosol = (-beta * air_mass * lambda.powf(-1.3)).exp();
+            rayleigh * aerosol
+        });
+        let sun_radiance = SUN_LUMINANCE * LUMINANCE_SCALE * Color::new(r, g, b);
+
+        let table = EnvironmentMap::from_fn(TABLE_WIDTH, TABLE_HEIGHT, |direction| {
+            sky_radiance(&sky, &sun_direction, direction)
+        });
+
+        // Split samples between the sun and the sky by the light each sends
+        let cos_sun_radius = SUN_RADIUS.to_radians().cos();
+        let sun_power = luminance(&sun_radiance) * 2.0 * PI * (1.0 - cos_sun_radius);
+        let sky_power = table.power();
+        let sun_probability = if sun_power + sky_power > 0.0 {
+            sun_power / (sun_power + sky_power)
+        } else {
+            0.5
+        };
+
+        Self {
+            intensity: 1.0,
+            sun: Onb::new(&sun_direction),
+            cos_sun_radius,
+            sun_radiance,
+            sky,
+            sun_probability,
+            table,
+        }
+    }
+
+    /// Unit vector towards the middle of the sun
+    pub fn sun_direction(&self) -> Vector3 {
+        self.sun.w()
+    }
+
+    fn sun_pdf(&self, direction: &Vector3) -> f64 {
+        if Vector3::dot(&direction.unit_vector(), &self.sun.w()) < self.cos_sun_radius {
+            return 0.0;
+        }
+        1.0 / (2.0 * PI * (1.0 - self.cos_sun_radius))
+    }
+}
+
+impl Environment for Sky {
+    fn radiance(&self, direction: &Vector3) -> Color {
+        let mut radiance = sky_radiance(&self.sky, &self.sun.w(), direction);
+        if direction.y() > 0.0 && self.sun_pdf(direction) > 0.0 {
+            radiance += self.sun_radiance;
+        }
+        self.intensity * radiance
+    }
+
+    fn pdf(&self, direction: &Vector3) -> f64 {
+        self.sun_probability * self.sun_pdf(direction)
+            + (1.0 - self.sun_probability) * self.table.pdf(direction)
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Vector3 {
+        if sampler.get_1d() < self.sun_probability {
+            self.sun
+                .transform(&Vector3::random_in_cone(sampler, self.cos_sun_radius))
+        } else {
+            self.table.sample(sampler)
+        }
+    }
+}
+
+/// Perez's formula for the distribution of a quantity over the sky,
+/// normalised to a given value at the zenith
+struct Perez {
+    coefficients: [f64; 5], // A to E of the formula
+    scale: f64,             // Value at the zenith over the formula's value there
+}
+
+impl Perez {
+    fn new(coefficients: [f64; 5], zenith: f64, theta_sun: f64) -> Self {
+        let mut perez = Self {
+            coefficients,
+            scale: 1.0,
+        };
+        perez.scale = zenith / perez.formula(1.0, theta_sun.cos());
+        perez
+    }
+
+    /// Value seen `cos_theta` from the zenith and `cos_gamma` from the sun
+    fn value(&self, cos_theta: f64, cos_gamma: f64) -> f64 {
+        self.scale * self.formula(cos_theta, cos_gamma)
+    }
+
+    fn formula(&self, cos_theta: f64, cos_gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.coefficients;
+        let gamma = cos_gamma.acos();
+        (1.0 + a * (b / cos_theta.max(0.01)).exp())
+            * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+    }
+}
+
+/// Radiance of the sky alone, without the sun's disk
+fn sky_radiance(sky: &[Perez; 3], sun: &Vector3, direction: &Vector3) -> Color {
+    let d = direction.unit_vector();
+    if d.y() <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let cos_gamma = Vector3::dot(&d, sun).clamp(-1.0, 1.0);
+    let [luminance, x, y] = sky.each_ref().map(|perez| perez.value(d.y(), cos_gamma));
+    xyy_to_rgb(x, y, luminance * LUMINANCE_SCALE)
+}
+
+/// Chromaticity at the zenith, a cubic in the sun's zenith angle for each of
+/// the turbidity squared, the turbidity and 1
+fn zenith_chromaticity(turbidity: f64, theta_sun: f64, rows: [[f64; 4]; 3]) -> f64 {
+    let thetas = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+    let turbidities = [turbidity * turbidity, turbidity, 1.0];
+    rows.iter()
+        .zip(turbidities)
+        .map(|(row, t)| t * row.iter().zip(thetas).map(|(k, th)| k * th).sum::<f64>())
+        .sum()
+}
+
+/// Linear sRGB of a colour given by its chromaticity and luminance
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Color {
+    if y <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Color::new(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
@@ -0,0 +1,366 @@
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::interval::Interval;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+/// Number of buckets the centroids are sorted into when searching for a split
+const BIN_COUNT: usize = 16;
+/// Largest number of primitives a leaf may hold when splitting would be cheaper
+const MAX_LEAF_SIZE: usize = 4;
+/// Cost of visiting an interior node relative to intersecting one primitive
+const TRAVERSAL_COST: f64 = 0.125;
+/// Deepest tree the traversal stack can hold
+const MAX_DEPTH: usize = 64;
+
+/// One node of the flattened tree. Interior nodes store their first child
+/// right after themselves and the index of the second child in `offset`,
+/// leaves store the range of primitives they own.
+struct FlatNode {
+    bbox: AABB,
+    offset: usize,
+    count: usize,
+    axis: usize,
+}
+
+struct Primitive {
+    index: usize,
+    bbox: AABB,
+    centroid: Point3,
+}
+
+#[derive(Clone, Copy)]
+struct Bin {
+    bbox: AABB,
+    count: usize,
+}
+
+/// Bounding volume hierarchy built with the binned surface area heuristic and
+/// stored as a flat array of nodes in depth-first order.
+pub struct BVH {
+    nodes: Vec<FlatNode>,
+    objects: Vec<Arc<dyn Hittable>>,
+    bbox: AABB,
+}
+
+impl BVH {
+    pub fn new(list: &HittableList) -> BVH {
+        Self::new_from_objects(list.objects.clone())
+    }
+
+    pub fn new_from_objects(objects: Vec<Arc<dyn Hittable>>) -> BVH {
+        let mut primitives: Vec<Primitive> = objects
+            .iter()
+            .enumerate()
+            .map(|(index, object)| {
+                let bbox = *object.bbox();
+                Primitive {
+                    index,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .collect();
+
+        let mut nodes = Vec::with_capacity(2 * objects.len());
+        if !primitives.is_empty() {
+            Self::build(&mut nodes, &mut primitives, 0, MAX_DEPTH - 1);
+        }
+
+        // Leaves refer to contiguous ranges, so store the objects in build order
+        let objects = primitives
+            .iter()
+            .map(|primitive| Arc::clone(&objects[primitive.index]))
+            .collect();
+
+        let bbox = nodes
+            .first()
+            .map_or(AABB::new_empty(), |node: &FlatNode| node.bbox);
+
+        Self {
+            nodes,
+            objects,
+            bbox,
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Builds the subtree over `primitives`, whose first element sits at
+    /// `offset` in the final object order, and returns the index of its root
+    fn build(
+        nodes: &mut Vec<FlatNode>,
+        primitives: &mut [Primitive],
+        offset: usize,
+        depth_left: usize,
+    ) -> usize {
+        let bbox = primitives
+            .iter()
+            .fold(AABB::new_empty(), |b, p| AABB::new_from_aabbs(&b, &p.bbox));
+
+        let node_index = nodes.len();
+        nodes.push(FlatNode {
+            bbox,
+            offset,
+            count: primitives.len(),
+            axis: 0,
+        });
+
+        if primitives.len() == 1 || depth_left == 0 {
+            return node_index;
+        }
+
+        let centroid_bounds = primitives.iter().fold(AABB::new_empty(), |b, p| {
+            AABB::new_from_aabbs(&b, &AABB::new_points(p.centroid, p.centroid))
+        });
+        let axis = centroid_bounds.longest_axis() as usize;
+        let axis_min = centroid_bounds.min()[axis];
+        let extent = centroid_bounds.max()[axis] - axis_min;
+
+        let mid = if extent > 0.0 {
+            match Self::find_sah_split(&bbox, primitives, axis, axis_min, extent) {
+                Some(split_bin) => Self::partition(primitives, |p| {
+                    Self::bin_index(p.centroid[axis], axis_min, extent) < split_bin
+                }),
+                None if primitives.len() <= MAX_LEAF_SIZE => return node_index,
+                // Splitting costs more than it saves but the leaf would be too
+                // large, fall back to halving the primitives along the axis
+                None => Self::split_middle(primitives, axis),
+            }
+        } else if primitives.len() <= MAX_LEAF_SIZE {
+            return node_index;
+        } else {
+            // All centroids coincide, any split is as good as another
+            primitives.len() / 2
+        };
+
+        let (left, right) = primitives.split_at_mut(mid);
+        Self::build(nodes, left, offset, depth_left - 1);
+        let second_child = Self::build(nodes, right, offset + mid, depth_left - 1);
+
+        let node = &mut nodes[node_index];
+        node.offset = second_child;
+        node.count = 0;
+        node.axis = axis;
+
+        node_index
+    }
+
+    /// Returns the first bin of the right-hand side of the cheapest split, or
+    /// `None` when keeping the primitives in one leaf is cheaper
+    fn find_sah_split(
+        bbox: &AABB,
+        primitives: &[Primitive],
+        axis: usize,
+        axis_min: f64,
+        extent: f64,
+    ) -> Option<usize> {
+        let mut bins = [Bin {
+            bbox: AABB::new_empty(),
+            count: 0,
+        }; BIN_COUNT];
+
+        for primitive in primitives {
+            let bin = &mut bins[Self::bin_index(primitive.centroid[axis], axis_min, extent)];
+            bin.bbox = AABB::new_from_aabbs(&bin.bbox, &primitive.bbox);
+            bin.count += 1;
+        }
+
+        // Sweep from the right to get the area and count of every suffix
+        let mut right_area = [0.0; BIN_COUNT];
+        let mut right_count = [0; BIN_COUNT];
+        let mut accumulated = Bin {
+            bbox: AABB::new_empty(),
+            count: 0,
+        };
+        for i in (1..BIN_COUNT).rev() {
+            accumulated.bbox = AABB::new_from_aabbs(&accumulated.bbox, &bins[i].bbox);
+            accumulated.count += bins[i].count;
+            right_area[i] = accumulated.bbox.surface_area();
+            right_count[i] = accumulated.count;
+        }
+
+        let mut best: Option<(usize, f64)> = None;
+        let mut left = Bin {
+            bbox: AABB::new_empty(),
+            count: 0,
+        };
+        for split in 1..BIN_COUNT {
+            left.bbox = AABB::new_from_aabbs(&left.bbox, &bins[split - 1].bbox);
+            left.count += bins[split - 1].count;
+
+            if left.count == 0 || right_count[split] == 0 {
+                continue;
+            }
+
+            let cost = left.count as f64 * left.bbox.surface_area()
+                + right_count[split] as f64 * right_area[split];
+            if best.is_none_or(|(_, best_cost)| cost < best_cost) {
+                best = Some((split, cost));
+            }
+        }
+
+        let (split, cost) = best?;
+        let split_cost = TRAVERSAL_COST + cost / bbox.surface_area();
+        let leaf_cost = primitives.len() as f64;
+
+        if primitives.len() > MAX_LEAF_SIZE || split_cost < leaf_cost {
+            Some(split)
+        } else {
+            None
+        }
+    }
+
+    fn bin_index(centroid: f64, axis_min: f64, extent: f64) -> usize {
+        let bin = ((centroid - axis_min) / extent * BIN_COUNT as f64) as usize;
+        bin.min(BIN_COUNT - 1)
+    }
+
+    fn split_middle(primitives: &mut [Primitive], axis: usize) -> usize {
+        let mid = primitives.len() / 2;
+        primitives
+            .select_nth_unstable_by(mid, |a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+        mid
+    }
+
+    /// Moves the primitives matching `predicate` to the front and returns how many there are
+    fn partition(primitives: &mut [Primitive], predicate: impl Fn(&Primitive) -> bool) -> usize {
+        let mut first_right = 0;
+        for i in 0..primitives.len() {
+            if predicate(&primitives[i]) {
+                primitives.swap(i, first_right);
+                first_right += 1;
+            }
+        }
+        first_right
+    }
+}
+
+impl Hittable for BVH {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let origin = ray.get_origin();
+        let direction = ray.get_direction();
+        let inv_direction = Vector3::new(
+            1.0 / direction.x(),
+            1.0 / direction.y(),
+            1.0 / direction.z(),
+        );
+
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_size = 0;
+        let mut node_index = 0;
+
+        let mut closest_so_far = t.max;
+        let mut hit_anything = false;
+        let mut temp_rec = HitRecord::new();
+
+        loop {
+            let node = &self.nodes[node_index];
+
+            if node.bbox.hit_inverse(
+                &origin,
+                &inv_direction,
+                Interval::new(t.min, closest_so_far),
+            ) {
+                if node.count > 0 {
+                    for object in &self.objects[node.offset..node.offset + node.count] {
+                        let mut object_t = Interval::new(t.min, closest_so_far);
+                        if object.hit(ray, &mut object_t, &mut temp_rec, sampler) {
+                            hit_anything = true;
+                            closest_so_far = temp_rec.t;
+                            std::mem::swap(rec, &mut temp_rec);
+                        }
+                    }
+                } else {
+                    // Visit the child on the ray's side of the split first so
+                    // that later boxes can be culled by the closer hit
+                    let (near, far) = if direction[node.axis] < 0.0 {
+                        (node.offset, node_index + 1)
+                    } else {
+                        (node_index + 1, node.offset)
+                    };
+
+                    stack[stack_size] = far;
+                    stack_size += 1;
+                    node_index = near;
+                    continue;
+                }
+            }
+
+            if stack_size == 0 {
+                break;
+            }
+            stack_size -= 1;
+            node_index = stack[stack_size];
+        }
+
+        hit_anything
+    }
+
+    fn bbox(&self) -> &AABB {
+        &self.bbox
+    }
+
+    /// Multiplies what every object along the ray lets through, stopping as
+    /// soon as one blocks it
+    fn transmittance(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> f64 {
+        if self.nodes.is_empty() {
+            return 1.0;
+        }
+
+        let origin = ray.get_origin();
+        let direction = ray.get_direction();
+        let inv_direction = Vector3::new(
+            1.0 / direction.x(),
+            1.0 / direction.y(),
+            1.0 / direction.z(),
+        );
+
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_size = 0;
+        let mut node_index = 0;
+        let mut transmittance = 1.0;
+
+        loop {
+            let node = &self.nodes[node_index];
+
+            if node.bbox.hit_inverse(&origin, &inv_direction, *t) {
+                if node.count > 0 {
+                    for object in &self.objects[node.offset..node.offset + node.count] {
+                        transmittance *= object.transmittance(ray, t, sampler);
+                        if transmittance <= 0.0 {
+                            return 0.0;
+                        }
+                    }
+                } else {
+                    stack[stack_size] = node.offset;
+                    stack_size += 1;
+                    node_index += 1;
+                    continue;
+                }
+            }
+
+            if stack_size == 0 {
+                break;
+            }
+            stack_size -= 1;
+            node_index = stack[stack_size];
+        }
+
+        transmittance
+    }
+}
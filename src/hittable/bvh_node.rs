@@ -2,6 +2,7 @@ use crate::aabb::AABB;
 use crate::hittable::{HitRecord, Hittable, HittableList};
 use crate::interval::Interval;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use std::cmp::Ordering;
 use std::sync::Arc;
 
@@ -19,7 +20,7 @@ impl BVHNode {
     pub fn new_from_objects(objects: &[Arc<dyn Hittable>], start: usize, end: usize) -> BVHNode {
         let mut bbox = AABB::new_empty();
         for object in objects.iter().take(end).skip(start) {
-            bbox = AABB::new_from_aabbs(&bbox, &object.bbox());
+            bbox = AABB::new_from_aabbs(&bbox, object.bbox());
         }
 
         let axis = bbox.longest_axis();
@@ -87,21 +88,31 @@ impl BVHNode {
 }
 
 impl Hittable for BVHNode {
-    fn hit(&self, ray: &Ray, t: &mut Interval, rec: &mut HitRecord) -> bool {
-        if !self.bbox.hit(ray, t) {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        // The box test narrows the interval it is given, so the children must
+        // each start again from the caller's interval
+        let mut bbox_t = *t;
+        if !self.bbox.hit(ray, &mut bbox_t) {
             return false;
         }
 
         let mut temp_rec = HitRecord::new();
-        let hit_left = self.left.hit(ray, t, &mut temp_rec);
+        let mut left_t = *t;
+        let hit_left = self.left.hit(ray, &mut left_t, &mut temp_rec, sampler);
 
-        let mut right_t = t;
+        let mut right_t = *t;
         if hit_left {
             right_t.max = temp_rec.t;
         }
 
         let mut right_rec = HitRecord::new();
-        let hit_right = self.right.hit(ray, &mut right_t, &mut right_rec);
+        let hit_right = self.right.hit(ray, &mut right_t, &mut right_rec, sampler);
 
         // Choose the closest hit
         if hit_left && hit_right {
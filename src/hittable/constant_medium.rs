@@ -2,39 +2,56 @@ use crate::aabb::AABB;
 use crate::color::Color;
 use crate::hittable::{HitRecord, Hittable};
 use crate::interval::Interval;
+use crate::material::Material;
 use crate::material::isotropic::Isotropic;
+use crate::material::phase::PhaseFunction;
+use crate::material::volume::Volume;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::texture::Texture;
-use crate::vector::Vector3;
-use rand::random_range;
 use std::sync::Arc;
 
 pub struct ConstantMedium {
     boundary: Arc<dyn Hittable>,
     neg_inv_density: f64,
-    phase_function: Arc<Isotropic>,
+    phase_function: Arc<dyn Material>,
 }
 
 impl ConstantMedium {
     pub fn new(boundary: Arc<dyn Hittable>, density: f64, texture: Arc<dyn Texture>) -> Self {
-        Self {
-            boundary,
-            neg_inv_density: -1.0 / density,
-            phase_function: Arc::new(Isotropic::new(texture)),
-        }
+        Self::from_material(boundary, density, Arc::new(Isotropic::new(texture)))
     }
 
     pub fn from_color(boundary: Arc<dyn Hittable>, density: f64, albedo: Color) -> Self {
+        Self::from_material(boundary, density, Arc::new(Isotropic::from_color(albedo)))
+    }
+
+    /// Medium scattering light according to `phase` rather than evenly in
+    /// every direction
+    pub fn with_phase(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        texture: Arc<dyn Texture>,
+        phase: PhaseFunction,
+    ) -> Self {
+        Self::from_material(boundary, density, Arc::new(Volume::new(texture, phase)))
+    }
+
+    fn from_material(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
         Self {
             boundary,
             neg_inv_density: -1.0 / density,
-            phase_function: Arc::new(Isotropic::from_color(albedo)),
+            phase_function,
         }
     }
-}
 
-impl Hittable for ConstantMedium {
-    fn hit(&self, ray: &Ray, t: &mut Interval, rec: &mut HitRecord) -> bool {
+    /// The stretch of `ray` within `t` that lies inside the boundary, as the
+    /// ray parameters where it enters and leaves
+    fn span(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> Option<(f64, f64)> {
         // Find the entry and exit points of the ray through the boundary
         let mut rec1 = HitRecord::new();
         let mut rec2 = HitRecord::new();
@@ -42,14 +59,20 @@ impl Hittable for ConstantMedium {
         // Use a very wide interval to find all intersections
         let mut interval_universe = Interval::new(f64::NEG_INFINITY, f64::INFINITY);
 
-        if !self.boundary.hit(ray, &mut interval_universe, &mut rec1) {
-            return false;
+        if !self
+            .boundary
+            .hit(ray, &mut interval_universe, &mut rec1, sampler)
+        {
+            return None;
         }
 
         // Find the second intersection (exit point)
         let mut interval_after_first = Interval::new(rec1.t + 0.0001, f64::INFINITY);
-        if !self.boundary.hit(ray, &mut interval_after_first, &mut rec2) {
-            return false;
+        if !self
+            .boundary
+            .hit(ray, &mut interval_after_first, &mut rec2, sampler)
+        {
+            return None;
         }
 
         // Clamp the intersection points to the valid ray parameter range
@@ -60,9 +83,9 @@ impl Hittable for ConstantMedium {
             rec2.t = t.max;
         }
 
-        // If the ray doesn't pass through the medium, return false
+        // The ray doesn't pass through the medium
         if rec1.t >= rec2.t {
-            return false;
+            return None;
         }
 
         // Ensure we don't have negative ray parameters
@@ -70,25 +93,38 @@ impl Hittable for ConstantMedium {
             rec1.t = 0.0;
         }
 
+        Some((rec1.t, rec2.t))
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let Some((t_enter, t_exit)) = self.span(ray, t, sampler) else {
+            return false;
+        };
+
         let ray_length = ray.get_direction().length();
-        let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
+        let distance_inside_boundary = (t_exit - t_enter) * ray_length;
 
         // Calculate the distance to the scattering event using exponential distribution
-        let hit_distance = self.neg_inv_density * random_range(0f64..1f64).ln();
+        let hit_distance = self.neg_inv_density * (1.0 - sampler.get_1d()).ln();
 
         // If the scattering event is beyond the boundary, no hit
         if hit_distance > distance_inside_boundary {
             return false;
         }
 
-        // Calculate the hit point
-        rec.t = rec1.t + hit_distance / ray_length;
-        rec.p = ray.at(rec.t);
-
-        // Set arbitrary normal and front_face (not meaningful for volumes)
-        rec.normal = Vector3::new(1.0, 0.0, 0.0);
-        rec.front_face = true;
-        rec.mat = self.phase_function.clone();
+        rec.set_medium_collision(
+            ray,
+            t_enter + hit_distance / ray_length,
+            &self.phase_function,
+        );
 
         true
     }
@@ -96,4 +132,15 @@ impl Hittable for ConstantMedium {
     fn bbox(&self) -> &AABB {
         self.boundary.bbox()
     }
+
+    /// Beer-Lambert's law over the distance inside the boundary
+    fn transmittance(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> f64 {
+        match self.span(ray, t, sampler) {
+            Some((t_enter, t_exit)) => {
+                let distance = (t_exit - t_enter) * ray.get_direction().length();
+                (distance / self.neg_inv_density).exp()
+            }
+            None => 1.0,
+        }
+    }
 }
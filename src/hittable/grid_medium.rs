@@ -0,0 +1,264 @@
+use crate::aabb::AABB;
+use crate::color::Color;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::material::phase::PhaseFunction;
+use crate::material::volume::Volume;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::texture::Texture;
+use crate::vector::{Point3, Vector3};
+use crate::voxel::VoxelGrid;
+use std::sync::Arc;
+
+/// Voxels along each axis of a majorant cell
+const MAJORANT_CELL: usize = 8;
+
+/// Heterogeneous medium filling a box, its density, and optionally its
+/// albedo and emission, read from a voxel grid. Collisions are found by
+/// delta tracking against the largest density of each cell of a coarse
+/// majorant grid, so that empty and thin regions are crossed in few steps,
+/// and shadow rays estimate how much light gets through by ratio tracking.
+pub struct GridMedium {
+    grid: Arc<VoxelGrid>,
+    min: Point3,
+    size: Vector3,
+    density: f64,                // Scale applied to the grid's densities
+    albedo: Arc<dyn Texture>,    // Per-voxel albedo, or the medium's own
+    emission: Arc<dyn Texture>,  // Per-voxel emitted radiance
+    material: Arc<dyn Material>, // Shared by every collision
+    majorant_resolution: [usize; 3],
+    majorants: Vec<f64>,
+    bbox: AABB,
+}
+
+impl GridMedium {
+    /// Stretches `grid` over the box with opposite corners `a` and `b`
+    pub fn new(grid: VoxelGrid, a: Point3, b: Point3, density: f64, albedo: Color) -> Self {
+        let bbox = AABB::new_points(a, b);
+        let min = bbox.min();
+        let size = bbox.max() - min;
+
+        let majorant_resolution = grid.resolution().map(|n| n.div_ceil(MAJORANT_CELL));
+        let [mx, my, mz] = majorant_resolution;
+        let majorants = (0..mx * my * mz)
+            .map(|i| {
+                let cell = [i % mx, i / mx % my, i / (mx * my)];
+                let lo = Point3::new(
+                    cell[0] as f64 / mx as f64,
+                    cell[1] as f64 / my as f64,
+                    cell[2] as f64 / mz as f64,
+                );
+                let hi = Point3::new(
+                    (cell[0] + 1) as f64 / mx as f64,
+                    (cell[1] + 1) as f64 / my as f64,
+                    (cell[2] + 1) as f64 / mz as f64,
+                );
+                density * grid.max_density(&lo, &hi)
+            })
+            .collect();
+
+        let grid = Arc::new(grid);
+        let albedo: Arc<dyn Texture> = Arc::new(VoxelTexture {
+            grid: Arc::clone(&grid),
+            min,
+            size,
+            lookup: VoxelGrid::albedo,
+            fallback: albedo,
+        });
+        let emission: Arc<dyn Texture> = Arc::new(VoxelTexture {
+            grid: Arc::clone(&grid),
+            min,
+            size,
+            lookup: VoxelGrid::emission,
+            fallback: Color::new(0.0, 0.0, 0.0),
+        });
+        let material = volume(&albedo, &emission, PhaseFunction::Isotropic);
+
+        Self {
+            grid,
+            min,
+            size,
+            density,
+            albedo,
+            emission,
+            material,
+            majorant_resolution,
+            majorants,
+            bbox,
+        }
+    }
+
+    /// Scatters light according to `phase` rather than evenly in every
+    /// direction
+    pub fn set_phase(&mut self, phase: PhaseFunction) {
+        self.material = volume(&self.albedo, &self.emission, phase);
+    }
+
+    /// Point of the grid's unit cube at `p`
+    fn grid_point(&self, p: &Point3) -> Point3 {
+        grid_point(&self.min, &self.size, p)
+    }
+
+    /// Walks the majorant cells `ray` crosses within `t` in order, calling
+    /// `visit` with the majorant of each and the span of ray parameters
+    /// inside it, until `visit` returns false
+    fn march(&self, ray: &Ray, t: &Interval, mut visit: impl FnMut(f64, f64, f64) -> bool) {
+        let mut span = *t;
+        if !self.bbox.hit(ray, &mut span) {
+            return;
+        }
+        let (t_start, t_end) = (span.min.max(0.0), span.max);
+        if t_start >= t_end {
+            return;
+        }
+
+        let start = self.grid_point(&ray.at(t_start));
+        let direction = ray.get_direction();
+        let mut cell = [0usize; 3];
+        let mut step = [0isize; 3];
+        let mut t_next = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            let n = self.majorant_resolution[axis];
+            let x = (start[axis] * n as f64).clamp(0.0, n as f64);
+            cell[axis] = (x as usize).min(n - 1);
+
+            // Cells crossed per unit of the ray parameter
+            let speed = direction[axis] * n as f64 / self.size[axis];
+            if speed > 0.0 {
+                step[axis] = 1;
+                t_next[axis] = t_start + ((cell[axis] + 1) as f64 - x) / speed;
+                t_delta[axis] = 1.0 / speed;
+            } else if speed < 0.0 {
+                step[axis] = -1;
+                t_next[axis] = t_start + (cell[axis] as f64 - x) / speed;
+                t_delta[axis] = -1.0 / speed;
+            }
+        }
+
+        let [mx, my, _] = self.majorant_resolution;
+        let mut t0 = t_start;
+        loop {
+            let axis = (0..3)
+                .min_by(|&a, &b| t_next[a].total_cmp(&t_next[b]))
+                .unwrap();
+            let t1 = t_next[axis].min(t_end);
+            let majorant = self.majorants[(cell[2] * my + cell[1]) * mx + cell[0]];
+            if !visit(majorant, t0, t1) || t1 >= t_end {
+                return;
+            }
+
+            t0 = t1;
+            match cell[axis].checked_add_signed(step[axis]) {
+                Some(next) if next < self.majorant_resolution[axis] => cell[axis] = next,
+                _ => return,
+            }
+            t_next[axis] += t_delta[axis];
+        }
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        // Delta tracking: tentative collisions come at the majorant's rate,
+        // and each is real in proportion to the density found there
+        let ray_length = ray.get_direction().length();
+        let mut collision = None;
+        self.march(ray, t, |majorant, t0, t1| {
+            if majorant <= 0.0 {
+                return true;
+            }
+            let mut t = t0;
+            loop {
+                t -= (1.0 - sampler.get_1d()).ln() / (majorant * ray_length);
+                if t >= t1 {
+                    return true;
+                }
+                let p = self.grid_point(&ray.at(t));
+                if sampler.get_1d() * majorant < self.density * self.grid.density(&p) {
+                    collision = Some(t);
+                    return false;
+                }
+            }
+        });
+
+        let Some(t) = collision else {
+            return false;
+        };
+        rec.set_medium_collision(ray, t, &self.material);
+
+        true
+    }
+
+    fn bbox(&self) -> &AABB {
+        &self.bbox
+    }
+
+    /// Ratio tracking: every tentative collision lets through the fraction
+    /// of the majorant that is not real density
+    fn transmittance(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> f64 {
+        let ray_length = ray.get_direction().length();
+        let mut transmittance = 1.0;
+        self.march(ray, t, |majorant, t0, t1| {
+            if majorant <= 0.0 {
+                return true;
+            }
+            let mut t = t0;
+            loop {
+                t -= (1.0 - sampler.get_1d()).ln() / (majorant * ray_length);
+                if t >= t1 {
+                    return true;
+                }
+                let density = self.density * self.grid.density(&self.grid_point(&ray.at(t)));
+                transmittance *= 1.0 - density / majorant;
+                if transmittance <= 0.0 {
+                    return false;
+                }
+            }
+        });
+        transmittance.max(0.0)
+    }
+}
+
+/// Material of a real collision: scattering of the local albedo by `phase`,
+/// and the emission of the part of the medium that absorbs
+fn volume(
+    albedo: &Arc<dyn Texture>,
+    emission: &Arc<dyn Texture>,
+    phase: PhaseFunction,
+) -> Arc<dyn Material> {
+    Arc::new(Volume::new(Arc::clone(albedo), phase).with_emission(Arc::clone(emission)))
+}
+
+/// Point of the unit cube of a grid stretched from `min` over `size` at `p`
+fn grid_point(min: &Point3, size: &Vector3, p: &Point3) -> Point3 {
+    let offset = *p - *min;
+    Point3::new(
+        offset.x() / size.x(),
+        offset.y() / size.y(),
+        offset.z() / size.z(),
+    )
+}
+
+/// One of a grid's per-voxel colours, looked up at the world point
+struct VoxelTexture {
+    grid: Arc<VoxelGrid>,
+    min: Point3,
+    size: Vector3,
+    lookup: fn(&VoxelGrid, &Point3) -> Option<Color>,
+    fallback: Color, // Where the voxels have no colours of their own
+}
+
+impl Texture for VoxelTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        (self.lookup)(&self.grid, &grid_point(&self.min, &self.size, p)).unwrap_or(self.fallback)
+    }
+}
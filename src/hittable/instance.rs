@@ -0,0 +1,83 @@
+use crate::aabb::AABB;
+use crate::hittable::bvh::BVH;
+use crate::hittable::transform::Transform;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::matrix::Matrix4;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+/// One placement of a shared, prebuilt BVH. Every instance only stores its
+/// transform and an optional material, so repeating a mesh a thousand times
+/// costs a thousand matrices rather than a thousand copies of the mesh. The
+/// world BVH built over the instances forms the top level of a two-level
+/// hierarchy, with the shared BVH as the bottom level.
+pub struct Instance {
+    transform: Transform,
+    mat: Option<Arc<dyn Material>>, // Replaces the materials of the prototype when set
+}
+
+impl Instance {
+    /// Panics if `object_to_world` can't be inverted
+    pub fn new(prototype: Arc<BVH>, object_to_world: Matrix4) -> Self {
+        Self {
+            transform: Transform::new(prototype, object_to_world),
+            mat: None,
+        }
+    }
+
+    /// Instance drawn entirely with `mat` instead of the prototype's materials
+    pub fn with_material(
+        prototype: Arc<BVH>,
+        object_to_world: Matrix4,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            mat: Some(mat),
+            ..Self::new(prototype, object_to_world)
+        }
+    }
+
+    pub fn object_to_world(&self) -> &Matrix4 {
+        self.transform.object_to_world()
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        if !self.transform.hit(ray, t, rec, sampler) {
+            return false;
+        }
+
+        if let Some(mat) = &self.mat {
+            rec.mat = Arc::clone(mat);
+        }
+
+        true
+    }
+
+    fn bbox(&self) -> &AABB {
+        self.transform.bbox()
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        self.transform.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        self.transform.random(origin, sampler)
+    }
+
+    fn transmittance(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> f64 {
+        self.transform.transmittance(ray, t, sampler)
+    }
+}
@@ -0,0 +1,177 @@
+use crate::aabb::AABB;
+use crate::hittable::bvh::BVH;
+use crate::hittable::triangle::{
+    geometric_normal, intersect, is_degenerate, set_surface, triangle_bbox,
+};
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+/// Vertex buffers of an indexed triangle mesh.
+/// `normals` and `uvs` are either empty or hold one entry per position.
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vector3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl MeshData {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vector3>,
+        uvs: Vec<(f64, f64)>,
+        indices: Vec<[usize; 3]>,
+    ) -> Self {
+        assert!(
+            normals.is_empty() || normals.len() == positions.len(),
+            "mesh needs one normal per vertex"
+        );
+        assert!(
+            uvs.is_empty() || uvs.len() == positions.len(),
+            "mesh needs one texture coordinate per vertex"
+        );
+        assert!(
+            indices.iter().flatten().all(|&i| i < positions.len()),
+            "mesh index out of range"
+        );
+
+        Self {
+            positions,
+            normals,
+            uvs,
+            indices,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn vertices(&self, triangle: usize) -> [Point3; 3] {
+        self.indices[triangle].map(|i| self.positions[i])
+    }
+
+    fn normals(&self, triangle: usize) -> Option<[Vector3; 3]> {
+        if self.normals.is_empty() {
+            None
+        } else {
+            Some(self.indices[triangle].map(|i| self.normals[i]))
+        }
+    }
+
+    fn uvs(&self, triangle: usize) -> Option<[(f64, f64); 3]> {
+        if self.uvs.is_empty() {
+            None
+        } else {
+            Some(self.indices[triangle].map(|i| self.uvs[i]))
+        }
+    }
+}
+
+/// A triangle mesh sharing one set of vertex buffers between all of its triangles,
+/// with its own BVH over those triangles.
+pub struct TriangleMesh {
+    data: Arc<MeshData>,
+    bvh: BVH,
+}
+
+impl TriangleMesh {
+    pub fn new(data: Arc<MeshData>, mat: Arc<dyn Material>) -> Self {
+        let triangles: Vec<Arc<dyn Hittable>> = (0..data.triangle_count())
+            .filter(|&index| !is_degenerate(&data.vertices(index)))
+            .map(|index| {
+                Arc::new(MeshTriangle::new(
+                    Arc::clone(&data),
+                    index,
+                    Arc::clone(&mat),
+                )) as Arc<dyn Hittable>
+            })
+            .collect();
+
+        Self {
+            data,
+            bvh: BVH::new_from_objects(triangles),
+        }
+    }
+
+    pub fn data(&self) -> &Arc<MeshData> {
+        &self.data
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        self.bvh.hit(ray, t, rec, sampler)
+    }
+
+    fn bbox(&self) -> &AABB {
+        self.bvh.bbox()
+    }
+}
+
+/// One triangle of a mesh, referring back to the shared vertex buffers
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    index: usize,
+    normal: Vector3,
+    mat: Arc<dyn Material>,
+    bbox: AABB,
+}
+
+impl MeshTriangle {
+    fn new(mesh: Arc<MeshData>, index: usize, mat: Arc<dyn Material>) -> Self {
+        let vertices = mesh.vertices(index);
+
+        Self {
+            normal: geometric_normal(&vertices),
+            bbox: triangle_bbox(&vertices),
+            mesh,
+            index,
+            mat,
+        }
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
+        let Some((t_hit, b1, b2)) = intersect(ray, &self.mesh.vertices(self.index), t) else {
+            return false;
+        };
+
+        rec.t = t_hit;
+        rec.p = ray.at(t_hit);
+        rec.mat = Arc::clone(&self.mat);
+        set_surface(
+            rec,
+            ray,
+            &self.normal,
+            self.mesh.normals(self.index),
+            self.mesh.uvs(self.index),
+            b1,
+            b2,
+        );
+
+        true
+    }
+
+    fn bbox(&self) -> &AABB {
+        &self.bbox
+    }
+}
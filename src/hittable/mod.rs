@@ -1,19 +1,57 @@
+pub mod bvh;
 pub mod bvh_node;
 pub mod constant_medium;
+pub mod grid_medium;
+pub mod instance;
+pub mod mesh;
 pub mod quad;
-pub mod rotate;
 pub mod sphere;
+pub mod transform;
+pub mod triangle;
 
 use crate::aabb::AABB;
 use crate::interval::Interval;
 use crate::material::{DefaultMaterial, Material};
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vector::{Point3, Vector3};
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
+
+// Shared placeholder so creating a blank hit record does not allocate
+static DEFAULT_MATERIAL: LazyLock<Arc<dyn Material>> =
+    LazyLock::new(|| Arc::new(DefaultMaterial::new()));
 
 pub trait Hittable: Send + Sync {
-    fn hit(&self, ray: &Ray, t: &mut Interval, rec: &mut HitRecord) -> bool;
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool;
     fn bbox(&self) -> &AABB;
+
+    /// Density, in solid angle, of `random` returning `direction` from `origin`
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vector3) -> f64 {
+        0.0
+    }
+
+    /// Direction from `origin` towards a random point on the object
+    fn random(&self, _origin: &Point3, _sampler: &mut dyn Sampler) -> Vector3 {
+        Vector3::new(1.0, 0.0, 0.0)
+    }
+
+    /// Fraction of the light along `ray` within `t` that gets through the
+    /// object. Surfaces block whatever they hit; media override this to
+    /// estimate it without settling on a single point where the light stops.
+    fn transmittance(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> f64 {
+        let mut rec = HitRecord::new();
+        if self.hit(ray, &mut t.clone(), &mut rec, sampler) {
+            0.0
+        } else {
+            1.0
+        }
+    }
 }
 
 pub struct HitRecord {
@@ -35,7 +73,7 @@ impl HitRecord {
             u: 0.0,
             v: 0.0,
             front_face: false,
-            mat: Arc::new(DefaultMaterial::new()),
+            mat: Arc::clone(&DEFAULT_MATERIAL),
         }
     }
 
@@ -47,6 +85,17 @@ impl HitRecord {
             -*outward_normal
         };
     }
+
+    /// Records a collision at `t` inside a participating medium
+    pub fn set_medium_collision(&mut self, ray: &Ray, t: f64, mat: &Arc<dyn Material>) {
+        self.t = t;
+        self.p = ray.at(t);
+
+        // Set arbitrary normal and front_face (not meaningful for volumes)
+        self.normal = Vector3::new(1.0, 0.0, 0.0);
+        self.front_face = true;
+        self.mat = Arc::clone(mat);
+    }
 }
 
 pub struct HittableList {
@@ -55,14 +104,20 @@ pub struct HittableList {
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, t: &mut Interval, rec: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
         let mut hit_anything = false;
         let mut closest_so_far = t.max;
 
         for object in &self.objects {
             let mut temp_rec = HitRecord::new();
             let mut temp_interval = Interval::new(t.min, closest_so_far);
-            if object.hit(ray, &mut temp_interval, &mut temp_rec) {
+            if object.hit(ray, &mut temp_interval, &mut temp_rec, sampler) {
                 hit_anything = true;
                 closest_so_far = temp_rec.t;
                 *rec = temp_rec;
@@ -75,6 +130,47 @@ impl Hittable for HittableList {
     fn bbox(&self) -> &AABB {
         &self.bbox
     }
+
+    /// Picks each object with equal probability
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f64 = self
+            .objects
+            .iter()
+            .map(|object| object.pdf_value(origin, direction))
+            .sum();
+        sum / self.objects.len() as f64
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        if self.objects.is_empty() {
+            return Vector3::new(1.0, 0.0, 0.0);
+        }
+
+        let len = self.objects.len();
+        let index = ((sampler.get_1d() * len as f64) as usize).min(len - 1);
+        self.objects[index].random(origin, sampler)
+    }
+
+    fn transmittance(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> f64 {
+        let mut transmittance = 1.0;
+        for object in &self.objects {
+            transmittance *= object.transmittance(ray, t, sampler);
+            if transmittance <= 0.0 {
+                break;
+            }
+        }
+        transmittance
+    }
+}
+
+impl Default for HitRecord {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HittableList {
@@ -87,21 +183,27 @@ impl HittableList {
 
     pub fn add(&mut self, object: Arc<dyn Hittable>) {
         self.objects.push(object.clone());
-        self.bbox = AABB::new_from_aabbs(&self.bbox, &object.bbox());
+        self.bbox = AABB::new_from_aabbs(&self.bbox, object.bbox());
     }
 
     pub fn clear(&mut self) {
         self.objects.clear();
     }
 
-    pub fn hit(&self, ray: &Ray, t: Interval, rec: &mut HitRecord) -> bool {
+    pub fn hit(
+        &self,
+        ray: &Ray,
+        t: Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
         let mut hit_anything = false;
         let mut closest_so_far = t.max;
 
         for object in &self.objects {
             let mut temp_rec = HitRecord::new();
             let mut temp_interval = Interval::new(t.min, closest_so_far);
-            if object.hit(ray, &mut temp_interval, &mut temp_rec) {
+            if object.hit(ray, &mut temp_interval, &mut temp_rec, sampler) {
                 hit_anything = true;
                 closest_so_far = temp_rec.t;
                 *rec = temp_rec;
@@ -112,38 +214,8 @@ impl HittableList {
     }
 }
 
-struct Translate {
-    object: Arc<dyn Hittable>,
-    offset: Vector3,
-    bbox: AABB,
-}
-
-impl Translate {
-    pub fn new(object: Arc<dyn Hittable>, offset: Vector3) -> Self {
-        let bbox = object.bbox() + offset;
-
-        Self {
-            object,
-            offset,
-            bbox,
-        }
-    }
-}
-
-impl Hittable for Translate {
-    fn hit(&self, ray: &Ray, t: &mut Interval, rec: &mut HitRecord) -> bool {
-        let offset_ray = Ray::new(ray.get_origin() - self.offset, ray.get_direction());
-
-        if !self.object.hit(&offset_ray, t, rec) {
-            return false;
-        }
-
-        rec.p += self.offset;
-
-        true
-    }
-
-    fn bbox(&self) -> &AABB {
-        &self.bbox
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -3,6 +3,7 @@ use crate::hittable::{HitRecord, Hittable, HittableList};
 use crate::interval::Interval;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vector::{Point3, Vector3};
 use std::sync::Arc;
 
@@ -15,6 +16,7 @@ pub struct Quad {
     pub bbox: AABB,
     pub normal: Vector3,
     pub d: f64,
+    pub area: f64,
 }
 
 impl Quad {
@@ -37,6 +39,7 @@ impl Quad {
             ), // temporary
             normal,
             d,
+            area: n.length(),
         };
 
         quad.set_bounding_box();
@@ -60,10 +63,9 @@ impl Quad {
         rec.v = b;
         true
     }
-}
 
-impl Hittable for Quad {
-    fn hit(&self, ray: &Ray, t: &mut Interval, rec: &mut HitRecord) -> bool {
+    /// Ray intersection shared by `hit` and `pdf_value`
+    fn intersect(&self, ray: &Ray, t: &Interval, rec: &mut HitRecord) -> bool {
         let denom = Vector3::dot(&self.normal, &ray.get_direction());
 
         // No hit if the ray is parallel to the plane.
@@ -94,10 +96,45 @@ impl Hittable for Quad {
 
         true
     }
+}
+
+impl Hittable for Quad {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
+        self.intersect(ray, t, rec)
+    }
 
     fn bbox(&self) -> &AABB {
         &self.bbox
     }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        let mut rec = HitRecord::new();
+        if !self.intersect(
+            &Ray::new(*origin, *direction),
+            &Interval::new(0.001, f64::INFINITY),
+            &mut rec,
+        ) {
+            return 0.0;
+        }
+
+        // Convert the uniform density over the area into one over solid angle
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = (Vector3::dot(direction, &rec.normal) / direction.length()).abs();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        let (a, b) = sampler.get_2d();
+        let p = self.q + (a * self.u) + (b * self.v);
+        p - *origin
+    }
 }
 
 /// Returns a 3D box (six sides) that contains the two opposite vertices a & b.
@@ -2,13 +2,15 @@ use crate::aabb::AABB;
 use crate::hittable::{HitRecord, Hittable};
 use crate::interval::Interval;
 use crate::material::Material;
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vector::{Point3, Vector3};
 use std::f64::consts::PI;
 use std::sync::Arc;
 
 pub struct Sphere {
-    center: Point3,
+    center: Ray, // Where the center is at time 0, and how far it moves by time 1
     radius: f64,
     mat: Arc<dyn Material>,
     bbox: AABB,
@@ -19,13 +21,49 @@ impl Sphere {
         let rvec = Vector3::new(radius, radius, radius);
 
         Sphere {
-            center,
+            center: Ray::new(center, Vector3::new(0.0, 0.0, 0.0)),
             radius,
             mat,
             bbox: AABB::new_points(center - rvec, center + rvec),
         }
     }
 
+    /// Sphere moving in a straight line from `center1` at time 0 to `center2`
+    /// at time 1. Light sampling has no time to place it at, so a moving
+    /// sphere reports no density towards itself.
+    pub fn new_moving(
+        center1: Point3,
+        center2: Point3,
+        radius: f64,
+        mat: Arc<dyn Material>,
+    ) -> Sphere {
+        let rvec = Vector3::new(radius, radius, radius);
+        let box1 = AABB::new_points(center1 - rvec, center1 + rvec);
+        let box2 = AABB::new_points(center2 - rvec, center2 + rvec);
+
+        Sphere {
+            center: Ray::new(center1, center2 - center1),
+            radius,
+            mat,
+            bbox: AABB::new_from_aabbs(&box1, &box2),
+        }
+    }
+
+    fn is_moving(&self) -> bool {
+        !self.center.get_direction().near_zero()
+    }
+
+    fn random_to_sphere(sampler: &mut dyn Sampler, radius: f64, distance_squared: f64) -> Vector3 {
+        let (r1, r2) = sampler.get_2d();
+        let z = 1.0 + r2 * ((1.0 - radius * radius / distance_squared).sqrt() - 1.0);
+
+        let phi = 2.0 * PI * r1;
+        let x = phi.cos() * (1.0 - z * z).sqrt();
+        let y = phi.sin() * (1.0 - z * z).sqrt();
+
+        Vector3::new(x, y, z)
+    }
+
     fn get_sphere_uv(p: &Point3, u: &mut f64, v: &mut f64) {
         let theta = (-p.y()).acos();
         let phi = (-p.z()).atan2(p.x()) + PI;
@@ -33,11 +71,11 @@ impl Sphere {
         *u = phi / (2.0 * PI);
         *v = theta / PI;
     }
-}
 
-impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t: &mut Interval, rec: &mut HitRecord) -> bool {
-        let oc = ray.get_origin() - self.center;
+    /// Ray intersection shared by `hit` and `pdf_value`
+    fn intersect(&self, ray: &Ray, t: &Interval, rec: &mut HitRecord) -> bool {
+        let center = self.center.at(ray.get_time());
+        let oc = ray.get_origin() - center;
         let a = ray.get_direction().length_squared();
         let half_b = Vector3::dot(&oc, &ray.get_direction());
         let c = oc.length_squared() - self.radius * self.radius;
@@ -60,15 +98,69 @@ impl Hittable for Sphere {
         rec.t = root;
         rec.p = ray.at(rec.t);
 
-        let outward_normal = (rec.p - self.center) / self.radius;
+        let outward_normal = (rec.p - center) / self.radius;
         rec.set_face_normal(ray, &outward_normal);
         Self::get_sphere_uv(&outward_normal, &mut rec.u, &mut rec.v);
         rec.mat = self.mat.clone();
 
         true
     }
+}
+
+impl Hittable for Sphere {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
+        self.intersect(ray, t, rec)
+    }
 
     fn bbox(&self) -> &AABB {
         &self.bbox
     }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        if self.is_moving() {
+            return 0.0;
+        }
+
+        let mut rec = HitRecord::new();
+        if !self.intersect(
+            &Ray::new(*origin, *direction),
+            &Interval::new(0.001, f64::INFINITY),
+            &mut rec,
+        ) {
+            return 0.0;
+        }
+
+        let distance_squared = (self.center.get_origin() - *origin).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            // From inside every direction sees the sphere
+            return 1.0 / (4.0 * PI);
+        }
+
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    /// Samples the cone of directions the sphere subtends
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        let direction = self.center.get_origin() - *origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return Vector3::random_unit_vector(sampler);
+        }
+
+        let uvw = Onb::new(&direction);
+        uvw.transform(&Self::random_to_sphere(
+            sampler,
+            self.radius,
+            distance_squared,
+        ))
+    }
 }
@@ -0,0 +1,231 @@
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::matrix::{AnimatedMatrix, Matrix4};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+/// Places an object in the world with an affine matrix. Rays are moved into
+/// the object's space rather than the object into the world, so any
+/// combination of translation, rotation and scale costs a single wrapper.
+pub struct Transform {
+    object: Arc<dyn Hittable>,
+    object_to_world: Matrix4,
+    world_to_object: Matrix4,
+    normal_to_world: Matrix4, // Inverse transpose, keeps normals perpendicular under scaling
+    bbox: AABB,
+}
+
+impl Transform {
+    /// Panics if `object_to_world` can't be inverted
+    pub fn new(object: Arc<dyn Hittable>, object_to_world: Matrix4) -> Self {
+        let world_to_object = object_to_world
+            .inverse()
+            .expect("object transform must be invertible");
+        let bbox = transform_bbox(object.bbox(), &object_to_world);
+
+        Self {
+            object,
+            object_to_world,
+            world_to_object,
+            normal_to_world: world_to_object.transpose(),
+            bbox,
+        }
+    }
+
+    pub fn object_to_world(&self) -> &Matrix4 {
+        &self.object_to_world
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        // The direction isn't normalized, so distances along the ray stay the same
+        let object_ray = Ray::new_at_time(
+            self.world_to_object.transform_point(&ray.get_origin()),
+            self.world_to_object.transform_vector(&ray.get_direction()),
+            ray.get_time(),
+        );
+
+        if !self.object.hit(&object_ray, t, rec, sampler) {
+            return false;
+        }
+
+        rec.p = self.object_to_world.transform_point(&rec.p);
+        rec.normal = self
+            .normal_to_world
+            .transform_vector(&rec.normal)
+            .unit_vector();
+
+        true
+    }
+
+    fn bbox(&self) -> &AABB {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vector3) -> f64 {
+        let unit = direction.unit_vector();
+        let object_direction = self.world_to_object.transform_vector(&unit);
+        let object_pdf = self.object.pdf_value(
+            &self.world_to_object.transform_point(origin),
+            &object_direction,
+        );
+
+        // A linear map stretches solid angle around a unit direction d by
+        // |det A| / |A d|^3, which is 1 for rotations
+        let stretch = object_direction.length();
+        object_pdf * self.world_to_object.determinant3().abs() / (stretch * stretch * stretch)
+    }
+
+    fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        let object_origin = self.world_to_object.transform_point(origin);
+        self.object_to_world
+            .transform_vector(&self.object.random(&object_origin, sampler))
+    }
+
+    fn transmittance(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> f64 {
+        let object_ray = Ray::new_at_time(
+            self.world_to_object.transform_point(&ray.get_origin()),
+            self.world_to_object.transform_vector(&ray.get_direction()),
+            ray.get_time(),
+        );
+        self.object.transmittance(&object_ray, t, sampler)
+    }
+}
+
+/// Places an object with a transform that moves from `start` at time 0 to
+/// `end` at time 1, blurring it over the exposure. Light sampling has no time
+/// to place it at, so like a moving sphere it reports no density.
+pub struct AnimatedTransform {
+    object: Arc<dyn Hittable>,
+    motion: AnimatedMatrix,
+    bbox: AABB,
+}
+
+impl AnimatedTransform {
+    /// Panics if either end can't be inverted
+    pub fn new(object: Arc<dyn Hittable>, start: Matrix4, end: Matrix4) -> Self {
+        let motion = AnimatedMatrix::new(start, end);
+        let bbox = animated_bbox(object.bbox(), &motion);
+
+        Self {
+            object,
+            motion,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for AnimatedTransform {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let object_to_world = self.motion.at(ray.get_time());
+        let Some(world_to_object) = object_to_world.inverse() else {
+            return false;
+        };
+
+        let object_ray = Ray::new_at_time(
+            world_to_object.transform_point(&ray.get_origin()),
+            world_to_object.transform_vector(&ray.get_direction()),
+            ray.get_time(),
+        );
+
+        if !self.object.hit(&object_ray, t, rec, sampler) {
+            return false;
+        }
+
+        rec.p = object_to_world.transform_point(&rec.p);
+        rec.normal = world_to_object
+            .transpose()
+            .transform_vector(&rec.normal)
+            .unit_vector();
+
+        true
+    }
+
+    fn bbox(&self) -> &AABB {
+        &self.bbox
+    }
+
+    fn transmittance(&self, ray: &Ray, t: &Interval, sampler: &mut dyn Sampler) -> f64 {
+        let Some(world_to_object) = self.motion.at(ray.get_time()).inverse() else {
+            return 1.0;
+        };
+
+        let object_ray = Ray::new_at_time(
+            world_to_object.transform_point(&ray.get_origin()),
+            world_to_object.transform_vector(&ray.get_direction()),
+            ray.get_time(),
+        );
+        self.object.transmittance(&object_ray, t, sampler)
+    }
+}
+
+/// Box around everywhere `bbox` goes over the animation. The transform is
+/// sampled at steps of at most a degree of rotation; between two steps a
+/// point strays from the straight line joining them by no more than its
+/// distance from the rotation center times the step angle, which pads the
+/// box.
+fn animated_bbox(bbox: &AABB, motion: &AnimatedMatrix) -> AABB {
+    let angle = motion.rotation_angle();
+    let steps = (angle.to_degrees().ceil() as usize).max(1);
+    let step_angle = angle / steps as f64;
+
+    let mut radius: f64 = 0.0;
+    let mut result = AABB::new_empty();
+
+    for step in 0..=steps {
+        let matrix = motion.at(step as f64 / steps as f64);
+        result = AABB::new_from_aabbs(&result, &transform_bbox(bbox, &matrix));
+
+        for corner in corners(bbox) {
+            radius = radius.max(matrix.transform_vector(&corner).length());
+        }
+    }
+
+    let pad = radius * step_angle;
+    let pad = Vector3::new(pad, pad, pad);
+    AABB::new_points(result.min() - pad, result.max() + pad)
+}
+
+/// Box around all eight transformed corners of `bbox`
+pub fn transform_bbox(bbox: &AABB, matrix: &Matrix4) -> AABB {
+    let mut new_min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+    let mut new_max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+    for corner in corners(bbox) {
+        let transformed = matrix.transform_point(&corner);
+
+        for axis in 0..3 {
+            new_min[axis] = new_min[axis].min(transformed[axis]);
+            new_max[axis] = new_max[axis].max(transformed[axis]);
+        }
+    }
+
+    AABB::new_points(new_min, new_max)
+}
+
+fn corners(bbox: &AABB) -> [Point3; 8] {
+    let (min, max) = (bbox.min(), bbox.max());
+    std::array::from_fn(|corner| {
+        Point3::new(
+            if corner & 1 == 0 { min.x() } else { max.x() },
+            if corner & 2 == 0 { min.y() } else { max.y() },
+            if corner & 4 == 0 { min.z() } else { max.z() },
+        )
+    })
+}
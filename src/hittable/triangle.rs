@@ -0,0 +1,179 @@
+use crate::aabb::AABB;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vector3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    normal: Vector3,
+    degenerate: bool, // Collinear or coincident vertices, which no ray hits
+    mat: Arc<dyn Material>,
+    bbox: AABB,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, mat: Arc<dyn Material>) -> Self {
+        Self::new_with_attributes([a, b, c], None, None, mat)
+    }
+
+    /// Creates a triangle with optional per-vertex normals for smooth shading and per-vertex
+    /// texture coordinates. Without texture coordinates the hit record carries the
+    /// barycentric coordinates of the hit point instead.
+    pub fn new_with_attributes(
+        vertices: [Point3; 3],
+        normals: Option<[Vector3; 3]>,
+        uvs: Option<[(f64, f64); 3]>,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        let degenerate = is_degenerate(&vertices);
+        let normal = if degenerate {
+            Vector3::new(0.0, 0.0, 0.0)
+        } else {
+            geometric_normal(&vertices)
+        };
+        let bbox = triangle_bbox(&vertices);
+
+        Self {
+            vertices,
+            normals,
+            uvs,
+            normal,
+            degenerate,
+            mat,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t: &mut Interval,
+        rec: &mut HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> bool {
+        if self.degenerate {
+            return false;
+        }
+        let Some((t_hit, b1, b2)) = intersect(ray, &self.vertices, t) else {
+            return false;
+        };
+
+        rec.t = t_hit;
+        rec.p = ray.at(t_hit);
+        rec.mat = Arc::clone(&self.mat);
+        set_surface(rec, ray, &self.normal, self.normals, self.uvs, b1, b2);
+
+        true
+    }
+
+    fn bbox(&self) -> &AABB {
+        &self.bbox
+    }
+}
+
+/// Möller-Trumbore ray/triangle intersection.
+/// Returns the ray parameter and the barycentric weights of the second and third vertex.
+pub(crate) fn intersect(
+    ray: &Ray,
+    vertices: &[Point3; 3],
+    t: &Interval,
+) -> Option<(f64, f64, f64)> {
+    let direction = ray.get_direction();
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+
+    let pvec = Vector3::cross(&direction, &edge2);
+    let det = Vector3::dot(&edge1, &pvec);
+
+    // No hit if the ray is parallel to the triangle's plane.
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = ray.get_origin() - vertices[0];
+    let b1 = Vector3::dot(&tvec, &pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let qvec = Vector3::cross(&tvec, &edge1);
+    let b2 = Vector3::dot(&direction, &qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t_hit = Vector3::dot(&edge2, &qvec) * inv_det;
+    if !t.surrounds(t_hit) {
+        return None;
+    }
+
+    Some((t_hit, b1, b2))
+}
+
+/// Fills in the normal and texture coordinates of a triangle hit from its barycentric weights.
+pub(crate) fn set_surface(
+    rec: &mut HitRecord,
+    ray: &Ray,
+    geometric_normal: &Vector3,
+    normals: Option<[Vector3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    b1: f64,
+    b2: f64,
+) {
+    let b0 = 1.0 - b1 - b2;
+
+    // Which side was hit is decided by the true surface, the interpolated normal only
+    // bends the shading and is flipped to the same side.
+    rec.set_face_normal(ray, geometric_normal);
+    if let Some([n0, n1, n2]) = normals {
+        let shading_normal = (b0 * n0 + b1 * n1 + b2 * n2).unit_vector();
+        rec.normal = if rec.front_face {
+            shading_normal
+        } else {
+            -shading_normal
+        };
+    }
+
+    match uvs {
+        Some([uv0, uv1, uv2]) => {
+            rec.u = b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0;
+            rec.v = b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1;
+        }
+        None => {
+            rec.u = b1;
+            rec.v = b2;
+        }
+    }
+}
+
+pub(crate) fn geometric_normal(vertices: &[Point3; 3]) -> Vector3 {
+    Vector3::cross(&(vertices[1] - vertices[0]), &(vertices[2] - vertices[0])).unit_vector()
+}
+
+/// Whether the vertices span no area, leaving the triangle without a normal
+pub(crate) fn is_degenerate(vertices: &[Point3; 3]) -> bool {
+    Vector3::cross(&(vertices[1] - vertices[0]), &(vertices[2] - vertices[0])).length_squared()
+        == 0.0
+}
+
+pub(crate) fn triangle_bbox(vertices: &[Point3; 3]) -> AABB {
+    let axis = |i: usize| {
+        let values = vertices.map(|v| v[i]);
+        Interval::new(
+            values.iter().copied().fold(f64::INFINITY, f64::min),
+            values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        )
+    };
+
+    // AABB::new pads axis-aligned triangles so their boxes are never flat
+    AABB::new(axis(0), axis(1), axis(2))
+}
@@ -0,0 +1,24 @@
+pub mod aabb;
+pub mod camera;
+pub mod color;
+pub mod distribution;
+pub mod environment;
+pub mod hittable;
+pub mod image;
+pub mod interval;
+pub mod light;
+pub mod material;
+pub mod matrix;
+pub mod obj;
+pub mod onb;
+pub mod output;
+pub mod pdf;
+pub mod perlin;
+pub mod ray;
+pub mod rng;
+pub mod sampler;
+pub mod scene;
+pub mod texture;
+pub mod tonemap;
+pub mod vector;
+pub mod voxel;
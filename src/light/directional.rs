@@ -0,0 +1,44 @@
+use crate::color::Color;
+use crate::light::{Light, LightSample};
+use crate::onb::Onb;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+
+/// Light from infinitely far away, such as the sun. With an angular radius
+/// it comes from a small disk in the sky and casts soft shadows.
+pub struct DirectionalLight {
+    to_light: Onb,     // Frame around the direction the light comes from
+    irradiance: Color, // Power per unit area facing the light
+    cos_radius: f64,   // Cosine of the angular radius, 1 for a point in the sky
+}
+
+impl DirectionalLight {
+    /// `direction` is the way the light travels and `angular_radius` is in
+    /// degrees
+    pub fn new(direction: Vector3, irradiance: Color, angular_radius: f64) -> Self {
+        Self {
+            to_light: Onb::new(&-direction),
+            irradiance,
+            cos_radius: angular_radius.clamp(0.0, 90.0).to_radians().cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        // Uniform direction in the cone, which carries the whole irradiance
+        // as the cone is never hit by scattered rays
+        let direction = if self.cos_radius < 1.0 {
+            self.to_light
+                .transform(&Vector3::random_in_cone(sampler, self.cos_radius))
+        } else {
+            self.to_light.w()
+        };
+
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.irradiance,
+        })
+    }
+}
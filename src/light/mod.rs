@@ -0,0 +1,48 @@
+//! Analytic lights: points, spots and distant suns that have no surface, so
+//! they are reached only by shadow rays and never by rays scattered at random.
+
+pub mod directional;
+pub mod point;
+pub mod spot;
+
+use crate::color::Color;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+/// Light arriving at a point from one analytic light
+pub struct LightSample {
+    pub direction: Vector3, // Unit vector from the point towards the light
+    pub distance: f64,      // How far the shadow ray must reach, infinite for distant lights
+    pub radiance: Color,    // Light arriving, already divided by the density of the sample
+}
+
+pub trait Light: Send + Sync {
+    /// Picks the light reaching `p`, `None` when none does
+    fn sample(&self, p: &Point3, sampler: &mut dyn Sampler) -> Option<LightSample>;
+}
+
+/// The analytic lights of a scene, every one of which is sampled at every
+/// diffuse bounce
+#[derive(Clone, Default)]
+pub struct LightList {
+    pub lights: Vec<Arc<dyn Light>>,
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        Self { lights: Vec::new() }
+    }
+
+    pub fn add(&mut self, light: Arc<dyn Light>) {
+        self.lights.push(light);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+}
@@ -0,0 +1,35 @@
+use crate::color::Color;
+use crate::light::{Light, LightSample};
+use crate::sampler::Sampler;
+use crate::vector::Point3;
+
+/// Light shining equally in every direction from a single point
+pub struct PointLight {
+    position: Point3,
+    intensity: Color, // Power per unit solid angle
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &Point3, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity / (distance * distance),
+        })
+    }
+}
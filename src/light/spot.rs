@@ -0,0 +1,70 @@
+use crate::color::Color;
+use crate::light::{Light, LightSample};
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+
+/// Point light restricted to a cone, at full strength inside `inner_angle`
+/// and fading smoothly to nothing at `outer_angle`
+pub struct SpotLight {
+    position: Point3,
+    direction: Vector3, // Unit axis of the cone
+    intensity: Color,   // Power per unit solid angle along the axis
+    cos_inner: f64,
+    cos_outer: f64,
+}
+
+impl SpotLight {
+    /// Angles are measured from the axis, in degrees
+    pub fn new(
+        position: Point3,
+        direction: Vector3,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> Self {
+        let outer_angle = outer_angle.clamp(0.0, 180.0);
+        let inner_angle = inner_angle.clamp(0.0, outer_angle);
+        Self {
+            position,
+            direction: direction.unit_vector(),
+            intensity,
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+        }
+    }
+
+    /// Fraction of the intensity sent along `cos_theta` off the axis
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+
+        let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &Point3, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+
+        let direction = to_light / distance;
+        let falloff = self.falloff(Vector3::dot(&-direction, &self.direction));
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: falloff * self.intensity / (distance * distance),
+        })
+    }
+}
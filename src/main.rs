@@ -1,402 +1,298 @@
-use crate::camera::Camera;
-use crate::color::Color;
-use crate::hittable::HittableList;
-use crate::hittable::constant_medium::ConstantMedium;
-use crate::hittable::quad::{Quad, create_box};
-use crate::hittable::rotate::RotateY;
-use crate::hittable::sphere::Sphere;
-use crate::image::Image;
-use crate::material::dielectric::{Dielectric, DiffuseLight};
-use crate::material::lambertian::Lambertian;
-use crate::material::metal::Metal;
-use crate::texture::checker::CheckerTexture;
-use crate::texture::image::ImageTexture;
-use crate::texture::noise::NoiseTexture;
-use crate::vector::{Point3, Vector3};
-use anyhow::Result;
-use std::sync::Arc;
-
-mod aabb;
-mod camera;
-mod color;
-mod hittable;
-mod image;
-mod interval;
-mod material;
-mod perlin;
-mod ray;
-mod texture;
-mod transform;
-mod vector;
-
-fn main() -> Result<()> {
-    match 5 {
-        1 => spheres()?,
-        2 => quads()?,
-        3 => simple_light()?,
-        4 => cornell_box()?,
-        5 => cornell_box_smoke()?,
-        _ => (),
-    }
-
-    Ok(())
+use anyhow::{Result, bail};
+use clap::{Args, Parser, Subcommand};
+use raytracer::camera::Camera;
+use raytracer::output::{BitDepth, ImageFormat};
+use raytracer::sampler::SamplerKind;
+use raytracer::scene::{Scene, builtin};
+use raytracer::tonemap::Operator;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(version, about = "Renders scenes from Ray Tracing in One Weekend")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
 }
 
-fn spheres() -> Result<()> {
-    let mut world = HittableList::new();
-
-    let material_ground = Arc::new(CheckerTexture::new_colors(
-        0.32,
-        Color::new(0.2, 0.3, 0.1),
-        Color::new(0.9, 0.9, 0.9),
-    ));
-    let earth_texture = Arc::new(ImageTexture::new(Image::from_file("earthmap.jpg")));
-    let material_center = Arc::new(Lambertian::new_texture(earth_texture));
-    let material_left = Arc::new(Dielectric::new(1.5));
-    let material_bubble = Arc::new(Dielectric::new(1. / 1.5));
-    // let material_right = Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 1.));
-    let material_right = Arc::new(Lambertian::new_texture(Arc::new(NoiseTexture::new(40.))));
-
-    world.add(Arc::new(Sphere::new(
-        Point3::new(0.0, -100.5, -1.0),
-        100.0,
-        Arc::new(Lambertian::new_texture(material_ground)),
-    )));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(0.0, 0.0, -1.2),
-        0.5,
-        material_center,
-    )));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(-1.0, 0.0, -1.0),
-        0.5,
-        material_left,
-    )));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(-1.0, 0.0, -1.0),
-        0.4,
-        material_bubble,
-    )));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(1.0, 0.0, -1.0),
-        0.5,
-        material_right,
-    )));
-
-    // let bvh_world = Arc::new(BVHNode::new(&world));
-    // world.clear();
-    // world.add(bvh_world);
-
-    let mut camera = Camera::new();
-
-    camera.aspect_ratio = 16. / 9.;
-    camera.image_width = 400;
-    camera.samples_per_pixel = 100;
-    camera.max_depth = 50;
-    camera.vfov = 20.;
-
-    camera.defocus_angle = 1.0;
-    camera.focus_dist = 3.4;
-
-    camera.lookfrom = Point3::new(-2., 2., 1.);
-    camera.lookat = Point3::new(0., 0., -1.);
-    camera.vup = Vector3::new(0., 1., 0.);
-
-    camera.initialize();
-
-    camera.render(world)?;
-
-    Ok(())
+#[derive(Subcommand)]
+enum Command {
+    /// Render a scene to an image file
+    Render {
+        #[command(flatten)]
+        scene: SceneArgs,
+        #[command(flatten)]
+        camera: CameraArgs,
+    },
+    /// List the built-in scenes
+    ListScenes,
+    /// Print the settings of a scene without rendering it
+    Info {
+        #[command(flatten)]
+        scene: SceneArgs,
+        #[command(flatten)]
+        camera: CameraArgs,
+    },
 }
 
-fn quads() -> Result<()> {
-    let mut world = HittableList::new();
-
-    let back_green = Arc::new(Lambertian::new(Color::new(0.2, 1.0, 0.2)));
-
-    let quad = Arc::new(Quad::new(
-        Point3::new(-2., -1., 1.),
-        Vector3::new(0., 2., 0.),
-        Vector3::new(0., 0., -2.),
-        back_green,
-    ));
-
-    world.add(quad);
-
-    let mut camera = Camera::new();
-
-    camera.aspect_ratio = 1.0;
-    camera.image_width = 400;
-    camera.samples_per_pixel = 100;
-    camera.max_depth = 50;
-    camera.background = Color::new(0.70, 0.80, 1.00);
-
-    camera.vfov = 80.;
-    camera.lookfrom = Point3::new(-5., 0., 0.);
-    camera.lookat = Point3::new(0., 0., 0.);
-    camera.vup = Vector3::new(0., 1., 0.);
-
-    // camera.defocus_angle = 1.0;
-    // camera.focus_dist = 3.4;
-
-    camera.initialize();
-
-    camera.render(world)?;
-
-    Ok(())
+#[derive(Args)]
+struct SceneArgs {
+    /// Name of a built-in scene or path to a TOML scene file
+    #[arg(default_value = "cornell-box-smoke")]
+    scene: String,
 }
 
-fn simple_light() -> Result<()> {
-    let mut world = HittableList::new();
-
-    let texture = Arc::new(NoiseTexture::new(5.));
-    let material_ground = Arc::new(Lambertian::new_texture(texture));
-
-    let material_ball = Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.9));
-    let light = Arc::new(DiffuseLight::from_color(Color::new(4., 4., 4.)));
-
-    world.add(Arc::new(Quad::new(
-        Point3::new(-500., -4., -500.),
-        Vector3::new(1000., 0., 0.),
-        Vector3::new(0., 0., 1000.),
-        material_ground,
-    )));
-
-    world.add(Arc::new(Sphere::new(
-        Point3::new(0., 0., 0.),
-        2.,
-        material_ball,
-    )));
-
-    world.add(Arc::new(Quad::new(
-        Point3::new(-1., 3., -2.),
-        Vector3::new(2., 0., 0.),
-        Vector3::new(0., 0., 2.),
-        light,
-    )));
-
-    let mut camera = Camera::new();
-
-    camera.aspect_ratio = 1.0;
-    camera.image_width = 400;
-    camera.samples_per_pixel = 100;
-    camera.max_depth = 50;
-    camera.background = Color::new(0., 0., 0.);
-
-    camera.vfov = 80.;
-    camera.lookfrom = Point3::new(-5., 0., 0.);
-    camera.lookat = Point3::new(0., 0., 0.);
-    camera.vup = Vector3::new(0., 1., 0.);
-
-    camera.initialize();
-
-    camera.render(world)?;
-
-    Ok(())
+#[derive(Args)]
+struct CameraArgs {
+    /// Image width in pixels, the height follows from the aspect ratio
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    width: Option<i32>,
+    /// Samples per pixel, the most a pixel takes with adaptive sampling
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    spp: Option<i32>,
+    /// Samples every pixel takes before adaptive sampling may stop it, at most
+    /// the samples per pixel
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    min_spp: Option<i32>,
+    /// Relative error at which a pixel stops taking samples, 0 disables
+    /// adaptive sampling
+    #[arg(long)]
+    adaptive_threshold: Option<f64>,
+    /// Maximum number of ray bounces
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    max_depth: Option<i32>,
+    /// Bounces before Russian roulette may end a path
+    #[arg(long)]
+    roulette_depth: Option<i32>,
+    /// Time the shutter opens, objects move from time 0 to 1
+    #[arg(long, allow_negative_numbers = true)]
+    shutter_open: Option<f64>,
+    /// Time the shutter closes, later than the opening for motion blur
+    #[arg(long, allow_negative_numbers = true)]
+    shutter_close: Option<f64>,
+    /// Sample generator: independent, stratified, halton or sobol
+    #[arg(long)]
+    sampler: Option<SamplerKind>,
+    /// Number of worker threads, 0 uses every available core
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Edge length in pixels of the square tiles threads pick up
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    tile_size: Option<i32>,
+    /// Path the rendered image is written to, its extension picks the format
+    /// (.png, .exr, .hdr or .ppm)
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    /// Also write an image showing how many samples every pixel took
+    #[arg(long)]
+    heatmap: Option<PathBuf>,
+    /// Bits per channel of PNG output, 8 or 16
+    #[arg(long)]
+    bit_depth: Option<BitDepth>,
+    /// Exposure compensation in stops, applied before tone mapping
+    #[arg(long, allow_negative_numbers = true)]
+    exposure: Option<f64>,
+    /// Tone mapping operator for PNG and PPM output: clamp, reinhard,
+    /// extended-reinhard, hable or aces
+    #[arg(long)]
+    tone_map: Option<Operator>,
+    /// Radiance mapped to white by the extended-reinhard operator
+    #[arg(long)]
+    white_point: Option<f64>,
+    /// Seed for the random number generators
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Intersect every object in turn instead of building a BVH, for debugging
+    #[arg(long)]
+    no_bvh: bool,
 }
 
-fn cornell_box() -> Result<()> {
-    let mut world = HittableList::new();
-
-    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
-    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
-    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
-    let light = Arc::new(DiffuseLight::from_color(Color::new(15., 15., 15.)));
-
-    // Left wall (green)
-    world.add(Arc::new(Quad::new(
-        Point3::new(555., 0., 0.),
-        Vector3::new(0., 555., 0.),
-        Vector3::new(0., 0., 555.),
-        green.clone(),
-    )));
-
-    // Right wall (red)
-    world.add(Arc::new(Quad::new(
-        Point3::new(0., 0., 0.),
-        Vector3::new(0., 555., 0.),
-        Vector3::new(0., 0., 555.),
-        red.clone(),
-    )));
-
-    // Floor (white)
-    world.add(Arc::new(Quad::new(
-        Point3::new(0., 0., 0.),
-        Vector3::new(555., 0., 0.),
-        Vector3::new(0., 0., 555.),
-        white.clone(),
-    )));
-
-    // Ceiling (white)
-    world.add(Arc::new(Quad::new(
-        Point3::new(555., 555., 555.),
-        Vector3::new(-555., 0., 0.),
-        Vector3::new(0., 0., -555.),
-        white.clone(),
-    )));
-
-    // Back wall (white)
-    world.add(Arc::new(Quad::new(
-        Point3::new(0., 0., 555.),
-        Vector3::new(555., 0., 0.),
-        Vector3::new(0., 555., 0.),
-        white.clone(),
-    )));
-
-    world.add(Arc::new(Quad::new(
-        Point3::new(213., 554., 227.),
-        Vector3::new(130., 0., 0.),
-        Vector3::new(0., 0., 105.),
-        light.clone(),
-    )));
-
-    let tall_box = create_box(
-        Point3::new(265., 0., 295.),
-        Point3::new(430., 330., 460.),
-        white.clone(),
-    );
-    let tall_box_objects = tall_box.objects;
-    for object in tall_box_objects {
-        world.add(Arc::new(RotateY::new(object, -18.0)));
+impl CameraArgs {
+    fn apply(self, camera: &mut Camera) -> Result<()> {
+        if let Some(width) = self.width {
+            camera.image_width = width;
+        }
+        if let Some(spp) = self.spp {
+            camera.samples_per_pixel = spp;
+        }
+        if let Some(min_spp) = self.min_spp {
+            if min_spp > camera.samples_per_pixel {
+                bail!(
+                    "--min-spp {} is more than the {} samples per pixel",
+                    min_spp,
+                    camera.samples_per_pixel
+                );
+            }
+            camera.min_samples_per_pixel = min_spp;
+        }
+        if let Some(adaptive_threshold) = self.adaptive_threshold {
+            camera.adaptive_threshold = adaptive_threshold;
+        }
+        if let Some(max_depth) = self.max_depth {
+            camera.max_depth = max_depth;
+        }
+        if let Some(roulette_depth) = self.roulette_depth {
+            camera.roulette_depth = roulette_depth;
+        }
+        if let Some(shutter_open) = self.shutter_open {
+            camera.shutter_open = shutter_open;
+        }
+        if let Some(shutter_close) = self.shutter_close {
+            camera.shutter_close = shutter_close;
+        }
+        if let Some(sampler) = self.sampler {
+            camera.sampler = sampler;
+        }
+        if let Some(threads) = self.threads {
+            camera.threads = threads;
+        }
+        if let Some(tile_size) = self.tile_size {
+            camera.tile_size = tile_size;
+        }
+        if let Some(output) = self.output {
+            camera.output = output;
+        }
+        if let Some(heatmap) = self.heatmap {
+            camera.heatmap = Some(heatmap);
+        }
+        if let Some(bit_depth) = self.bit_depth {
+            camera.bit_depth = bit_depth;
+        }
+        if let Some(exposure) = self.exposure {
+            camera.tone_mapper.exposure = exposure;
+        }
+        if let Some(operator) = self.tone_map {
+            camera.tone_mapper.operator = operator;
+        }
+        if let Some(white_point) = self.white_point {
+            camera.tone_mapper.white_point = white_point;
+        }
+        if let Some(seed) = self.seed {
+            camera.seed = seed;
+        }
+        if self.no_bvh {
+            camera.use_bvh = false;
+        }
+        Ok(())
     }
+}
 
-    let short_box = create_box(
-        Point3::new(130., 0., 65.),
-        Point3::new(295., 165., 230.),
-        white.clone(),
-    );
-    let short_box_objects = short_box.objects;
-    for object in short_box_objects {
-        let rotated_object = Arc::new(RotateY::new(object, 15.0));
-        world.add(rotated_object);
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Render { scene, camera } => {
+            let mut scene = load_scene(&scene.scene)?;
+            camera.apply(&mut scene.camera)?;
+            scene.render()
+        }
+        Command::ListScenes => {
+            for scene in builtin::SCENES {
+                println!("{:<20} {}", scene.name, scene.description);
+            }
+            Ok(())
+        }
+        Command::Info {
+            scene: args,
+            camera,
+        } => {
+            let mut scene = load_scene(&args.scene)?;
+            camera.apply(&mut scene.camera)?;
+            print_info(&args.scene, &mut scene);
+            Ok(())
+        }
     }
-
-    let mut camera = Camera::new();
-
-    camera.aspect_ratio = 1.0;
-    camera.image_width = 400;
-    camera.samples_per_pixel = 100;
-    camera.max_depth = 50;
-    camera.background = Color::new(0., 0., 0.);
-
-    camera.vfov = 40.;
-    camera.lookfrom = Point3::new(278., 278., -800.);
-    camera.lookat = Point3::new(278., 278., 0.);
-    camera.vup = Vector3::new(0., 1., 0.);
-
-    camera.initialize();
-
-    camera.render(world)?;
-
-    Ok(())
 }
 
-fn cornell_box_smoke() -> Result<()> {
-    let mut world = HittableList::new();
-
-    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
-    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
-    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
-    let light = Arc::new(DiffuseLight::from_color(Color::new(15., 15., 15.)));
-
-    // Left wall (green)
-    world.add(Arc::new(Quad::new(
-        Point3::new(555., 0., 0.),
-        Vector3::new(0., 555., 0.),
-        Vector3::new(0., 0., 555.),
-        green.clone(),
-    )));
-
-    // Right wall (red)
-    world.add(Arc::new(Quad::new(
-        Point3::new(0., 0., 0.),
-        Vector3::new(0., 555., 0.),
-        Vector3::new(0., 0., 555.),
-        red.clone(),
-    )));
-
-    // Floor (white)
-    world.add(Arc::new(Quad::new(
-        Point3::new(0., 0., 0.),
-        Vector3::new(555., 0., 0.),
-        Vector3::new(0., 0., 555.),
-        white.clone(),
-    )));
+/// Resolves a scene argument to a built-in scene or, failing that, a scene file
+fn load_scene(name: &str) -> Result<Scene> {
+    if let Some(scene) = builtin::find(name) {
+        return Ok((scene.build)());
+    }
 
-    // Ceiling (white)
-    world.add(Arc::new(Quad::new(
-        Point3::new(555., 555., 555.),
-        Vector3::new(-555., 0., 0.),
-        Vector3::new(0., 0., -555.),
-        white.clone(),
-    )));
+    let path = Path::new(name);
+    if path.exists() {
+        return Scene::from_file(path);
+    }
 
-    // Back wall (white)
-    world.add(Arc::new(Quad::new(
-        Point3::new(0., 0., 555.),
-        Vector3::new(555., 0., 0.),
-        Vector3::new(0., 555., 0.),
-        white.clone(),
-    )));
+    bail!(
+        "'{}' is neither a built-in scene nor a scene file (see `raytracer list-scenes`)",
+        name
+    )
+}
 
-    // Light source
-    world.add(Arc::new(Quad::new(
-        Point3::new(213., 554., 227.),
-        Vector3::new(130., 0., 0.),
-        Vector3::new(0., 0., 105.),
-        light.clone(),
-    )));
+fn print_info(name: &str, scene: &mut Scene) {
+    let camera = &mut scene.camera;
+    camera.initialize();
 
-    let tall_box_boundary = create_box(
-        Point3::new(265., 0., 295.),
-        Point3::new(430., 330., 460.),
-        white.clone(),
+    println!("Scene:             {}", name);
+    println!(
+        "Resolution:        {}x{}",
+        camera.image_width,
+        camera.image_height()
     );
-
-    let mut rotated_tall_box = HittableList::new();
-    for object in tall_box_boundary.objects {
-        rotated_tall_box.add(Arc::new(RotateY::new(object, -18.0)));
+    if camera.adaptive_threshold > 0.0 {
+        println!(
+            "Samples per pixel: {} to {} (adaptive, threshold {})",
+            camera.min_samples(),
+            camera.samples_per_pixel,
+            camera.adaptive_threshold
+        );
+    } else {
+        println!("Samples per pixel: {}", camera.samples_per_pixel);
     }
-
-    world.add(Arc::new(ConstantMedium::from_color(
-        Arc::new(rotated_tall_box),
-        0.01,
-        Color::new(0.0, 0.0, 0.0),
-    )));
-
-    let short_box_boundary = create_box(
-        Point3::new(130., 0., 65.),
-        Point3::new(295., 165., 230.),
-        white.clone(),
+    println!("Max depth:         {}", camera.max_depth);
+    println!("Roulette depth:    {}", camera.roulette_depth);
+    println!("Sampler:           {}", camera.sampler);
+    println!("Vertical FOV:      {}", camera.vfov);
+    println!("Look from:         {}", camera.lookfrom);
+    println!("Look at:           {}", camera.lookat);
+    println!("Defocus angle:     {}", camera.defocus_angle);
+    println!("Focus distance:    {}", camera.focus_dist);
+    if camera.shutter_close > camera.shutter_open {
+        println!(
+            "Shutter:           {} to {}",
+            camera.shutter_open, camera.shutter_close
+        );
+    } else {
+        println!(
+            "Shutter:           {} (no motion blur)",
+            camera.shutter_open
+        );
+    }
+    match camera.environment {
+        Some(_) => println!("Background:        environment"),
+        None => println!("Background:        {}", camera.background),
+    }
+    println!("Objects:           {}", scene.world.objects.len());
+    if !scene.analytic_lights.is_empty() {
+        println!("Analytic lights:   {}", scene.analytic_lights.len());
+    }
+    println!(
+        "BVH:               {}",
+        if camera.use_bvh { "on" } else { "off" }
     );
-
-    let mut rotated_short_box = HittableList::new();
-    for object in short_box_boundary.objects {
-        rotated_short_box.add(Arc::new(RotateY::new(object, 15.0)));
+    if camera.threads > 0 {
+        println!("Threads:           {}", camera.threads);
+    } else {
+        println!("Threads:           {} (all cores)", num_cpus::get());
+    }
+    println!("Tile size:         {}", camera.tile_size);
+    println!("Seed:              {}", camera.seed);
+    println!("Output:            {}", camera.output.display());
+    if let Some(heatmap) = &camera.heatmap {
+        println!("Sample heatmap:    {}", heatmap.display());
+    }
+    match ImageFormat::from_path(&camera.output) {
+        Ok(ImageFormat::Png) => println!("Format:            PNG, {}-bit", camera.bit_depth),
+        Ok(format) => println!("Format:            {}", format),
+        Err(e) => println!("Format:            {}", e),
+    }
+    let tone_mapper = &camera.tone_mapper;
+    println!("Exposure:          {:+} EV", tone_mapper.exposure);
+    if tone_mapper.operator == Operator::ExtendedReinhard {
+        println!(
+            "Tone mapping:      {} (white point {})",
+            tone_mapper.operator, tone_mapper.white_point
+        );
+    } else {
+        println!("Tone mapping:      {}", tone_mapper.operator);
     }
-
-    world.add(Arc::new(ConstantMedium::from_color(
-        Arc::new(rotated_short_box),
-        0.01,
-        Color::new(1.0, 1.0, 1.0),
-    )));
-
-    let mut camera = Camera::new();
-
-    camera.aspect_ratio = 1.0;
-    camera.image_width = 400;
-    camera.samples_per_pixel = 250;
-    camera.max_depth = 50;
-    camera.background = Color::new(0., 0., 0.);
-
-    camera.vfov = 40.;
-    camera.lookfrom = Point3::new(278., 278., -800.);
-    camera.lookat = Point3::new(278., 278., 0.);
-    camera.vup = Vector3::new(0., 1., 0.);
-
-    camera.initialize();
-
-    camera.render(world)?;
-
-    Ok(())
 }
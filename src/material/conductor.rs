@@ -0,0 +1,183 @@
+use crate::color::Color;
+use crate::hittable::HitRecord;
+use crate::material::Material;
+use crate::material::microfacet::{TrowbridgeReitz, fresnel_conductor, reflect};
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vector::Vector3;
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::str::FromStr;
+
+/// Metal with a complex index of refraction, reflecting through a GGX
+/// microfacet lobe. A roughness of zero gives a perfect mirror.
+pub struct Conductor {
+    eta: Color, // Real part of the index of refraction, per channel
+    k: Color,   // Absorption coefficient, per channel
+    distribution: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::new(roughness),
+        }
+    }
+
+    pub fn from_preset(preset: ConductorPreset, roughness: f64) -> Self {
+        let (eta, k) = preset.eta_k();
+        Self::new(eta, k, roughness)
+    }
+
+    /// Outgoing direction in the local frame around the normal, `None` when
+    /// the ray arrives from below the shading normal
+    fn local_wo(ray_in: &Ray, frame: &Onb) -> Option<Vector3> {
+        let wo = frame.to_local(&-ray_in.get_direction().unit_vector());
+        (wo.z() > 0.0).then_some(wo)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let frame = Onb::new(&hit_record.normal);
+        let Some(wo) = Self::local_wo(ray_in, &frame) else {
+            return false;
+        };
+
+        if self.distribution.is_smooth() {
+            let wi = Vector3::new(-wo.x(), -wo.y(), wo.z());
+            *attenuation = fresnel_conductor(wo.z(), &self.eta, &self.k);
+            *scattered = Ray::new_at_time(hit_record.p, frame.transform(&wi), ray_in.get_time());
+            return true;
+        }
+
+        let wm = self.distribution.sample_wm(&wo, sampler.get_2d());
+        let wi = reflect(&wo, &wm);
+        if wi.z() <= 0.0 {
+            return false;
+        }
+
+        // f cos / pdf with pdf = D_wo(wm) / (4 wo.wm), most terms cancel
+        let fresnel = fresnel_conductor(Vector3::dot(&wo, &wm).abs(), &self.eta, &self.k);
+        *attenuation = self.distribution.g(&wo, &wi) / self.distribution.g1(&wo) * fresnel;
+        *scattered = Ray::new_at_time(hit_record.p, frame.transform(&wi), ray_in.get_time());
+        true
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+
+        let frame = Onb::new(&hit_record.normal);
+        let Some(wo) = Self::local_wo(ray_in, &frame) else {
+            return 0.0;
+        };
+        let wi = frame.to_local(&scattered.get_direction().unit_vector());
+        if wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let wm = (wo + wi).unit_vector();
+        self.distribution.pdf(&wo, &wm) / (4.0 * Vector3::dot(&wo, &wm).abs())
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        if self.distribution.is_smooth() {
+            return black;
+        }
+
+        let frame = Onb::new(&hit_record.normal);
+        let Some(wo) = Self::local_wo(ray_in, &frame) else {
+            return black;
+        };
+        let wi = frame.to_local(&scattered.get_direction().unit_vector());
+        if wi.z() <= 0.0 {
+            return black;
+        }
+
+        let wm = (wo + wi).unit_vector();
+        let fresnel = fresnel_conductor(Vector3::dot(&wo, &wm).abs(), &self.eta, &self.k);
+        // D G F / (4 cos_o cos_i), times cos_i
+        self.distribution.d(&wm) * self.distribution.g(&wo, &wi) / (4.0 * wo.z()) * fresnel
+    }
+}
+
+/// Measured metals, their spectral indices reduced to red, green and blue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConductorPreset {
+    Gold,
+    Copper,
+    Aluminum,
+    Silver,
+}
+
+impl ConductorPreset {
+    pub const ALL: [ConductorPreset; 4] = [Self::Gold, Self::Copper, Self::Aluminum, Self::Silver];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gold => "gold",
+            Self::Copper => "copper",
+            Self::Aluminum => "aluminum",
+            Self::Silver => "silver",
+        }
+    }
+
+    /// Real and imaginary parts of the index of refraction
+    pub fn eta_k(&self) -> (Color, Color) {
+        match self {
+            Self::Gold => (
+                Color::new(0.143, 0.374, 1.442),
+                Color::new(3.983, 2.385, 1.603),
+            ),
+            Self::Copper => (
+                Color::new(0.200, 0.924, 1.102),
+                Color::new(3.912, 2.452, 2.142),
+            ),
+            Self::Aluminum => (
+                Color::new(1.657, 0.880, 0.521),
+                Color::new(9.224, 6.270, 4.837),
+            ),
+            Self::Silver => (
+                Color::new(0.155, 0.117, 0.138),
+                Color::new(4.828, 3.122, 2.147),
+            ),
+        }
+    }
+}
+
+impl FromStr for ConductorPreset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|preset| preset.name()).collect();
+                anyhow!(
+                    "unknown conductor '{}', expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for ConductorPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
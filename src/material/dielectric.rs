@@ -1,11 +1,13 @@
 use crate::color::Color;
 use crate::hittable::HitRecord;
 use crate::material::Material;
+use crate::material::microfacet::TrowbridgeReitz;
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::texture::Texture;
 use crate::texture::solid::SolidTexture;
 use crate::vector::{Point3, Vector3};
-use rand::random_range;
 use std::sync::Arc;
 
 pub struct Dielectric {
@@ -33,6 +35,7 @@ impl Material for Dielectric {
         hit_record: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool {
         *attenuation = Color::new(1.0, 1.0, 1.0);
         let ir = if hit_record.front_face {
@@ -46,18 +49,99 @@ impl Material for Dielectric {
         let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
 
         let cannot_refract = ir * sin_theta > 1.0;
-        let direction = if cannot_refract
-            || Dielectric::reflectance(cos_theta, ir) > random_range(0f64..1f64)
-        {
-            Vector3::reflect(&unit_direction, &hit_record.normal)
+        let direction =
+            if cannot_refract || Dielectric::reflectance(cos_theta, ir) > sampler.get_1d() {
+                Vector3::reflect(&unit_direction, &hit_record.normal)
+            } else {
+                Vector3::refract(&unit_direction, &hit_record.normal, ir)
+            };
+
+        *scattered = Ray::new_at_time(hit_record.p, direction, ray_in.get_time());
+
+        true
+    }
+}
+
+/// Glass with a GGX microfacet surface, which blurs both what it reflects
+/// and what it lets through. Which of the two a ray does is picked by the
+/// Fresnel term of the microfacet it lands on.
+pub struct RoughDielectric {
+    ir: f64,
+    distribution: TrowbridgeReitz,
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: f64, roughness: f64) -> Self {
+        Self {
+            ir: index_of_refraction,
+            distribution: TrowbridgeReitz::new(roughness),
+        }
+    }
+
+    /// The local frame around the normal, the outgoing direction in it, and
+    /// the ratio of the index beyond the surface to the one in front of it
+    fn local(&self, ray_in: &Ray, hit_record: &HitRecord) -> (Onb, Vector3, f64) {
+        let frame = Onb::new(&hit_record.normal);
+        let wo = frame.to_local(&-ray_in.get_direction().unit_vector());
+        let eta = if hit_record.front_face {
+            self.ir
         } else {
-            Vector3::refract(&unit_direction, &hit_record.normal, ir)
+            1.0 / self.ir
         };
+        (frame, wo, eta)
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let (frame, wo, eta) = self.local(ray_in, hit_record);
+        if wo.z() <= 0.0 {
+            return false;
+        }
 
-        *scattered = Ray::new(hit_record.p, direction);
+        let Some(wi) = self.distribution.sample_dielectric(&wo, eta, sampler) else {
+            return false;
+        };
 
+        // Picking reflection or refraction by the Fresnel term cancels it,
+        // and sampling visible normals leaves only the masking of `wi`
+        *attenuation = if self.distribution.is_smooth() {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            let weight = self.distribution.g(&wo, &wi) / self.distribution.g1(&wo);
+            Color::new(weight, weight, weight)
+        };
+        *scattered = Ray::new_at_time(hit_record.p, frame.transform(&wi), ray_in.get_time());
         true
     }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+
+        let (frame, wo, eta) = self.local(ray_in, hit_record);
+        let wi = frame.to_local(&scattered.get_direction().unit_vector());
+        self.distribution.dielectric_pdf(&wo, &wi, eta)
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        if self.distribution.is_smooth() {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let (frame, wo, eta) = self.local(ray_in, hit_record);
+        let wi = frame.to_local(&scattered.get_direction().unit_vector());
+        let value = self.distribution.dielectric_eval(&wo, &wi, eta);
+        Color::new(value, value, value)
+    }
 }
 
 pub struct DiffuseLight {
@@ -79,10 +163,11 @@ impl DiffuseLight {
 impl Material for DiffuseLight {
     fn scatter(
         &self,
-        ray_in: &Ray,
-        hit_record: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
+        _ray_in: &Ray,
+        _hit_record: &HitRecord,
+        _attenuation: &mut Color,
+        _scattered: &mut Ray,
+        _sampler: &mut dyn Sampler,
     ) -> bool {
         false
     }
@@ -1,25 +1,32 @@
+//! The book's isotropic material, a volume that scatters evenly in every
+//! direction.
+
 use crate::color::Color;
 use crate::hittable::HitRecord;
 use crate::material::Material;
+use crate::material::phase::PhaseFunction;
+use crate::material::volume::Volume;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::texture::Texture;
-use crate::texture::solid::SolidTexture;
-use crate::vector::Vector3;
 use std::sync::Arc;
 
-/// Isotropic material for volumetric scattering (used in constant medium)
+/// Isotropic material for volumetric scattering, a `Volume` with the
+/// isotropic phase function
 pub struct Isotropic {
-    texture: Arc<dyn Texture>,
+    volume: Volume,
 }
 
 impl Isotropic {
     pub fn new(texture: Arc<dyn Texture>) -> Self {
-        Self { texture }
+        Self {
+            volume: Volume::new(texture, PhaseFunction::Isotropic),
+        }
     }
 
     pub fn from_color(albedo: Color) -> Self {
         Self {
-            texture: Arc::new(SolidTexture::new(albedo)),
+            volume: Volume::from_color(albedo, PhaseFunction::Isotropic),
         }
     }
 }
@@ -27,15 +34,21 @@ impl Isotropic {
 impl Material for Isotropic {
     fn scatter(
         &self,
-        _ray_in: &Ray,
+        ray_in: &Ray,
         hit_record: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool {
-        *scattered = Ray::new(hit_record.p, Vector3::random_unit_vector());
-        *attenuation = self
-            .texture
-            .value(hit_record.u, hit_record.v, &hit_record.p);
-        true
+        self.volume
+            .scatter(ray_in, hit_record, attenuation, scattered, sampler)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        self.volume.scattering_pdf(ray_in, hit_record, scattered)
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        self.volume.eval(ray_in, hit_record, scattered)
     }
 }
@@ -1,10 +1,13 @@
 use crate::color::Color;
 use crate::hittable::HitRecord;
 use crate::material::Material;
+use crate::pdf::{CosinePdf, Pdf};
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::texture::Texture;
 use crate::texture::solid::SolidTexture;
 use crate::vector::Vector3;
+use std::f64::consts::PI;
 use std::sync::Arc;
 
 pub struct Lambertian {
@@ -30,15 +33,27 @@ impl Material for Lambertian {
         hit_record: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool {
-        let mut scatter_direction = hit_record.normal + Vector3::random_unit_vector();
+        let scatter_direction = CosinePdf::new(&hit_record.normal).generate(sampler);
 
-        if scatter_direction.near_zero() {
-            scatter_direction = hit_record.normal;
-        }
-
-        *scattered = Ray::new(hit_record.p, scatter_direction);
+        // Sampling proportional to the cosine cancels it against the pdf,
+        // leaving just the albedo as the weight of the scattered ray
+        *scattered = Ray::new_at_time(hit_record.p, scatter_direction, ray_in.get_time());
         *attenuation = self.tex.value(hit_record.u, hit_record.v, &hit_record.p);
         true
     }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        CosinePdf::new(&hit_record.normal).value(&scattered.get_direction())
+    }
+
+    fn eval(&self, _ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let cosine = Vector3::dot(&hit_record.normal, &scattered.get_direction().unit_vector());
+        if cosine <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        cosine / PI * self.tex.value(hit_record.u, hit_record.v, &hit_record.p)
+    }
 }
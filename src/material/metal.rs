@@ -1,6 +1,7 @@
 use crate::color::Color;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vector::Vector3;
 
 pub struct Metal {
@@ -21,10 +22,11 @@ impl Material for Metal {
         hit_record: &crate::hittable::HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool {
         let mut reflected = Vector3::reflect(&ray_in.get_direction(), &hit_record.normal);
-        reflected = reflected.unit_vector() + (self.fuzz * Vector3::random_unit_vector());
-        *scattered = Ray::new(hit_record.p, reflected);
+        reflected = reflected.unit_vector() + (self.fuzz * Vector3::random_unit_vector(sampler));
+        *scattered = Ray::new_at_time(hit_record.p, reflected, ray_in.get_time());
         *attenuation = self.albedo;
 
         Vector3::dot(&reflected, &hit_record.normal) > 0.0
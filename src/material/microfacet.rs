@@ -0,0 +1,330 @@
+//! Building blocks shared by the microfacet materials.
+//!
+//! Directions are given in a local frame around the shading normal, which is
+//! +z, and point away from the surface.
+
+use crate::color::Color;
+use crate::sampler::Sampler;
+use crate::vector::Vector3;
+use std::f64::consts::PI;
+
+/// Below this `alpha` a surface is treated as perfectly smooth
+const SMOOTH_ALPHA: f64 = 1e-3;
+
+/// Trowbridge-Reitz (GGX) distribution of microfacet normals, with Smith's
+/// height-correlated masking-shadowing
+#[derive(Clone, Copy, Debug)]
+pub struct TrowbridgeReitz {
+    alpha: f64,
+}
+
+impl TrowbridgeReitz {
+    /// Roughness in [0, 1], squared to get `alpha` so that it reads roughly
+    /// linearly
+    pub fn new(roughness: f64) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Self {
+            alpha: roughness * roughness,
+        }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Whether the lobe is so narrow it should be treated as a mirror
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < SMOOTH_ALPHA
+    }
+
+    /// Density of microfacet normals `wm`, per unit of projected area
+    pub fn d(&self, wm: &Vector3) -> f64 {
+        let cos2 = wm.z() * wm.z();
+        if cos2 <= 0.0 {
+            return 0.0;
+        }
+
+        let tan2 = (1.0 - cos2) / cos2;
+        let alpha2 = self.alpha * self.alpha;
+        let e = 1.0 + tan2 / alpha2;
+        1.0 / (PI * alpha2 * cos2 * cos2 * e * e)
+    }
+
+    /// Smith's auxiliary function, the masked microfacet area per visible area
+    fn lambda(&self, w: &Vector3) -> f64 {
+        let cos2 = w.z() * w.z();
+        if cos2 <= 0.0 {
+            return f64::INFINITY;
+        }
+
+        let tan2 = (1.0 - cos2) / cos2;
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of microfacets visible from `w`
+    pub fn g1(&self, w: &Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi`
+    pub fn g(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of the normals `sample_wm` returns for `w`
+    pub fn pdf(&self, w: &Vector3, wm: &Vector3) -> f64 {
+        if w.z() == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z().abs() * self.d(wm) * Vector3::dot(w, wm).abs()
+    }
+
+    /// Picks a microfacet normal among those visible from `w` (Heitz,
+    /// "Sampling the GGX Distribution of Visible Normals")
+    pub fn sample_wm(&self, w: &Vector3, (u1, u2): (f64, f64)) -> Vector3 {
+        // Stretch the view direction so the distribution becomes a hemisphere
+        let mut wh = Vector3::new(self.alpha * w.x(), self.alpha * w.y(), w.z()).unit_vector();
+        if wh.z() < 0.0 {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z() < 0.99999 {
+            Vector3::cross(&Vector3::new(0.0, 0.0, 1.0), &wh).unit_vector()
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = Vector3::cross(&wh, &t1);
+
+        // Uniform point on the disk, squeezed onto the visible half of it
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let (px, py) = (r * phi.cos(), r * phi.sin());
+        let h = (1.0 - px * px).sqrt();
+        let s = (1.0 + wh.z()) / 2.0;
+        let py = (1.0 - s) * h + s * py;
+        let pz = (1.0 - px * px - py * py).max(0.0).sqrt();
+
+        let nh = px * t1 + py * t2 + pz * wh;
+        Vector3::new(self.alpha * nh.x(), self.alpha * nh.y(), nh.z().max(1e-6)).unit_vector()
+    }
+
+    /// Density of `wi` when `wo` is mirrored about a sampled visible normal
+    pub fn reflection_pdf(&self, wo: &Vector3, wi: &Vector3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let wm = (*wo + *wi).unit_vector();
+        self.pdf(wo, &wm) / (4.0 * Vector3::dot(wo, &wm).abs())
+    }
+
+    /// Reflects or refracts `wo` through a dielectric interface, picking
+    /// between the two by the Fresnel term of a sampled visible normal.
+    /// `eta` is the ratio of the index below the surface to the one above.
+    pub fn sample_dielectric(
+        &self,
+        wo: &Vector3,
+        eta: f64,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Vector3> {
+        let wm = if self.is_smooth() {
+            Vector3::new(0.0, 0.0, 1.0)
+        } else {
+            self.sample_wm(wo, sampler.get_2d())
+        };
+        let reflectance = fresnel_dielectric(Vector3::dot(wo, &wm), eta);
+
+        if sampler.get_1d() < reflectance {
+            Some(reflect(wo, &wm)).filter(|wi| wi.z() > 0.0)
+        } else {
+            refract(wo, &wm, eta).filter(|wi| wi.z() < 0.0)
+        }
+    }
+
+    /// Density with which `sample_dielectric` picks `wi`
+    pub fn dielectric_pdf(&self, wo: &Vector3, wi: &Vector3, eta: f64) -> f64 {
+        let Some(wm) = dielectric_half_vector(wo, wi, eta) else {
+            return 0.0;
+        };
+
+        let reflectance = fresnel_dielectric(Vector3::dot(wo, &wm), eta);
+        let normal_pdf = self.pdf(wo, &wm);
+        if wi.z() > 0.0 {
+            normal_pdf / (4.0 * Vector3::dot(wo, &wm).abs()) * reflectance
+        } else {
+            let denom = Vector3::dot(wi, &wm) + Vector3::dot(wo, &wm) / eta;
+            normal_pdf * Vector3::dot(wi, &wm).abs() / (denom * denom) * (1.0 - reflectance)
+        }
+    }
+
+    /// The BSDF of a rough dielectric interface times |cos_i|, which cancels
+    /// the cos_i in its denominator
+    pub fn dielectric_eval(&self, wo: &Vector3, wi: &Vector3, eta: f64) -> f64 {
+        let Some(wm) = dielectric_half_vector(wo, wi, eta) else {
+            return 0.0;
+        };
+
+        let reflectance = fresnel_dielectric(Vector3::dot(wo, &wm), eta);
+        let dg = self.d(&wm) * self.g(wo, wi);
+        if wi.z() > 0.0 {
+            dg * reflectance / (4.0 * wo.z())
+        } else {
+            let denom = Vector3::dot(wi, &wm) + Vector3::dot(wo, &wm) / eta;
+            dg * (1.0 - reflectance)
+                * (Vector3::dot(wi, &wm) * Vector3::dot(wo, &wm) / (wo.z() * denom * denom)).abs()
+        }
+    }
+}
+
+/// Microfacet normal that turns `wo` into `wi`, by reflection when both are
+/// on the same side and by refraction otherwise. `None` when that microfacet
+/// would face away from either direction.
+fn dielectric_half_vector(wo: &Vector3, wi: &Vector3, eta: f64) -> Option<Vector3> {
+    let etap = if wi.z() > 0.0 { 1.0 } else { eta };
+    let wm = (etap * *wi + *wo).unit_vector();
+    let wm = if wm.z() < 0.0 { -wm } else { wm };
+
+    if wi.z() == 0.0
+        || wm.near_zero()
+        || Vector3::dot(&wm, wi) * wi.z() < 0.0
+        || Vector3::dot(&wm, wo) * wo.z() < 0.0
+    {
+        return None;
+    }
+    Some(wm)
+}
+
+/// Unpolarized Fresnel reflectance of a dielectric interface, for light
+/// arriving at `cos_theta_i` from the side with the normal and `eta` the
+/// ratio of the index on the far side to the near side
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let (cos_theta_i, eta) = if cos_theta_i < 0.0 {
+        (-cos_theta_i, 1.0 / eta)
+    } else {
+        (cos_theta_i, eta)
+    };
+    let cos_theta_i = cos_theta_i.min(1.0);
+
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Fresnel reflectance of a conductor with complex index `eta + i k`, per
+/// colour channel
+pub fn fresnel_conductor(cos_theta_i: f64, eta: &Color, k: &Color) -> Color {
+    let channel = |axis: usize| fresnel_complex(cos_theta_i, Complex::new(eta[axis], k[axis]));
+    Color::new(channel(0), channel(1), channel(2))
+}
+
+fn fresnel_complex(cos_theta_i: f64, eta: Complex) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_i = 1.0 - cos_theta_i * cos_theta_i;
+    let sin2_theta_t = Complex::new(sin2_theta_i, 0.0) / (eta * eta);
+    let cos_theta_t = (Complex::new(1.0, 0.0) - sin2_theta_t).sqrt();
+    let cos_i = Complex::new(cos_theta_i, 0.0);
+
+    let r_parallel = (eta * cos_i - cos_theta_t) / (eta * cos_i + cos_theta_t);
+    let r_perpendicular = (cos_i - eta * cos_theta_t) / (cos_i + eta * cos_theta_t);
+    (r_parallel.norm() + r_perpendicular.norm()) / 2.0
+}
+
+/// Just enough complex arithmetic for the conductor Fresnel term
+#[derive(Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    /// Squared magnitude
+    fn norm(&self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Principal square root
+    fn sqrt(&self) -> Self {
+        let n = self.norm().sqrt();
+        if n == 0.0 {
+            return Self::new(0.0, 0.0);
+        }
+
+        let t1 = (0.5 * (n + self.re.abs())).sqrt();
+        let t2 = 0.5 * self.im / t1;
+        if self.re >= 0.0 {
+            Self::new(t1, t2)
+        } else {
+            Self::new(t2.abs(), t1.copysign(self.im))
+        }
+    }
+}
+
+impl std::ops::Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl std::ops::Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl std::ops::Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl std::ops::Div for Complex {
+    type Output = Complex;
+    fn div(self, other: Complex) -> Complex {
+        let scale = 1.0 / other.norm();
+        Complex::new(
+            scale * (self.re * other.re + self.im * other.im),
+            scale * (self.im * other.re - self.re * other.im),
+        )
+    }
+}
+
+/// Mirror image of `w` about the microfacet normal `wm`
+pub fn reflect(w: &Vector3, wm: &Vector3) -> Vector3 {
+    -*w + 2.0 * Vector3::dot(w, wm) * *wm
+}
+
+/// Direction `w` bends into when crossing the microfacet with normal `wm`,
+/// `eta` being the ratio of the index on the far side to the near side.
+/// `None` under total internal reflection.
+pub fn refract(w: &Vector3, wm: &Vector3, eta: f64) -> Option<Vector3> {
+    let (wm, eta) = if Vector3::dot(w, wm) < 0.0 {
+        (-*wm, 1.0 / eta)
+    } else {
+        (*wm, eta)
+    };
+
+    let cos_theta_i = Vector3::dot(w, &wm);
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i).max(0.0) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    Some(-*w / eta + (cos_theta_i / eta - cos_theta_t) * wm)
+}
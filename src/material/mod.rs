@@ -1,11 +1,17 @@
+pub mod conductor;
 pub mod dielectric;
 pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod microfacet;
+pub mod phase;
+pub mod principled;
+pub mod volume;
 
 use crate::color::Color;
 use crate::hittable::HitRecord;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vector::Point3;
 
 pub trait Material: Send + Sync {
@@ -15,8 +21,22 @@ pub trait Material: Send + Sync {
         hit_record: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
     ) -> bool;
 
+    /// Density, in solid angle, with which `scatter` picks the direction of
+    /// `scattered`. Zero marks a specular material whose directions can't be
+    /// reached by sampling a light.
+    fn scattering_pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    /// The BSDF times the cosine term for light leaving along `scattered`,
+    /// used to weight directions that were not picked by `scatter`
+    fn eval(&self, _ray_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
     fn emitted(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
         Color::new(0.0, 0.0, 0.0)
     }
@@ -30,13 +50,20 @@ impl DefaultMaterial {
     }
 }
 
+impl Default for DefaultMaterial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Material for DefaultMaterial {
     fn scatter(
         &self,
-        ray_in: &Ray,
-        hit_record: &HitRecord,
-        attenuation: &mut Color,
-        scattered: &mut Ray,
+        _ray_in: &Ray,
+        _hit_record: &HitRecord,
+        _attenuation: &mut Color,
+        _scattered: &mut Ray,
+        _sampler: &mut dyn Sampler,
     ) -> bool {
         false
     }
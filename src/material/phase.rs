@@ -0,0 +1,99 @@
+//! Phase functions, the angular distribution of light scattered inside a
+//! participating medium.
+//!
+//! They are functions of the cosine of the angle between the direction a ray
+//! was travelling and the one it leaves along, so positive cosines mean
+//! forward scattering. Each is sampled exactly, so its value is also the
+//! density of the directions `sample` picks.
+
+use crate::onb::Onb;
+use crate::sampler::Sampler;
+use crate::vector::Vector3;
+use std::f64::consts::PI;
+
+/// Below this asymmetry a Henyey-Greenstein lobe is sampled as isotropic
+const ISOTROPIC_G: f64 = 1e-3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PhaseFunction {
+    /// The same in every direction
+    #[default]
+    Isotropic,
+    /// Henyey-Greenstein lobe whose mean cosine `g`, in (-1, 1), is positive
+    /// for forward scattering such as fog and negative for backward
+    HenyeyGreenstein { g: f64 },
+    /// Mix of two Henyey-Greenstein lobes, `weight` of the first, such as a
+    /// strong forward lobe with a weak backward one for clouds
+    DoubleHenyeyGreenstein { g1: f64, g2: f64, weight: f64 },
+    /// Scattering by particles much smaller than the wavelength, like air
+    Rayleigh,
+}
+
+impl PhaseFunction {
+    /// Density, in solid angle, of turning `cos_theta` away from the
+    /// direction of travel
+    pub fn value(&self, cos_theta: f64) -> f64 {
+        match *self {
+            PhaseFunction::Isotropic => 1.0 / (4.0 * PI),
+            PhaseFunction::HenyeyGreenstein { g } => henyey_greenstein(g, cos_theta),
+            PhaseFunction::DoubleHenyeyGreenstein { g1, g2, weight } => {
+                weight * henyey_greenstein(g1, cos_theta)
+                    + (1.0 - weight) * henyey_greenstein(g2, cos_theta)
+            }
+            PhaseFunction::Rayleigh => 3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta),
+        }
+    }
+
+    /// Density of leaving along `scattered` after travelling along `direction`
+    pub fn pdf(&self, direction: &Vector3, scattered: &Vector3) -> f64 {
+        let cos_theta = Vector3::dot(&direction.unit_vector(), &scattered.unit_vector());
+        self.value(cos_theta.clamp(-1.0, 1.0))
+    }
+
+    /// Unit vector to leave along after travelling along `direction`
+    pub fn sample(&self, direction: &Vector3, sampler: &mut dyn Sampler) -> Vector3 {
+        let (u1, u2) = sampler.get_2d();
+        let cos_theta = match *self {
+            PhaseFunction::Isotropic => 1.0 - 2.0 * u1,
+            PhaseFunction::HenyeyGreenstein { g } => sample_henyey_greenstein(g, u1),
+            PhaseFunction::DoubleHenyeyGreenstein { g1, g2, weight } => {
+                // Pick a lobe, then stretch what is left of u1 back over [0, 1)
+                if u1 < weight {
+                    sample_henyey_greenstein(g1, u1 / weight)
+                } else {
+                    sample_henyey_greenstein(g2, (u1 - weight) / (1.0 - weight))
+                }
+            }
+            PhaseFunction::Rayleigh => {
+                // Cardano's formula for the cubic the CDF inverts to,
+                // cos^3 + 3 cos = 8 u1 - 4
+                let q = 4.0 * u1 - 2.0;
+                let root = (q * q + 1.0).sqrt();
+                (q + root).cbrt() + (q - root).cbrt()
+            }
+        };
+
+        let cos_theta = cos_theta.clamp(-1.0, 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u2;
+        Onb::new(direction).transform(&Vector3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
+
+fn henyey_greenstein(g: f64, cos_theta: f64) -> f64 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+}
+
+/// Inverts the CDF of a Henyey-Greenstein lobe at `u`
+fn sample_henyey_greenstein(g: f64, u: f64) -> f64 {
+    if g.abs() < ISOTROPIC_G {
+        return 1.0 - 2.0 * u;
+    }
+    let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+    (1.0 + g * g - s * s) / (2.0 * g)
+}
@@ -0,0 +1,301 @@
+use crate::color::{Color, luminance};
+use crate::hittable::HitRecord;
+use crate::material::Material;
+use crate::material::microfacet::{TrowbridgeReitz, reflect};
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::texture::Texture;
+use crate::texture::solid::SolidTexture;
+use crate::vector::Vector3;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Lowest roughness of the specular and clearcoat lobes. Perfect mirrors
+/// would be delta lobes, which cannot be mixed with the others.
+const MIN_ROUGHNESS: f64 = 0.05;
+
+/// Normal incidence reflectance of the clearcoat, a varnish of index 1.5
+const CLEARCOAT_F0: f64 = 0.04;
+
+/// Artist-friendly "uber" material after Burley's Disney BRDF, layering a
+/// clearcoat over a blend of metal, opaque dielectric and glass. Every
+/// parameter but the index of refraction may be driven by a texture; scalar
+/// ones read its red channel and expect values in [0, 1].
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>, // Albedo, metal reflectance and glass tint
+    pub metallic: Arc<dyn Texture>,   // Blend from dielectric to metal
+    pub roughness: Arc<dyn Texture>,  // Of the specular and glass lobes
+    pub specular: Arc<dyn Texture>,   // Dielectric reflectance, 0.5 being 4%
+    pub clearcoat: Arc<dyn Texture>,  // Strength of the varnish layer
+    pub clearcoat_roughness: Arc<dyn Texture>, // Roughness of the varnish
+    pub sheen: Arc<dyn Texture>,      // Extra grazing reflection, as on cloth
+    pub sheen_tint: Arc<dyn Texture>, // Sheen from white to the base colour
+    pub transmission: Arc<dyn Texture>, // Fraction of the dielectric that is glass
+    pub ior: f64,                     // Index of refraction of the glass
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        Self::new_texture(Arc::new(SolidTexture::new(base_color)))
+    }
+
+    pub fn new_texture(base_color: Arc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: Self::scalar(0.0),
+            roughness: Self::scalar(0.5),
+            specular: Self::scalar(0.5),
+            clearcoat: Self::scalar(0.0),
+            clearcoat_roughness: Self::scalar(0.1),
+            sheen: Self::scalar(0.0),
+            sheen_tint: Self::scalar(0.5),
+            transmission: Self::scalar(0.0),
+            ior: 1.5,
+        }
+    }
+
+    /// Constant texture for a scalar parameter
+    pub fn scalar(value: f64) -> Arc<dyn Texture> {
+        Arc::new(SolidTexture::new(Color::new(value, value, value)))
+    }
+
+    /// Looks up the parameters at a hit, `None` when the ray arrives from
+    /// below the shading normal
+    fn surface(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<Surface> {
+        let frame = Onb::new(&hit_record.normal);
+        let wo = frame.to_local(&-ray_in.get_direction().unit_vector());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let (u, v, p) = (hit_record.u, hit_record.v, &hit_record.p);
+        let read = |texture: &Arc<dyn Texture>| texture.value(u, v, p).x().clamp(0.0, 1.0);
+        let base = self.base_color.value(u, v, p);
+        let metallic = read(&self.metallic);
+        let transmission = read(&self.transmission);
+        let roughness = read(&self.roughness).max(MIN_ROUGHNESS);
+
+        let tint = match luminance(&base) {
+            l if l > 0.0 => base / l,
+            _ => Color::new(1.0, 1.0, 1.0),
+        };
+        let sheen_tint = read(&self.sheen_tint);
+        let sheen = read(&self.sheen)
+            * ((1.0 - sheen_tint) * Color::new(1.0, 1.0, 1.0) + sheen_tint * tint);
+
+        Some(Surface {
+            frame,
+            wo,
+            base,
+            metallic,
+            dielectric: (1.0 - metallic) * (1.0 - transmission),
+            glass: (1.0 - metallic) * transmission,
+            roughness,
+            specular: TrowbridgeReitz::new(roughness),
+            specular_f0: 0.08 * read(&self.specular),
+            // The varnish sits on the outside, so rays leaving the inside of
+            // a transmissive object do not see it
+            clearcoat: if hit_record.front_face {
+                read(&self.clearcoat)
+            } else {
+                0.0
+            },
+            coat: TrowbridgeReitz::new(read(&self.clearcoat_roughness).max(MIN_ROUGHNESS)),
+            sheen,
+            eta: if hit_record.front_face {
+                self.ior
+            } else {
+                1.0 / self.ior
+            },
+        })
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        let Some(surface) = self.surface(ray_in, hit_record) else {
+            return false;
+        };
+        let wo = surface.wo;
+        let [diffuse, specular, glass, _] = surface.lobe_probabilities();
+
+        let u = sampler.get_1d();
+        let wi = if u < diffuse {
+            Vector3::random_cosine_direction(sampler)
+        } else if u < diffuse + specular {
+            let wm = surface.specular.sample_wm(&wo, sampler.get_2d());
+            reflect(&wo, &wm)
+        } else if u < diffuse + specular + glass {
+            match surface
+                .specular
+                .sample_dielectric(&wo, surface.eta, sampler)
+            {
+                Some(wi) => wi,
+                None => return false,
+            }
+        } else {
+            let wm = surface.coat.sample_wm(&wo, sampler.get_2d());
+            reflect(&wo, &wm)
+        };
+
+        // Weigh by the whole mixture, so that any lobe could have picked wi
+        let pdf = surface.pdf(&wi);
+        if pdf <= 0.0 {
+            return false;
+        }
+        *attenuation = surface.eval(&wi) / pdf;
+        *scattered = Ray::new_at_time(
+            hit_record.p,
+            surface.frame.transform(&wi),
+            ray_in.get_time(),
+        );
+        true
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let Some(surface) = self.surface(ray_in, hit_record) else {
+            return 0.0;
+        };
+        let wi = surface
+            .frame
+            .to_local(&scattered.get_direction().unit_vector());
+        surface.pdf(&wi)
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let Some(surface) = self.surface(ray_in, hit_record) else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
+        let wi = surface
+            .frame
+            .to_local(&scattered.get_direction().unit_vector());
+        surface.eval(&wi)
+    }
+}
+
+/// The parameters of a `Principled` material at one point, with directions
+/// in the local frame around the shading normal
+struct Surface {
+    frame: Onb,
+    wo: Vector3,
+    base: Color,
+    metallic: f64,
+    dielectric: f64, // Weight of the opaque dielectric, diffuse under a specular coat
+    glass: f64,      // Weight of the transmissive dielectric
+    roughness: f64,
+    specular: TrowbridgeReitz,
+    specular_f0: f64,
+    clearcoat: f64,
+    coat: TrowbridgeReitz,
+    sheen: Color,
+    eta: f64,
+}
+
+impl Surface {
+    /// Reflectance of the specular lobe, metal and dielectric blended
+    fn specular_fresnel(&self, cos_theta: f64) -> Color {
+        let weight = schlick_weight(cos_theta);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let metal = self.base + weight * (white - self.base);
+        let dielectric = self.specular_f0 + weight * (1.0 - self.specular_f0);
+        self.metallic * metal + self.dielectric * dielectric * white
+    }
+
+    /// Fraction of light the clearcoat lets through at `cos_theta`
+    fn coat_transmittance(&self, cos_theta: f64) -> f64 {
+        1.0 - self.clearcoat * schlick(CLEARCOAT_F0, cos_theta)
+    }
+
+    /// Chances of sampling the diffuse, specular, glass and clearcoat lobes,
+    /// roughly in proportion to the light each reflects towards `wo`
+    fn lobe_probabilities(&self) -> [f64; 4] {
+        let cos_o = self.wo.z();
+        let base = self.coat_transmittance(cos_o);
+        let weights = [
+            base * self.dielectric * (luminance(&self.base) + luminance(&self.sheen)),
+            base * luminance(&self.specular_fresnel(cos_o)),
+            base * self.glass,
+            self.clearcoat * schlick(CLEARCOAT_F0, cos_o),
+        ];
+
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return [0.0; 4];
+        }
+        weights.map(|weight| weight / total)
+    }
+
+    fn pdf(&self, wi: &Vector3) -> f64 {
+        let [diffuse, specular, glass, coat] = self.lobe_probabilities();
+        let wo = &self.wo;
+
+        let mut pdf = glass * self.specular.dielectric_pdf(wo, wi, self.eta);
+        if wi.z() > 0.0 {
+            pdf += diffuse * wi.z() / PI
+                + specular * self.specular.reflection_pdf(wo, wi)
+                + coat * self.coat.reflection_pdf(wo, wi);
+        }
+        pdf
+    }
+
+    /// The BSDF times |cos_i|
+    fn eval(&self, wi: &Vector3) -> Color {
+        let wo = &self.wo;
+        let mut value = Color::new(0.0, 0.0, 0.0);
+
+        if self.glass > 0.0 {
+            let glass = self.glass * self.specular.dielectric_eval(wo, wi, self.eta);
+            // Only what passes through the glass takes on its colour
+            value += if wi.z() < 0.0 {
+                glass * self.base
+            } else {
+                glass * Color::new(1.0, 1.0, 1.0)
+            };
+        }
+
+        if wi.z() <= 0.0 {
+            return self.coat_transmittance(wo.z()) * value;
+        }
+
+        let wm = (*wo + *wi).unit_vector();
+        let cos_d = Vector3::dot(wi, &wm);
+
+        // Burley's diffuse with its retro-reflection at grazing angles,
+        // renormalised as in Frostbite so that it does not add energy
+        let fd90 = 0.5 * self.roughness + 2.0 * self.roughness * cos_d * cos_d;
+        let retro = (1.0 + (fd90 - 1.0) * schlick_weight(wo.z()))
+            * (1.0 + (fd90 - 1.0) * schlick_weight(wi.z()))
+            * (1.0 - self.roughness + self.roughness / 1.51);
+        let diffuse = retro / PI * self.base + schlick_weight(cos_d) * self.sheen;
+        value += self.dielectric * wi.z() * diffuse;
+
+        let specular = self.specular.d(&wm) * self.specular.g(wo, wi) / (4.0 * wo.z());
+        value += specular * self.specular_fresnel(Vector3::dot(wo, &wm));
+
+        value *= self.coat_transmittance(wo.z()) * self.coat_transmittance(wi.z());
+
+        if self.clearcoat > 0.0 {
+            let coat = self.coat.d(&wm) * self.coat.g(wo, wi) / (4.0 * wo.z());
+            let coat = self.clearcoat * coat * schlick(CLEARCOAT_F0, cos_d);
+            value += Color::new(coat, coat, coat);
+        }
+        value
+    }
+}
+
+/// (1 - cos)^5, the shape of Schlick's Fresnel approximation
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+fn schlick(f0: f64, cos_theta: f64) -> f64 {
+    f0 + (1.0 - f0) * schlick_weight(cos_theta)
+}
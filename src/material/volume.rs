@@ -0,0 +1,90 @@
+use crate::color::Color;
+use crate::hittable::HitRecord;
+use crate::material::Material;
+use crate::material::phase::PhaseFunction;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::texture::Texture;
+use crate::texture::solid::SolidTexture;
+use crate::vector::Point3;
+use std::sync::Arc;
+
+/// Material inside a participating medium, scattering a textured albedo in
+/// the directions given by a phase function
+pub struct Volume {
+    texture: Arc<dyn Texture>,
+    phase: PhaseFunction,
+    emission: Option<Arc<dyn Texture>>, // Radiance of the absorbing part, as seen through a thick region
+}
+
+impl Volume {
+    pub fn new(texture: Arc<dyn Texture>, phase: PhaseFunction) -> Self {
+        Self {
+            texture,
+            phase,
+            emission: None,
+        }
+    }
+
+    pub fn from_color(albedo: Color, phase: PhaseFunction) -> Self {
+        Self::new(Arc::new(SolidTexture::new(albedo)), phase)
+    }
+
+    /// Makes the medium glow, such as fire
+    pub fn with_emission(mut self, emission: Arc<dyn Texture>) -> Self {
+        self.emission = Some(emission);
+        self
+    }
+}
+
+impl Material for Volume {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+        sampler: &mut dyn Sampler,
+    ) -> bool {
+        *attenuation = self
+            .texture
+            .value(hit_record.u, hit_record.v, &hit_record.p);
+        if attenuation.near_zero() {
+            return false;
+        }
+        *scattered = Ray::new_at_time(
+            hit_record.p,
+            self.phase.sample(&ray_in.get_direction(), sampler),
+            ray_in.get_time(),
+        );
+        true
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, _hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        self.phase
+            .pdf(&ray_in.get_direction(), &scattered.get_direction())
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        // The phase function has no cosine term
+        self.scattering_pdf(ray_in, hit_record, scattered)
+            * self
+                .texture
+                .value(hit_record.u, hit_record.v, &hit_record.p)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let Some(emission) = &self.emission else {
+            return Color::new(0.0, 0.0, 0.0);
+        };
+
+        // Of the collisions, the fraction 1 - albedo are absorptions
+        let albedo = self.texture.value(u, v, p);
+        let absorbed = Color::new(
+            (1.0 - albedo.x()).max(0.0),
+            (1.0 - albedo.y()).max(0.0),
+            (1.0 - albedo.z()).max(0.0),
+        );
+        absorbed * emission.value(u, v, p)
+    }
+}
@@ -0,0 +1,416 @@
+use crate::vector::{Point3, Vector3};
+use std::ops::Mul;
+
+/// Affine transformation as a 4x4 matrix acting on column vectors. Points
+/// take the translation in the last column, vectors ignore it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Matrix4 = Matrix4 {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        self.m[row][column]
+    }
+
+    pub fn translate(offset: Vector3) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(factors: Vector3) -> Self {
+        Self::new([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Counter-clockwise rotation when looking down `axis` towards the origin
+    pub fn rotate(angle_degrees: f64, axis: Vector3) -> Self {
+        let a = axis.unit_vector();
+        let (sin, cos) = angle_degrees.to_radians().sin_cos();
+        let (x, y, z) = (a.x(), a.y(), a.z());
+
+        Self::new([
+            [
+                x * x + (1.0 - x * x) * cos,
+                x * y * (1.0 - cos) - z * sin,
+                x * z * (1.0 - cos) + y * sin,
+                0.0,
+            ],
+            [
+                x * y * (1.0 - cos) + z * sin,
+                y * y + (1.0 - y * y) * cos,
+                y * z * (1.0 - cos) - x * sin,
+                0.0,
+            ],
+            [
+                x * z * (1.0 - cos) - y * sin,
+                y * z * (1.0 - cos) + x * sin,
+                z * z + (1.0 - z * z) * cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotate_x(angle_degrees: f64) -> Self {
+        Self::rotate(angle_degrees, Vector3::new(1.0, 0.0, 0.0))
+    }
+
+    pub fn rotate_y(angle_degrees: f64) -> Self {
+        Self::rotate(angle_degrees, Vector3::new(0.0, 1.0, 0.0))
+    }
+
+    pub fn rotate_z(angle_degrees: f64) -> Self {
+        Self::rotate(angle_degrees, Vector3::new(0.0, 0.0, 1.0))
+    }
+
+    /// Moves the origin to `from` and turns +z towards `at`, keeping +y as
+    /// close to `up` as possible
+    pub fn look_at(from: Point3, at: Point3, up: Vector3) -> Self {
+        let w = (at - from).unit_vector();
+        let u = Vector3::cross(&up, &w).unit_vector();
+        let v = Vector3::cross(&w, &u);
+
+        Self::new([
+            [u.x(), v.x(), w.x(), from.x()],
+            [u.y(), v.y(), w.y(), from.y()],
+            [u.z(), v.z(), w.z(), from.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Applies `self` and then `next`
+    pub fn then(&self, next: &Matrix4) -> Self {
+        *next * *self
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = self.m[column][row];
+            }
+        }
+        Self::new(m)
+    }
+
+    /// Inverse by Gauss-Jordan elimination with partial pivoting, `None` for
+    /// a singular matrix such as a scale by zero
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inverse = Self::IDENTITY.m;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for k in 0..4 {
+                a[column][k] *= scale;
+                inverse[column][k] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+                let factor = a[row][column];
+                for k in 0..4 {
+                    a[row][k] -= factor * a[column][k];
+                    inverse[row][k] -= factor * inverse[column][k];
+                }
+            }
+        }
+
+        Some(Self::new(inverse))
+    }
+
+    /// Determinant of the linear part, by how much volumes are scaled
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        self.transform_vector(p) + Vector3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    pub fn transform_vector(&self, v: &Vector3) -> Vector3 {
+        let m = &self.m;
+        Vector3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[row][k] * other.m[k][column]).sum();
+            }
+        }
+        Matrix4::new(m)
+    }
+}
+
+/// Unit quaternion representing a rotation
+#[derive(Clone, Copy, Debug)]
+pub struct Quaternion {
+    pub v: Vector3,
+    pub w: f64,
+}
+
+impl Quaternion {
+    /// Rotation part of `m`, which must be a rotation matrix
+    pub fn from_matrix(m: &Matrix4) -> Self {
+        let m = &m.m;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        // Divide by the largest of the components to stay accurate
+        let (x, y, z, w) = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            (
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                0.25 * s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            (
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            (
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            )
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            (
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        };
+
+        Self {
+            v: Vector3::new(x, y, z),
+            w,
+        }
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        Vector3::dot(&self.v, &other.v) + self.w * other.w
+    }
+
+    /// Angle, in radians, of the rotation taking `self` to `other`
+    pub fn angle_to(&self, other: &Quaternion) -> f64 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    /// Spherical interpolation along the shorter arc, at constant angular speed
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Self {
+        // q and -q are the same rotation, take the one closer to `self`
+        let (other, cos_theta) = match self.dot(other) {
+            d if d < 0.0 => (other.scaled(-1.0), -d),
+            d => (*other, d),
+        };
+
+        if cos_theta > 0.9995 {
+            // Nearly parallel, a normalized lerp is indistinguishable
+            return self.scaled(1.0 - t).add(&other.scaled(t)).normalized();
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        self.scaled(((1.0 - t) * theta).sin() / sin_theta)
+            .add(&other.scaled((t * theta).sin() / sin_theta))
+    }
+
+    pub fn to_matrix(&self) -> Matrix4 {
+        let (x, y, z, w) = (self.v.x(), self.v.y(), self.v.z(), self.w);
+
+        Matrix4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    fn scaled(&self, factor: f64) -> Self {
+        Self {
+            v: factor * self.v,
+            w: factor * self.w,
+        }
+    }
+
+    fn add(&self, other: &Quaternion) -> Self {
+        Self {
+            v: self.v + other.v,
+            w: self.w + other.w,
+        }
+    }
+
+    fn normalized(&self) -> Self {
+        self.scaled(1.0 / self.dot(self).sqrt())
+    }
+}
+
+/// A transform that changes between time 0 and time 1. Each end is split
+/// into translation, rotation and the remaining scale and shear, which are
+/// interpolated separately so a rotating object keeps its shape on the way.
+#[derive(Clone, Copy, Debug)]
+pub struct AnimatedMatrix {
+    translations: [Vector3; 2],
+    rotations: [Quaternion; 2],
+    scales: [Matrix4; 2],
+}
+
+impl AnimatedMatrix {
+    /// Panics if either end can't be inverted, or only one of them mirrors
+    pub fn new(start: Matrix4, end: Matrix4) -> Self {
+        assert!(
+            (start.determinant3() < 0.0) == (end.determinant3() < 0.0),
+            "can't animate between a mirrored and an unmirrored transform"
+        );
+        let (start_translation, start_rotation, start_scale) = decompose(&start);
+        let (end_translation, end_rotation, end_scale) = decompose(&end);
+
+        Self {
+            translations: [start_translation, end_translation],
+            rotations: [start_rotation, end_rotation],
+            scales: [start_scale, end_scale],
+        }
+    }
+
+    /// The transform at `time`, clamped to the range of the animation
+    pub fn at(&self, time: f64) -> Matrix4 {
+        let t = time.clamp(0.0, 1.0);
+        let translation = (1.0 - t) * self.translations[0] + t * self.translations[1];
+        let rotation = self.rotations[0].slerp(&self.rotations[1], t);
+
+        let mut scale = [[0.0; 4]; 4];
+        for (row, values) in scale.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value =
+                    (1.0 - t) * self.scales[0].m[row][column] + t * self.scales[1].m[row][column];
+            }
+        }
+
+        Matrix4::new(scale)
+            .then(&rotation.to_matrix())
+            .then(&Matrix4::translate(translation))
+    }
+
+    /// Angle, in radians, the rotation turns through over the animation
+    pub fn rotation_angle(&self) -> f64 {
+        self.rotations[0].angle_to(&self.rotations[1])
+    }
+}
+
+/// Splits `m` into a translation, a rotation and the scale and shear applied
+/// before the rotation, using the polar decomposition of its linear part
+fn decompose(m: &Matrix4) -> (Vector3, Quaternion, Matrix4) {
+    let translation = Vector3::new(m.m[0][3], m.m[1][3], m.m[2][3]);
+    let mut linear = *m;
+    for row in 0..3 {
+        linear.m[row][3] = 0.0;
+    }
+
+    // A mirror can't be a rotation, so decompose the negated matrix instead
+    // and leave the mirror in the scale
+    let mut rotation = if linear.determinant3() < 0.0 {
+        linear * Matrix4::scale(Vector3::new(-1.0, -1.0, -1.0))
+    } else {
+        linear
+    };
+
+    // Averaging a matrix with its inverse transpose converges to the nearest
+    // orthogonal matrix
+    for _ in 0..100 {
+        let inverse_transpose = rotation
+            .inverse()
+            .expect("animated transform must be invertible")
+            .transpose();
+        let mut next = [[0.0; 4]; 4];
+        let mut change: f64 = 0.0;
+        for (row, values) in next.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = 0.5 * (rotation.m[row][column] + inverse_transpose.m[row][column]);
+                change = change.max((*value - rotation.m[row][column]).abs());
+            }
+        }
+        rotation = Matrix4::new(next);
+        if change < 1e-12 {
+            break;
+        }
+    }
+
+    let scale = rotation.transpose() * linear;
+    (translation, Quaternion::from_matrix(&rotation), scale)
+}
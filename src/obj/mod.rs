@@ -0,0 +1,244 @@
+//! Wavefront OBJ import.
+//!
+//! Faces are grouped by their `usemtl` material and each group becomes one
+//! [`TriangleMesh`]. Polygons are triangulated as fans, and normals or texture
+//! coordinates are kept for a group only when every face in it provides them.
+
+pub mod mtl;
+
+use crate::hittable::HittableList;
+use crate::hittable::mesh::{MeshData, TriangleMesh};
+use crate::material::Material;
+use crate::vector::{Point3, Vector3};
+use anyhow::{Context, Result, anyhow, bail};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Indices of the position, texture coordinate and normal used by one face corner
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Corner {
+    v: usize,
+    vt: Option<usize>,
+    vn: Option<usize>,
+}
+
+struct Group {
+    material: Option<String>,
+    faces: Vec<[Corner; 3]>,
+}
+
+/// Loads an OBJ file and the MTL libraries it references.
+/// Faces without a material use `default_material`.
+pub fn load(path: &Path, default_material: Arc<dyn Material>) -> Result<HittableList> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("could not read OBJ file '{}'", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    parse(&source, dir, default_material)
+        .with_context(|| format!("invalid OBJ file '{}'", path.display()))
+}
+
+/// Parses the text of an OBJ file, resolving `mtllib` paths relative to `dir`
+pub fn parse(
+    source: &str,
+    dir: &Path,
+    default_material: Arc<dyn Material>,
+) -> Result<HittableList> {
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+    let mut normals: Vec<Vector3> = Vec::new();
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut groups = vec![Group {
+        material: None,
+        faces: Vec::new(),
+    }];
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = strip_comment(line).split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let values = parse_floats(&args, line_number)?;
+                if values.len() < 3 {
+                    bail!("line {}: vertex needs 3 coordinates", line_number);
+                }
+                positions.push(Point3::new(values[0], values[1], values[2]));
+            }
+            "vt" => {
+                let values = parse_floats(&args, line_number)?;
+                if values.is_empty() {
+                    bail!("line {}: texture coordinate needs a value", line_number);
+                }
+                uvs.push((values[0], values.get(1).copied().unwrap_or(0.0)));
+            }
+            "vn" => {
+                let values = parse_floats(&args, line_number)?;
+                if values.len() != 3 {
+                    bail!("line {}: normal needs 3 components", line_number);
+                }
+                normals.push(Vector3::new(values[0], values[1], values[2]).unit_vector());
+            }
+            "f" => {
+                if args.len() < 3 {
+                    bail!("line {}: face needs at least 3 vertices", line_number);
+                }
+                let corners = args
+                    .iter()
+                    .map(|arg| {
+                        parse_corner(arg, positions.len(), uvs.len(), normals.len())
+                            .map_err(|e| anyhow!("line {}: {}", line_number, e))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                let group = groups.last_mut().unwrap();
+                for i in 1..corners.len() - 1 {
+                    group.faces.push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                if !materials.contains_key(&name) {
+                    bail!("line {}: unknown material '{}'", line_number, name);
+                }
+                groups.push(Group {
+                    material: Some(name),
+                    faces: Vec::new(),
+                });
+            }
+            "mtllib" => {
+                for file in &args {
+                    let library = mtl::load(&dir.join(file))
+                        .with_context(|| format!("line {}: in mtllib", line_number))?;
+                    materials.extend(library);
+                }
+            }
+            // Object and smoothing groups, lines and points carry nothing we render
+            _ => {}
+        }
+    }
+
+    let mut meshes = HittableList::new();
+    for group in groups {
+        if group.faces.is_empty() {
+            continue;
+        }
+
+        let material = match &group.material {
+            Some(name) => Arc::clone(&materials[name]),
+            None => Arc::clone(&default_material),
+        };
+        let data = build_mesh(&group.faces, &positions, &uvs, &normals);
+        meshes.add(Arc::new(TriangleMesh::new(Arc::new(data), material)));
+    }
+
+    Ok(meshes)
+}
+
+/// Gathers the corners of a group's faces into its own deduplicated vertex buffers
+fn build_mesh(
+    faces: &[[Corner; 3]],
+    positions: &[Point3],
+    uvs: &[(f64, f64)],
+    normals: &[Vector3],
+) -> MeshData {
+    let has_uvs = faces.iter().flatten().all(|corner| corner.vt.is_some());
+    let has_normals = faces.iter().flatten().all(|corner| corner.vn.is_some());
+
+    let mut vertex_ids: HashMap<Corner, usize> = HashMap::new();
+    let mut mesh_positions = Vec::new();
+    let mut mesh_uvs = Vec::new();
+    let mut mesh_normals = Vec::new();
+
+    let indices = faces
+        .iter()
+        .map(|face| {
+            face.map(|corner| {
+                let corner = Corner {
+                    v: corner.v,
+                    vt: corner.vt.filter(|_| has_uvs),
+                    vn: corner.vn.filter(|_| has_normals),
+                };
+                *vertex_ids.entry(corner).or_insert_with(|| {
+                    mesh_positions.push(positions[corner.v]);
+                    if let Some(vt) = corner.vt {
+                        mesh_uvs.push(uvs[vt]);
+                    }
+                    if let Some(vn) = corner.vn {
+                        mesh_normals.push(normals[vn]);
+                    }
+                    mesh_positions.len() - 1
+                })
+            })
+        })
+        .collect();
+
+    MeshData::new(mesh_positions, mesh_normals, mesh_uvs, indices)
+}
+
+/// Parses a face corner in any of the `v`, `v/vt`, `v//vn` and `v/vt/vn` forms
+fn parse_corner(arg: &str, positions: usize, uvs: usize, normals: usize) -> Result<Corner> {
+    let mut parts = arg.split('/');
+
+    let v = resolve_index(parts.next().unwrap_or(""), positions, "vertex")?;
+    let vt = match parts.next() {
+        Some("") | None => None,
+        Some(index) => Some(resolve_index(index, uvs, "texture coordinate")?),
+    };
+    let vn = match parts.next() {
+        Some("") | None => None,
+        Some(index) => Some(resolve_index(index, normals, "normal")?),
+    };
+
+    if parts.next().is_some() {
+        bail!("malformed face vertex '{}'", arg);
+    }
+
+    Ok(Corner { v, vt, vn })
+}
+
+/// Converts a 1-based or negative (relative to the end) OBJ index into a 0-based one
+fn resolve_index(index: &str, count: usize, kind: &str) -> Result<usize> {
+    let value: i64 = index
+        .parse()
+        .map_err(|_| anyhow!("invalid {} index '{}'", kind, index))?;
+
+    let resolved = if value > 0 {
+        value - 1
+    } else {
+        count as i64 + value
+    };
+
+    if value == 0 || resolved < 0 || resolved >= count as i64 {
+        bail!(
+            "{} index {} is out of range, {} defined so far",
+            kind,
+            value,
+            count
+        );
+    }
+
+    Ok(resolved as usize)
+}
+
+pub(crate) fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(position) => &line[..position],
+        None => line,
+    }
+}
+
+pub(crate) fn parse_floats(args: &[&str], line_number: usize) -> Result<Vec<f64>> {
+    args.iter()
+        .map(|arg| {
+            arg.parse::<f64>()
+                .map_err(|_| anyhow!("line {}: invalid number '{}'", line_number, arg))
+        })
+        .collect()
+}
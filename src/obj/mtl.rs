@@ -0,0 +1,256 @@
+use crate::color::Color;
+use crate::image::Image;
+use crate::material::Material;
+use crate::material::dielectric::{Dielectric, DiffuseLight};
+use crate::material::lambertian::Lambertian;
+use crate::material::metal::Metal;
+use crate::material::principled::Principled;
+use crate::obj::{parse_floats, strip_comment};
+use crate::texture::Texture;
+use crate::texture::image::ImageTexture;
+use crate::texture::solid::SolidTexture;
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Index of refraction for transparent materials that leave `Ni` unset
+const GLASS_IOR: f64 = 1.5;
+
+/// The subset of an MTL material description the renderer understands
+struct MtlMaterial {
+    kd: Color,
+    ks: Color,
+    ke: Color,
+    ns: f64,
+    ni: f64,
+    d: f64,
+    illum: i32,
+    map_kd: Option<(String, usize)>, // File name and the line naming it
+    pbr: Pbr,
+}
+
+/// Values of the PBR extension keys, which ask for a principled material
+#[derive(Default)]
+struct Pbr {
+    roughness: Option<f64>,           // Pr
+    metallic: Option<f64>,            // Pm
+    sheen: Option<f64>,               // Ps
+    clearcoat: Option<f64>,           // Pc
+    clearcoat_roughness: Option<f64>, // Pcr
+}
+
+impl Pbr {
+    fn is_set(&self) -> bool {
+        [
+            self.roughness,
+            self.metallic,
+            self.sheen,
+            self.clearcoat,
+            self.clearcoat_roughness,
+        ]
+        .iter()
+        .any(Option::is_some)
+    }
+}
+
+impl MtlMaterial {
+    fn new() -> Self {
+        Self {
+            kd: Color::new(0.8, 0.8, 0.8),
+            ks: Color::new(0.0, 0.0, 0.0),
+            ke: Color::new(0.0, 0.0, 0.0),
+            ns: 0.0,
+            ni: 1.0,
+            d: 1.0,
+            illum: 2,
+            map_kd: None,
+            pbr: Pbr::default(),
+        }
+    }
+
+    /// Picks the closest of the renderer's materials:
+    /// emissive materials become lights, those using the PBR extension
+    /// principled, transparent ones glass, materials with mirror reflection or
+    /// a specular colour brighter than the diffuse one metal, and everything
+    /// else Lambertian.
+    fn build(&self, dir: &Path) -> Result<Arc<dyn Material>> {
+        if max_component(&self.ke) > 0.0 {
+            return Ok(Arc::new(DiffuseLight::from_color(self.ke)));
+        }
+
+        if self.pbr.is_set() {
+            return Ok(Arc::new(self.principled(dir)?));
+        }
+
+        if self.d < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            return Ok(Arc::new(Dielectric::new(self.glass_ior())));
+        }
+
+        if self.illum == 3 || max_component(&self.ks) > max_component(&self.kd) {
+            // Map the Phong exponent onto the fuzz radius of a rough mirror
+            let fuzz = (2.0 / (self.ns + 2.0)).sqrt().min(1.0);
+            return Ok(Arc::new(Metal::new(self.ks, fuzz)));
+        }
+
+        Ok(Arc::new(Lambertian::new_texture(
+            self.diffuse_texture(dir)?,
+        )))
+    }
+
+    fn principled(&self, dir: &Path) -> Result<Principled> {
+        let mut principled = Principled::new_texture(self.diffuse_texture(dir)?);
+        let parameters = [
+            (self.pbr.roughness, &mut principled.roughness),
+            (self.pbr.metallic, &mut principled.metallic),
+            (self.pbr.sheen, &mut principled.sheen),
+            (self.pbr.clearcoat, &mut principled.clearcoat),
+            (
+                self.pbr.clearcoat_roughness,
+                &mut principled.clearcoat_roughness,
+            ),
+        ];
+        for (value, texture) in parameters {
+            if let Some(value) = value {
+                *texture = Principled::scalar(value);
+            }
+        }
+
+        // Dissolve stands in for transmission
+        principled.transmission = Principled::scalar(1.0 - self.d.clamp(0.0, 1.0));
+        principled.ior = self.glass_ior();
+        Ok(principled)
+    }
+
+    /// `Ni`, unless it is left at the default of 1, which would make the glass
+    /// invisible
+    fn glass_ior(&self) -> f64 {
+        if self.ni > 1.0 { self.ni } else { GLASS_IOR }
+    }
+
+    /// The `map_Kd` image if there is one, else the `Kd` colour
+    fn diffuse_texture(&self, dir: &Path) -> Result<Arc<dyn Texture>> {
+        let Some((file, line_number)) = &self.map_kd else {
+            return Ok(Arc::new(SolidTexture::new(self.kd)));
+        };
+
+        let path = dir.join(file);
+        let mut image = Image::new();
+        if !image.load(&path.to_string_lossy()) {
+            bail!(
+                "line {}: could not load map_Kd image '{}'",
+                line_number,
+                path.display()
+            );
+        }
+        Ok(Arc::new(ImageTexture::new(image)))
+    }
+}
+
+/// Loads every material of an MTL file, keyed by name
+pub fn load(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("could not read MTL file '{}'", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    parse(&source, dir).with_context(|| format!("invalid MTL file '{}'", path.display()))
+}
+
+/// Parses an MTL file, resolving texture maps relative to `dir`
+pub fn parse(source: &str, dir: &Path) -> Result<HashMap<String, Arc<dyn Material>>> {
+    let mut parsed: Vec<(String, MtlMaterial)> = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = strip_comment(line).split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if args.is_empty() {
+                bail!("line {}: newmtl without a name", line_number);
+            }
+            parsed.push((args.join(" "), MtlMaterial::new()));
+            continue;
+        }
+
+        let Some((_, material)) = parsed.last_mut() else {
+            bail!("line {}: '{}' before any newmtl", line_number, keyword);
+        };
+
+        match keyword {
+            "Kd" => material.kd = parse_color(&args, line_number)?,
+            "Ks" => material.ks = parse_color(&args, line_number)?,
+            "Ke" => material.ke = parse_color(&args, line_number)?,
+            "Ns" => material.ns = parse_scalar(&args, line_number)?,
+            "Ni" => material.ni = parse_scalar(&args, line_number)?,
+            "d" => material.d = parse_scalar(&args, line_number)?,
+            "Tr" => material.d = 1.0 - parse_scalar(&args, line_number)?,
+            "illum" => material.illum = parse_scalar(&args, line_number)? as i32,
+            "Pr" => material.pbr.roughness = Some(parse_fraction(&args, keyword, line_number)?),
+            "Pm" => material.pbr.metallic = Some(parse_fraction(&args, keyword, line_number)?),
+            "Ps" => material.pbr.sheen = Some(parse_fraction(&args, keyword, line_number)?),
+            "Pc" => material.pbr.clearcoat = Some(parse_fraction(&args, keyword, line_number)?),
+            "Pcr" => {
+                material.pbr.clearcoat_roughness =
+                    Some(parse_fraction(&args, keyword, line_number)?)
+            }
+            "map_Kd" => {
+                // Options such as -s or -o come before the file name
+                let Some(file) = args.last() else {
+                    bail!("line {}: map_Kd without a file name", line_number);
+                };
+                material.map_kd = Some((file.to_string(), line_number));
+            }
+            _ => {}
+        }
+    }
+
+    parsed
+        .into_iter()
+        .map(|(name, material)| {
+            let built = material
+                .build(dir)
+                .with_context(|| format!("in material '{}'", name))?;
+            Ok((name, built))
+        })
+        .collect()
+}
+
+fn parse_color(args: &[&str], line_number: usize) -> Result<Color> {
+    let values = parse_floats(args, line_number)?;
+    match values.as_slice() {
+        [r, g, b] => Ok(Color::new(*r, *g, *b)),
+        // A single value means a grey colour
+        [v] => Ok(Color::new(*v, *v, *v)),
+        _ => bail!("line {}: expected a colour of 1 or 3 values", line_number),
+    }
+}
+
+fn parse_scalar(args: &[&str], line_number: usize) -> Result<f64> {
+    match parse_floats(args, line_number)?.as_slice() {
+        [v] => Ok(*v),
+        _ => bail!("line {}: expected a single value", line_number),
+    }
+}
+
+/// A scalar of the PBR extension, which must lie in [0, 1]
+fn parse_fraction(args: &[&str], keyword: &str, line_number: usize) -> Result<f64> {
+    let value = parse_scalar(args, line_number)?;
+    if !(0.0..=1.0).contains(&value) {
+        bail!(
+            "line {}: {} must be between 0 and 1, got {}",
+            line_number,
+            keyword,
+            value
+        );
+    }
+    Ok(value)
+}
+
+fn max_component(color: &Color) -> f64 {
+    color.x().max(color.y()).max(color.z())
+}
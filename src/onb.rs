@@ -0,0 +1,49 @@
+use crate::vector::Vector3;
+
+/// Orthonormal basis whose `w` axis follows a given direction
+pub struct Onb {
+    u: Vector3,
+    v: Vector3,
+    w: Vector3,
+}
+
+impl Onb {
+    pub fn new(n: &Vector3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x().abs() > 0.9 {
+            Vector3::new(0.0, 1.0, 0.0)
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let v = Vector3::cross(&w, &a).unit_vector();
+        let u = Vector3::cross(&w, &v);
+
+        Self { u, v, w }
+    }
+
+    pub fn u(&self) -> Vector3 {
+        self.u
+    }
+
+    pub fn v(&self) -> Vector3 {
+        self.v
+    }
+
+    pub fn w(&self) -> Vector3 {
+        self.w
+    }
+
+    /// Converts coordinates in this basis to world space
+    pub fn transform(&self, v: &Vector3) -> Vector3 {
+        v.x() * self.u + v.y() * self.v + v.z() * self.w
+    }
+
+    /// Converts a world space vector to coordinates in this basis
+    pub fn to_local(&self, v: &Vector3) -> Vector3 {
+        Vector3::new(
+            Vector3::dot(v, &self.u),
+            Vector3::dot(v, &self.v),
+            Vector3::dot(v, &self.w),
+        )
+    }
+}
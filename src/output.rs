@@ -0,0 +1,233 @@
+//! Writing rendered images to disk.
+//!
+//! The format is picked from the file extension. PNG and PPM are tone mapped
+//! and quantized, OpenEXR and Radiance HDR store the linear radiance as
+//! 32-bit floats without clamping.
+
+use crate::color::Color;
+use crate::tonemap::{ToneMapper, srgb_decode};
+use anyhow::{Context, Result, anyhow, bail};
+use image::{ImageBuffer, Rgb, Rgb32FImage, RgbImage};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Linear radiance of every pixel, stored row by row from the top left
+#[derive(Clone)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::from_pixels(
+            width,
+            height,
+            vec![Color::new(0.0, 0.0, 0.0); (width * height) as usize],
+        )
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (width * height) as usize,
+            "framebuffer needs one colour per pixel"
+        );
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        self.pixels[(y * self.width + x) as usize] = color;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+    Exr,
+    Hdr,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("ppm") => Ok(Self::Ppm),
+            Some("png") => Ok(Self::Png),
+            Some("exr") => Ok(Self::Exr),
+            Some("hdr") => Ok(Self::Hdr),
+            _ => bail!(
+                "unsupported output file '{}', expected a .png, .exr, .hdr or .ppm extension",
+                path.display()
+            ),
+        }
+    }
+
+    /// Whether the format keeps linear values outside [0, 1]
+    pub fn is_hdr(&self) -> bool {
+        matches!(self, Self::Exr | Self::Hdr)
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ppm => write!(f, "PPM"),
+            Self::Png => write!(f, "PNG"),
+            Self::Exr => write!(f, "OpenEXR"),
+            Self::Hdr => write!(f, "Radiance HDR"),
+        }
+    }
+}
+
+/// Bits per channel of integer formats. PPM is always written with 8 bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+impl FromStr for BitDepth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "8" => Ok(Self::Eight),
+            "16" => Ok(Self::Sixteen),
+            _ => Err(anyhow!("bit depth must be 8 or 16, got '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for BitDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eight => write!(f, "8"),
+            Self::Sixteen => write!(f, "16"),
+        }
+    }
+}
+
+/// Writes the framebuffer in the format matching the extension of `path`,
+/// passing it through `tone_mapper` first unless the format stores floats
+pub fn write(
+    framebuffer: &Framebuffer,
+    path: &Path,
+    bit_depth: BitDepth,
+    tone_mapper: &ToneMapper,
+) -> Result<()> {
+    let format = ImageFormat::from_path(path)?;
+
+    let result = match (format, bit_depth) {
+        (ImageFormat::Png, BitDepth::Sixteen) => {
+            quantize_16(framebuffer, tone_mapper).save_with_format(path, image::ImageFormat::Png)
+        }
+        (ImageFormat::Png, BitDepth::Eight) => {
+            quantize_8(framebuffer, tone_mapper).save_with_format(path, image::ImageFormat::Png)
+        }
+        (ImageFormat::Ppm, _) => {
+            quantize_8(framebuffer, tone_mapper).save_with_format(path, image::ImageFormat::Pnm)
+        }
+        (ImageFormat::Exr, _) => {
+            to_float(framebuffer, false).save_with_format(path, image::ImageFormat::OpenExr)
+        }
+        // RGBE has no sign bit
+        (ImageFormat::Hdr, _) => {
+            to_float(framebuffer, true).save_with_format(path, image::ImageFormat::Hdr)
+        }
+    };
+
+    result.with_context(|| format!("could not write image '{}'", path.display()))
+}
+
+/// False-colour image of per-pixel sample counts, from black for pixels
+/// that took no samples through purple, red and orange to pale yellow for
+/// `max_samples`
+pub fn heatmap(sample_counts: &[u32], width: u32, height: u32, max_samples: u32) -> Framebuffer {
+    // Display colours, decoded so that writing the image restores them
+    const RAMP: [[f64; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [0.34, 0.06, 0.43],
+        [0.73, 0.21, 0.33],
+        [0.98, 0.55, 0.04],
+        [0.99, 1.0, 0.64],
+    ];
+
+    let pixels = sample_counts
+        .iter()
+        .map(|&count| {
+            let t = (count as f64 / max_samples.max(1) as f64).clamp(0.0, 1.0);
+            let position = t * (RAMP.len() - 1) as f64;
+            let index = (position as usize).min(RAMP.len() - 2);
+            let fraction = position - index as f64;
+
+            let [r, g, b] = [0, 1, 2].map(|channel| {
+                let (from, to) = (RAMP[index][channel], RAMP[index + 1][channel]);
+                srgb_decode(from + fraction * (to - from))
+            });
+            Color::new(r, g, b)
+        })
+        .collect();
+
+    Framebuffer::from_pixels(width, height, pixels)
+}
+
+fn quantize_8(framebuffer: &Framebuffer, tone_mapper: &ToneMapper) -> RgbImage {
+    RgbImage::from_fn(framebuffer.width, framebuffer.height, |x, y| {
+        let color = tone_mapper.to_display(framebuffer.get(x, y));
+        Rgb([0, 1, 2].map(|i| (color[i] * 255.0).round() as u8))
+    })
+}
+
+fn quantize_16(
+    framebuffer: &Framebuffer,
+    tone_mapper: &ToneMapper,
+) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+    ImageBuffer::from_fn(framebuffer.width, framebuffer.height, |x, y| {
+        let color = tone_mapper.to_display(framebuffer.get(x, y));
+        Rgb([0, 1, 2].map(|i| (color[i] * 65535.0).round() as u16))
+    })
+}
+
+fn to_float(framebuffer: &Framebuffer, non_negative: bool) -> Rgb32FImage {
+    Rgb32FImage::from_fn(framebuffer.width, framebuffer.height, |x, y| {
+        let color = framebuffer.get(x, y);
+        Rgb([0, 1, 2].map(|i| {
+            let value = if color[i].is_nan() { 0.0 } else { color[i] };
+            if non_negative {
+                value.max(0.0) as f32
+            } else {
+                value as f32
+            }
+        }))
+    })
+}
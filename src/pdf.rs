@@ -0,0 +1,92 @@
+//! Probability densities over directions, measured in solid angle.
+
+use crate::hittable::Hittable;
+use crate::onb::Onb;
+use crate::sampler::Sampler;
+use crate::vector::{Point3, Vector3};
+use std::f64::consts::PI;
+
+pub trait Pdf {
+    fn value(&self, direction: &Vector3) -> f64;
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vector3;
+}
+
+/// Uniform density over the whole sphere of directions
+pub struct SpherePdf;
+
+impl SpherePdf {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for SpherePdf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pdf for SpherePdf {
+    fn value(&self, _direction: &Vector3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vector3 {
+        Vector3::random_unit_vector(sampler)
+    }
+}
+
+/// Cosine weighted density over the hemisphere around a normal
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(normal: &Vector3) -> Self {
+        Self {
+            uvw: Onb::new(normal),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vector3) -> f64 {
+        let cosine_theta = Vector3::dot(&direction.unit_vector(), &self.uvw.w());
+        f64::max(0.0, cosine_theta / PI)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vector3 {
+        self.uvw
+            .transform(&Vector3::random_cosine_direction(sampler))
+    }
+}
+
+/// Density of directions from `origin` towards points sampled on a shape
+pub struct HittablePdf<'a> {
+    objects: &'a dyn Hittable,
+    origin: Point3,
+}
+
+impl<'a> HittablePdf<'a> {
+    pub fn new(objects: &'a dyn Hittable, origin: Point3) -> Self {
+        Self { objects, origin }
+    }
+}
+
+impl Pdf for HittablePdf<'_> {
+    fn value(&self, direction: &Vector3) -> f64 {
+        self.objects.pdf_value(&self.origin, direction)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vector3 {
+        self.objects.random(&self.origin, sampler)
+    }
+}
+
+/// Multiple importance sampling weight of a sample drawn from the density
+/// `pdf` when `other_pdf` could have produced it as well
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let a = pdf * pdf;
+    let b = other_pdf * other_pdf;
+    if a + b == 0.0 { 0.0 } else { a / (a + b) }
+}
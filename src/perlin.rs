@@ -1,5 +1,5 @@
+use crate::rng::Rng;
 use crate::vector::Point3;
-use rand::random_range;
 
 const POINT_COUNT: usize = 256;
 
@@ -12,15 +12,21 @@ pub struct Perlin {
 
 impl Perlin {
     pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    /// Noise with tables drawn from `seed`, the same seed gives the same noise
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
         let mut rand_floats = [0.; POINT_COUNT];
 
-        for i in 0..POINT_COUNT {
-            rand_floats[i] = random_range(-1f64..1f64);
+        for value in rand_floats.iter_mut() {
+            *value = rng.random_range(-1.0, 1.0);
         }
 
-        let perm_x = Self::generate_perm();
-        let perm_y = Self::generate_perm();
-        let perm_z = Self::generate_perm();
+        let perm_x = Self::generate_perm(&mut rng);
+        let perm_y = Self::generate_perm(&mut rng);
+        let perm_z = Self::generate_perm(&mut rng);
 
         Self {
             rand_floats,
@@ -41,14 +47,14 @@ impl Perlin {
 
         let mut c = [[[0.; 2]; 2]; 2];
 
-        for di in 0..2 {
-            for dj in 0..2 {
-                for dk in 0..2 {
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, cell) in row.iter_mut().enumerate() {
                     let idx = (self.perm_x[((i + di as i32) & 255) as usize]
                         + self.perm_y[((j + dj as i32) & 255) as usize]
                         + self.perm_z[((k + dk as i32) & 255) as usize])
                         & 255;
-                    c[di][dj][dk] = self.rand_floats[idx];
+                    *cell = self.rand_floats[idx];
                 }
             }
         }
@@ -63,13 +69,13 @@ impl Perlin {
 
         let mut accum = 0.;
 
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
+        for (i, plane) in c.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, cell) in row.iter().enumerate() {
                     let weight_u = if i == 1 { uu } else { 1. - uu };
                     let weight_v = if j == 1 { vv } else { 1. - vv };
                     let weight_w = if k == 1 { ww } else { 1. - ww };
-                    accum += weight_u * weight_v * weight_w * c[i][j][k];
+                    accum += weight_u * weight_v * weight_w * cell;
                 }
             }
         }
@@ -77,20 +83,26 @@ impl Perlin {
         accum
     }
 
-    fn generate_perm() -> [usize; POINT_COUNT] {
+    fn generate_perm(rng: &mut Rng) -> [usize; POINT_COUNT] {
         let mut p = [0; POINT_COUNT];
         for (i, v) in p.iter_mut().enumerate() {
             *v = i;
         }
 
-        Self::permute(&mut p, POINT_COUNT);
+        Self::permute(&mut p, POINT_COUNT, rng);
         p
     }
 
-    fn permute(p: &mut [usize; POINT_COUNT], n: usize) {
+    fn permute(p: &mut [usize; POINT_COUNT], n: usize, rng: &mut Rng) {
         for i in (1..n).rev() {
-            let target = random_range(0..i + 1);
+            let target = rng.random_index(i + 1);
             p.swap(i, target);
         }
     }
 }
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,11 +3,20 @@ use crate::vector::Vector3;
 pub struct Ray {
     origin: Vector3,
     direction: Vector3,
+    time: f64, // Moment within the exposure the ray samples
 }
 
 impl Ray {
     pub fn new(origin: Vector3, direction: Vector3) -> Self {
-        Self { origin, direction }
+        Self::new_at_time(origin, direction, 0.0)
+    }
+
+    pub fn new_at_time(origin: Vector3, direction: Vector3, time: f64) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn get_origin(&self) -> Vector3 {
@@ -18,6 +27,10 @@ impl Ray {
         self.direction
     }
 
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
     pub fn at(&self, t: f64) -> Vector3 {
         self.origin + t * self.direction
     }
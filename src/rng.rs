@@ -0,0 +1,80 @@
+//! Deterministic random numbers for rendering.
+//!
+//! Every pixel gets its own generator derived from the render seed and its
+//! coordinates, so the image doesn't depend on which thread rendered which
+//! pixel or in what order.
+
+/// PCG32 generator (O'Neill, "PCG: A Family of Simple Fast Space-Efficient
+/// Statistically Good Algorithms for Random Number Generation")
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+    inc: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, 0)
+    }
+
+    /// Generators with the same seed but different streams give unrelated sequences
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            inc: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Generator for one pixel of a render with the given seed
+    pub fn for_pixel(seed: u64, x: u32, y: u32) -> Self {
+        let pixel = ((y as u64) << 32) | x as u64;
+        Self::with_stream(mix(seed ^ mix(pixel)), pixel)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Self::MULTIPLIER).wrapping_add(self.inc);
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    /// Uniform value in [0, 1)
+    pub fn random_f64(&mut self) -> f64 {
+        // The top 53 bits fill the mantissa exactly
+        let bits = ((self.next_u32() as u64) << 32) | self.next_u32() as u64;
+        (bits >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform value in [min, max)
+    pub fn random_range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.random_f64()
+    }
+
+    /// Uniform index in [0, len)
+    pub fn random_index(&mut self, len: usize) -> usize {
+        ((self.random_f64() * len as f64) as usize).min(len - 1)
+    }
+}
+
+/// SplitMix64 finalizer, spreads nearby inputs over the whole range
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Well-mixed hash of several values, for deriving seeds
+pub fn hash(values: &[u64]) -> u64 {
+    values
+        .iter()
+        .fold(0x243f6a8885a308d3, |hash, &value| mix(hash ^ mix(value)))
+}
@@ -0,0 +1,85 @@
+use super::{ONE_MINUS_EPSILON, SampleState, Sampler, permutation_element};
+use crate::rng::hash;
+
+/// Bases of the dimensions the sequence covers, later dimensions fall back to
+/// hashed random numbers
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// The Halton sequence, the radical inverse of the sample index in a
+/// different prime base per dimension. The digits are Owen-scrambled with a
+/// seed per pixel and dimension, which breaks up the correlation between
+/// high dimensions and keeps neighbouring pixels from sharing their error.
+pub struct HaltonSampler {
+    seed: u64,
+    state: SampleState,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: SampleState::default(),
+        }
+    }
+
+    fn sample_dimension(&self, dimension: u32, key: u64) -> f64 {
+        let Some(&base) = PRIMES.get(dimension as usize) else {
+            return to_unit(hash(&[key, self.state.index as u64]));
+        };
+
+        scrambled_radical_inverse(base, self.state.index as u64, key)
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state = SampleState {
+            x,
+            y,
+            index,
+            dimension: 0,
+        };
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (dimension, key) = self.state.next_dimensions(1, self.seed);
+        self.sample_dimension(dimension, key)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (dimension, first) = self.state.next_dimensions(2, self.seed);
+        let second = hash(&[first]);
+        (
+            self.sample_dimension(dimension, first),
+            self.sample_dimension(dimension + 1, second),
+        )
+    }
+}
+
+/// Mirrors the digits of `index` in `base` around the radix point, permuting
+/// every digit depending on the digits before it. The leading zeros are
+/// permuted too, down to the precision of a double.
+fn scrambled_radical_inverse(base: u64, mut index: u64, key: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut reversed = 0;
+    let mut inverse_base_power = 1.0;
+
+    while 1.0 - inverse_base_power < 1.0 {
+        let next = index / base;
+        let digit = index - next * base;
+        let digit_key = hash(&[key, reversed]) as u32;
+        let digit = permutation_element(digit as u32, base as u32, digit_key) as u64;
+        reversed = reversed * base + digit;
+        inverse_base_power *= inverse_base;
+        index = next;
+    }
+
+    (reversed as f64 * inverse_base_power).min(ONE_MINUS_EPSILON)
+}
+
+fn to_unit(hash: u64) -> f64 {
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
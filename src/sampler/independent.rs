@@ -0,0 +1,31 @@
+use super::Sampler;
+use crate::rng::{Rng, hash};
+
+/// Fresh random numbers for every dimension of every sample
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.rng = Rng::for_pixel(hash(&[self.seed, index as u64]), x, y);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.random_f64()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.random_f64(), self.rng.random_f64())
+    }
+}
@@ -0,0 +1,171 @@
+//! Sources of the uniform numbers a path consumes.
+//!
+//! A path asks for its numbers one dimension at a time: two for the position
+//! inside the pixel, two for the lens, then a few per bounce for scattering
+//! and light sampling. Low-discrepancy samplers spread the samples of a pixel
+//! evenly over each of these dimensions, which converges faster than
+//! independent random numbers.
+
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+
+use crate::rng::{Rng, hash};
+use anyhow::{Result, anyhow};
+use halton::HaltonSampler;
+use independent::IndependentSampler;
+use sobol::SobolSampler;
+use std::fmt;
+use std::str::FromStr;
+use stratified::StratifiedSampler;
+
+pub trait Sampler {
+    /// Starts sample `index` of pixel (x, y), rewinding to the first dimension
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32);
+
+    /// Next dimension, uniform in [0, 1)
+    fn get_1d(&mut self) -> f64;
+
+    /// Next two dimensions, stratified together where the sampler can
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+/// A bare generator is an independent sampler without pixel boundaries
+impl Sampler for Rng {
+    fn start_pixel_sample(&mut self, _x: u32, _y: u32, _index: u32) {}
+
+    fn get_1d(&mut self) -> f64 {
+        self.random_f64()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.random_f64(), self.random_f64())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SamplerKind {
+    /// Independent uniform random numbers
+    #[default]
+    Independent,
+    /// Jittered strata, shuffled separately for every dimension
+    Stratified,
+    /// Halton sequence, randomly shifted per pixel
+    Halton,
+    /// Owen-scrambled Sobol points, shuffled separately for every dimension
+    Sobol,
+}
+
+impl SamplerKind {
+    pub const ALL: [SamplerKind; 4] = [
+        Self::Independent,
+        Self::Stratified,
+        Self::Halton,
+        Self::Sobol,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Independent => "independent",
+            Self::Stratified => "stratified",
+            Self::Halton => "halton",
+            Self::Sobol => "sobol",
+        }
+    }
+
+    /// Sampler for pixels taking `samples_per_pixel` samples each
+    pub fn build(&self, samples_per_pixel: u32, seed: u64) -> Box<dyn Sampler> {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        match self {
+            Self::Independent => Box::new(IndependentSampler::new(seed)),
+            Self::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel, seed)),
+            Self::Halton => Box::new(HaltonSampler::new(seed)),
+            Self::Sobol => Box::new(SobolSampler::new(samples_per_pixel, seed)),
+        }
+    }
+}
+
+impl FromStr for SamplerKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|kind| kind.name()).collect();
+                anyhow!(
+                    "unknown sampler '{}', expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for SamplerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Where a sampler is within the sample it is producing
+#[derive(Clone, Copy, Debug, Default)]
+struct SampleState {
+    x: u32,
+    y: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl SampleState {
+    /// Claims the next `count` dimensions and hashes the first of them
+    /// together with the pixel and seed
+    fn next_dimensions(&mut self, count: u32, seed: u64) -> (u32, u64) {
+        let dimension = self.dimension;
+        self.dimension += count;
+        let key = hash(&[seed, self.x as u64, self.y as u64, dimension as u64]);
+        (dimension, key)
+    }
+}
+
+/// Element `index` of a pseudo-random permutation of 0..len picked by `seed`
+/// (Kensler, "Correlated Multi-Jittered Sampling")
+fn permutation_element(mut index: u32, len: u32, seed: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        index ^= seed;
+        index = index.wrapping_mul(0xe170893d);
+        index ^= seed >> 16;
+        index ^= (index & w) >> 4;
+        index ^= seed >> 8;
+        index = index.wrapping_mul(0x0929eb3f);
+        index ^= seed >> 23;
+        index ^= (index & w) >> 1;
+        index = index.wrapping_mul(1 | seed >> 27);
+        index = index.wrapping_mul(0x6935fa69);
+        index ^= (index & w) >> 11;
+        index = index.wrapping_mul(0x74dcb303);
+        index ^= (index & w) >> 2;
+        index = index.wrapping_mul(0x9e501cc3);
+        index ^= (index & w) >> 2;
+        index = index.wrapping_mul(0xc860a3df);
+        index &= w;
+        index ^= index >> 5;
+        if index < len {
+            break;
+        }
+    }
+
+    index.wrapping_add(seed) % len
+}
+
+/// Largest double below one, samples are clamped to it so they stay in [0, 1)
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
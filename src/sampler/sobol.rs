@@ -0,0 +1,91 @@
+use super::{ONE_MINUS_EPSILON, SampleState, Sampler, permutation_element};
+use crate::rng::hash;
+
+/// Owen-scrambled Sobol points. Only the first two Sobol dimensions are
+/// used: every dimension, or pair of dimensions, shuffles the sample order
+/// and scrambles the points with its own seed, which keeps them uncorrelated
+/// with one another while each stays well stratified.
+pub struct SobolSampler {
+    samples_per_pixel: u32,
+    seed: u64,
+    state: SampleState,
+}
+
+impl SobolSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        Self {
+            samples_per_pixel,
+            seed,
+            state: SampleState::default(),
+        }
+    }
+
+    fn shuffled_index(&self, key: u64) -> u32 {
+        let index = self.state.index % self.samples_per_pixel;
+        permutation_element(index, self.samples_per_pixel, key as u32)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state = SampleState {
+            x,
+            y,
+            index,
+            dimension: 0,
+        };
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (_, key) = self.state.next_dimensions(1, self.seed);
+        let index = self.shuffled_index(key);
+        to_unit(owen_scramble(sobol_first(index), (key >> 32) as u32))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (_, key) = self.state.next_dimensions(2, self.seed);
+        let index = self.shuffled_index(key);
+        (
+            to_unit(owen_scramble(sobol_first(index), (key >> 32) as u32)),
+            to_unit(owen_scramble(sobol_second(index), hash(&[key]) as u32)),
+        )
+    }
+}
+
+/// First Sobol dimension, the van der Corput sequence in base 2
+fn sobol_first(index: u32) -> u32 {
+    index.reverse_bits()
+}
+
+/// Second Sobol dimension, from the primitive polynomial x + 1
+fn sobol_second(mut index: u32) -> u32 {
+    let mut direction = 1u32 << 31;
+    let mut value = 0;
+
+    while index != 0 {
+        if index & 1 != 0 {
+            value ^= direction;
+        }
+        direction ^= direction >> 1;
+        index >>= 1;
+    }
+
+    value
+}
+
+/// Nested uniform scrambling of the bits of `value`, hashed so that the
+/// flip of every bit depends only on the bits above it (Laine and Karras,
+/// "Stratified Sampling for Stochastic Transparency")
+fn owen_scramble(value: u32, seed: u32) -> u32 {
+    let mut v = value.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+    v.reverse_bits()
+}
+
+fn to_unit(value: u32) -> f64 {
+    (value as f64 / (1u64 << 32) as f64).min(ONE_MINUS_EPSILON)
+}
@@ -0,0 +1,63 @@
+use super::{ONE_MINUS_EPSILON, SampleState, Sampler, permutation_element};
+use crate::rng::{Rng, hash};
+
+/// Jittered sampling: every dimension is split into one stratum per sample
+/// and each sample of a pixel lands in a different stratum. Pairs of
+/// dimensions are split into a grid instead, so they are stratified together.
+pub struct StratifiedSampler {
+    samples_per_pixel: u32,
+    x_strata: u32,
+    y_strata: u32,
+    seed: u64,
+    state: SampleState,
+    rng: Rng,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        // The smallest grid with a cell for every sample
+        let x_strata = (samples_per_pixel as f64).sqrt().ceil() as u32;
+        let y_strata = samples_per_pixel.div_ceil(x_strata);
+
+        Self {
+            samples_per_pixel,
+            x_strata,
+            y_strata,
+            seed,
+            state: SampleState::default(),
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state = SampleState {
+            x,
+            y,
+            index,
+            dimension: 0,
+        };
+        self.rng = Rng::for_pixel(hash(&[self.seed, index as u64]), x, y);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (_, key) = self.state.next_dimensions(1, self.seed);
+        let strata = self.samples_per_pixel;
+        let stratum = permutation_element(self.state.index % strata, strata, key as u32);
+
+        ((stratum as f64 + self.rng.random_f64()) / strata as f64).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (_, key) = self.state.next_dimensions(2, self.seed);
+        let strata = self.x_strata * self.y_strata;
+        let stratum = permutation_element(self.state.index % strata, strata, key as u32);
+        let (x, y) = (stratum % self.x_strata, stratum / self.x_strata);
+
+        (
+            ((x as f64 + self.rng.random_f64()) / self.x_strata as f64).min(ONE_MINUS_EPSILON),
+            ((y as f64 + self.rng.random_f64()) / self.y_strata as f64).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
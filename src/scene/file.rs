@@ -0,0 +1,1035 @@
+//! TOML scene description format.
+//!
+//! A scene file holds `[camera]` and `[environment]` tables, named
+//! `[textures]`, `[materials]` and `[groups]`, and `[[objects]]` and
+//! `[[lights]]` arrays, with paths relative to the file. `docs/scenes.md`
+//! describes every key.
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::environment::Environment;
+use crate::environment::map::EnvironmentMap;
+use crate::environment::sky::Sky;
+use crate::hittable::bvh::BVH;
+use crate::hittable::constant_medium::ConstantMedium;
+use crate::hittable::grid_medium::GridMedium;
+use crate::hittable::instance::Instance;
+use crate::hittable::quad::{Quad, create_box};
+use crate::hittable::sphere::Sphere;
+use crate::hittable::transform::{AnimatedTransform, Transform};
+use crate::hittable::{Hittable, HittableList};
+use crate::image::Image;
+use crate::light::directional::DirectionalLight;
+use crate::light::point::PointLight;
+use crate::light::spot::SpotLight;
+use crate::light::{Light, LightList};
+use crate::material::Material;
+use crate::material::conductor::Conductor;
+use crate::material::dielectric::{Dielectric, DiffuseLight, RoughDielectric};
+use crate::material::isotropic::Isotropic;
+use crate::material::lambertian::Lambertian;
+use crate::material::metal::Metal;
+use crate::material::phase::PhaseFunction;
+use crate::material::principled::Principled;
+use crate::matrix::Matrix4;
+use crate::obj;
+use crate::scene::Scene;
+use crate::texture::Texture;
+use crate::texture::checker::CheckerTexture;
+use crate::texture::image::ImageTexture;
+use crate::texture::noise::NoiseTexture;
+use crate::texture::solid::SolidTexture;
+use crate::vector::Vector3;
+use crate::voxel::VoxelGrid;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use toml::Spanned;
+
+type Vec3 = [f64; 3];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    #[serde(default)]
+    camera: CameraDesc,
+    environment: Option<EnvironmentDesc>,
+    #[serde(default)]
+    textures: HashMap<String, Spanned<TextureDesc>>,
+    #[serde(default)]
+    materials: HashMap<String, Spanned<MaterialDesc>>,
+    #[serde(default)]
+    groups: HashMap<String, GroupDesc>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectDesc>>,
+    #[serde(default)]
+    lights: Vec<Spanned<LightDesc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GroupDesc {
+    objects: Vec<Spanned<ObjectDesc>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    aspect_ratio: Option<f64>,
+    image_width: Option<i32>,
+    samples_per_pixel: Option<i32>,
+    min_samples_per_pixel: Option<i32>,
+    adaptive_threshold: Option<f64>,
+    max_depth: Option<i32>,
+    roulette_depth: Option<i32>,
+    vfov: Option<f64>,
+    lookfrom: Option<Vec3>,
+    lookat: Option<Vec3>,
+    vup: Option<Vec3>,
+    defocus_angle: Option<f64>,
+    focus_dist: Option<f64>,
+    shutter_open: Option<f64>,
+    shutter_close: Option<f64>,
+    background: Option<Vec3>,
+    sampler: Option<String>,
+    exposure: Option<f64>,
+    tone_map: Option<String>,
+    white_point: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum EnvironmentDesc {
+    Image {
+        file: String,
+        #[serde(default)]
+        rotation: f64,
+        intensity: Option<f64>,
+    },
+    Sky {
+        elevation: f64,
+        #[serde(default)]
+        azimuth: f64,
+        turbidity: Option<f64>,
+        intensity: Option<f64>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightDesc {
+    Point {
+        position: Vec3,
+        intensity: Vec3,
+    },
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        intensity: Vec3,
+        inner_angle: f64,
+        outer_angle: f64,
+    },
+    Directional {
+        direction: Vec3,
+        irradiance: Vec3,
+        #[serde(default)]
+        angular_radius: f64,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum PhaseDesc {
+    Isotropic,
+    HenyeyGreenstein { g: f64 },
+    DoubleHenyeyGreenstein { g1: f64, g2: f64, weight: f64 },
+    Rayleigh,
+}
+
+/// A colour given either inline or as the name of a texture
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorSource {
+    Color(Vec3),
+    Texture(String),
+}
+
+/// A scalar given either inline or as the name of a texture
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScalarSource {
+    Value(f64),
+    Texture(String),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Solid { color: Vec3 },
+    Checker { scale: f64, even: Vec3, odd: Vec3 },
+    Image { file: String },
+    Noise { scale: f64 },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian {
+        albedo: ColorSource,
+    },
+    Metal {
+        albedo: Vec3,
+        fuzz: f64,
+    },
+    Conductor {
+        preset: Option<String>,
+        eta: Option<Vec3>,
+        k: Option<Vec3>,
+        #[serde(default)]
+        roughness: f64,
+    },
+    Dielectric {
+        refraction_index: f64,
+        roughness: Option<f64>,
+    },
+    DiffuseLight {
+        emit: ColorSource,
+    },
+    Isotropic {
+        albedo: ColorSource,
+    },
+    Principled {
+        base_color: ColorSource,
+        metallic: Option<ScalarSource>,
+        roughness: Option<ScalarSource>,
+        specular: Option<ScalarSource>,
+        clearcoat: Option<ScalarSource>,
+        clearcoat_roughness: Option<ScalarSource>,
+        sheen: Option<ScalarSource>,
+        sheen_tint: Option<ScalarSource>,
+        transmission: Option<ScalarSource>,
+        ior: Option<f64>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TransformDesc {
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+    Rotate { axis: Vec3, angle: f64 },
+    Scale(Vec3),
+    Translate(Vec3),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    Sphere {
+        center: Vec3,
+        end_center: Option<Vec3>,
+        radius: f64,
+        material: String,
+        #[serde(default)]
+        transforms: Vec<TransformDesc>,
+        end_transforms: Option<Vec<TransformDesc>>,
+    },
+    Quad {
+        q: Vec3,
+        u: Vec3,
+        v: Vec3,
+        material: String,
+        #[serde(default)]
+        transforms: Vec<TransformDesc>,
+        end_transforms: Option<Vec<TransformDesc>>,
+    },
+    Box {
+        a: Vec3,
+        b: Vec3,
+        material: String,
+        #[serde(default)]
+        transforms: Vec<TransformDesc>,
+        end_transforms: Option<Vec<TransformDesc>>,
+    },
+    Obj {
+        file: String,
+        material: Option<String>,
+        #[serde(default)]
+        transforms: Vec<TransformDesc>,
+        end_transforms: Option<Vec<TransformDesc>>,
+    },
+    ConstantMedium {
+        boundary: Box<ObjectDesc>,
+        density: f64,
+        albedo: ColorSource,
+        phase: Option<PhaseDesc>,
+        #[serde(default)]
+        transforms: Vec<TransformDesc>,
+        end_transforms: Option<Vec<TransformDesc>>,
+    },
+    GridMedium {
+        a: Vec3,
+        b: Vec3,
+        density: f64,
+        file: Option<String>,
+        noise: Option<f64>,
+        #[serde(default)]
+        seed: u64,
+        resolution: Option<[usize; 3]>,
+        albedo: Option<Vec3>,
+        albedo_file: Option<String>,
+        emission_file: Option<String>,
+        emission_scale: Option<f64>,
+        phase: Option<PhaseDesc>,
+        #[serde(default)]
+        transforms: Vec<TransformDesc>,
+        end_transforms: Option<Vec<TransformDesc>>,
+    },
+    Instance {
+        group: String,
+        material: Option<String>,
+        #[serde(default)]
+        transforms: Vec<TransformDesc>,
+    },
+}
+
+/// Loads a scene from a TOML file on disk
+pub fn load(path: &Path) -> Result<Scene> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("could not read scene file '{}'", path.display()))?;
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    parse_in(&source, dir).with_context(|| format!("invalid scene file '{}'", path.display()))
+}
+
+/// Builds a scene from the text of a TOML scene description
+pub fn parse(source: &str) -> Result<Scene> {
+    parse_in(source, Path::new(""))
+}
+
+/// Builds a scene from the text of a TOML scene description, resolving the
+/// files it refers to relative to `dir`
+pub fn parse_in(source: &str, dir: &Path) -> Result<Scene> {
+    let desc: SceneDesc = toml::from_str(source)?;
+    let builder = SceneBuilder::new(source, dir, &desc)?;
+
+    let mut world = HittableList::new();
+    let mut lights = HittableList::new();
+    for object in &desc.objects {
+        let line = builder.line(object.span().start);
+        let hittable = builder
+            .object(object.get_ref())
+            .with_context(|| format!("in object at line {}", line))?;
+        if builder.is_light(object.get_ref()) {
+            lights.add(Arc::clone(&hittable));
+        }
+        world.add(hittable);
+    }
+
+    let mut analytic_lights = LightList::new();
+    for light in &desc.lights {
+        let line = builder.line(light.span().start);
+        analytic_lights.add(
+            light
+                .get_ref()
+                .build()
+                .with_context(|| format!("in light at line {}", line))?,
+        );
+    }
+
+    let mut camera = desc.camera.build()?;
+    if let Some(environment) = &desc.environment {
+        camera.environment = Some(environment.build(dir).context("in environment")?);
+    }
+
+    let mut scene = Scene::new(world, lights, camera);
+    scene.analytic_lights = analytic_lights;
+    Ok(scene)
+}
+
+impl LightDesc {
+    fn build(&self) -> Result<Arc<dyn Light>> {
+        match self {
+            LightDesc::Point {
+                position,
+                intensity,
+            } => {
+                check_power("intensity", *intensity)?;
+                Ok(Arc::new(PointLight::new(vec3(*position), vec3(*intensity))))
+            }
+            LightDesc::Spot {
+                position,
+                direction,
+                intensity,
+                inner_angle,
+                outer_angle,
+            } => {
+                check_power("intensity", *intensity)?;
+                if !(0.0..=180.0).contains(outer_angle) {
+                    bail!("outer_angle must be between 0 and 180, got {}", outer_angle);
+                }
+                if !(0.0..=*outer_angle).contains(inner_angle) {
+                    bail!(
+                        "inner_angle must be between 0 and outer_angle, got {}",
+                        inner_angle
+                    );
+                }
+                Ok(Arc::new(SpotLight::new(
+                    vec3(*position),
+                    check_direction(*direction)?,
+                    vec3(*intensity),
+                    *inner_angle,
+                    *outer_angle,
+                )))
+            }
+            LightDesc::Directional {
+                direction,
+                irradiance,
+                angular_radius,
+            } => {
+                check_power("irradiance", *irradiance)?;
+                if !(0.0..90.0).contains(angular_radius) {
+                    bail!(
+                        "angular_radius must be at least 0 and below 90, got {}",
+                        angular_radius
+                    );
+                }
+                Ok(Arc::new(DirectionalLight::new(
+                    check_direction(*direction)?,
+                    vec3(*irradiance),
+                    *angular_radius,
+                )))
+            }
+        }
+    }
+}
+
+impl EnvironmentDesc {
+    fn build(&self, dir: &Path) -> Result<Arc<dyn Environment>> {
+        match self {
+            EnvironmentDesc::Image {
+                file,
+                rotation,
+                intensity,
+            } => {
+                let mut map = EnvironmentMap::from_file(&dir.join(file))?;
+                map.rotation = *rotation;
+                map.intensity = environment_intensity(*intensity)?;
+                Ok(Arc::new(map))
+            }
+            EnvironmentDesc::Sky {
+                elevation,
+                azimuth,
+                turbidity,
+                intensity,
+            } => {
+                if !(0.0..=90.0).contains(elevation) {
+                    bail!("elevation must be between 0 and 90, got {}", elevation);
+                }
+                let turbidity = turbidity.unwrap_or(3.0);
+                if !(2.0..=10.0).contains(&turbidity) {
+                    bail!("turbidity must be between 2 and 10, got {}", turbidity);
+                }
+                let mut sky = Sky::new(*elevation, *azimuth, turbidity);
+                sky.intensity = environment_intensity(*intensity)?;
+                Ok(Arc::new(sky))
+            }
+        }
+    }
+}
+
+fn environment_intensity(intensity: Option<f64>) -> Result<f64> {
+    let intensity = intensity.unwrap_or(1.0);
+    if intensity < 0.0 {
+        bail!("intensity must not be negative, got {}", intensity);
+    }
+    Ok(intensity)
+}
+
+impl CameraDesc {
+    fn build(&self) -> Result<Camera> {
+        let mut camera = Camera::new();
+
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            camera.aspect_ratio = aspect_ratio;
+        }
+        if let Some(image_width) = self.image_width {
+            check_positive("image_width", image_width)?;
+            camera.image_width = image_width;
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            check_positive("samples_per_pixel", samples_per_pixel)?;
+            camera.samples_per_pixel = samples_per_pixel;
+        }
+        if let Some(min_samples_per_pixel) = self.min_samples_per_pixel {
+            check_positive("min_samples_per_pixel", min_samples_per_pixel)?;
+            if min_samples_per_pixel > camera.samples_per_pixel {
+                bail!(
+                    "min_samples_per_pixel {} is more than samples_per_pixel {}",
+                    min_samples_per_pixel,
+                    camera.samples_per_pixel
+                );
+            }
+            camera.min_samples_per_pixel = min_samples_per_pixel;
+        }
+        if let Some(adaptive_threshold) = self.adaptive_threshold {
+            camera.adaptive_threshold = adaptive_threshold;
+        }
+        if let Some(max_depth) = self.max_depth {
+            check_positive("max_depth", max_depth)?;
+            camera.max_depth = max_depth;
+        }
+        if let Some(roulette_depth) = self.roulette_depth {
+            camera.roulette_depth = roulette_depth;
+        }
+        if let Some(vfov) = self.vfov {
+            camera.vfov = vfov;
+        }
+        if let Some(lookfrom) = self.lookfrom {
+            camera.lookfrom = vec3(lookfrom);
+        }
+        if let Some(lookat) = self.lookat {
+            camera.lookat = vec3(lookat);
+        }
+        if let Some(vup) = self.vup {
+            camera.vup = vec3(vup);
+        }
+        if let Some(defocus_angle) = self.defocus_angle {
+            camera.defocus_angle = defocus_angle;
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            camera.focus_dist = focus_dist;
+        }
+        if let Some(shutter_open) = self.shutter_open {
+            camera.shutter_open = shutter_open;
+        }
+        if let Some(shutter_close) = self.shutter_close {
+            camera.shutter_close = shutter_close;
+        }
+        if let Some(background) = self.background {
+            camera.background = vec3(background);
+        }
+        if let Some(sampler) = &self.sampler {
+            camera.sampler = sampler.parse().context("in camera")?;
+        }
+        if let Some(exposure) = self.exposure {
+            camera.tone_mapper.exposure = exposure;
+        }
+        if let Some(operator) = &self.tone_map {
+            camera.tone_mapper.operator = operator.parse().context("in camera")?;
+        }
+        if let Some(white_point) = self.white_point {
+            camera.tone_mapper.white_point = white_point;
+        }
+
+        Ok(camera)
+    }
+}
+
+struct SceneBuilder<'a> {
+    source: &'a str,
+    dir: &'a Path,
+    textures: HashMap<&'a str, Arc<dyn Texture>>,
+    materials: HashMap<&'a str, Arc<dyn Material>>,
+    emissive: HashSet<&'a str>,
+    groups: HashMap<&'a str, Arc<BVH>>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn new(source: &'a str, dir: &'a Path, desc: &'a SceneDesc) -> Result<Self> {
+        let mut builder = Self {
+            source,
+            dir,
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            emissive: HashSet::new(),
+            groups: HashMap::new(),
+        };
+
+        for (name, texture) in &desc.textures {
+            let line = builder.line(texture.span().start);
+            let built = builder
+                .texture(texture.get_ref())
+                .with_context(|| format!("in texture '{}' at line {}", name, line))?;
+            builder.textures.insert(name, built);
+        }
+
+        for (name, material) in &desc.materials {
+            let line = builder.line(material.span().start);
+            let built = builder
+                .material(material.get_ref())
+                .with_context(|| format!("in material '{}' at line {}", name, line))?;
+            builder.materials.insert(name, built);
+            if matches!(material.get_ref(), MaterialDesc::DiffuseLight { .. }) {
+                builder.emissive.insert(name);
+            }
+        }
+
+        for (name, group) in &desc.groups {
+            let built = builder
+                .group(group)
+                .with_context(|| format!("in group '{}'", name))?;
+            builder.groups.insert(name, built);
+        }
+
+        Ok(builder)
+    }
+
+    /// Converts a byte offset into the source to a 1-based line number
+    fn line(&self, offset: usize) -> usize {
+        self.source[..offset].matches('\n').count() + 1
+    }
+
+    fn texture(&self, desc: &TextureDesc) -> Result<Arc<dyn Texture>> {
+        Ok(match desc {
+            TextureDesc::Solid { color } => Arc::new(SolidTexture::new(vec3(*color))),
+            TextureDesc::Checker { scale, even, odd } => {
+                Arc::new(CheckerTexture::new_colors(*scale, vec3(*even), vec3(*odd)))
+            }
+            TextureDesc::Image { file } => {
+                let path = self.dir.join(file);
+                let mut image = Image::new();
+                if !image.load(&path.to_string_lossy()) {
+                    bail!("could not load texture image '{}'", path.display());
+                }
+                Arc::new(ImageTexture::new(image))
+            }
+            TextureDesc::Noise { scale } => Arc::new(NoiseTexture::new(*scale)),
+        })
+    }
+
+    fn color_source(&self, source: &ColorSource) -> Result<Arc<dyn Texture>> {
+        match source {
+            ColorSource::Color(color) => Ok(Arc::new(SolidTexture::new(vec3(*color)))),
+            ColorSource::Texture(name) => self.texture_named(name),
+        }
+    }
+
+    /// Texture for a scalar parameter, `None` when the key was left out
+    fn scalar_source(
+        &self,
+        key: &str,
+        source: &Option<ScalarSource>,
+    ) -> Result<Option<Arc<dyn Texture>>> {
+        match source {
+            None => Ok(None),
+            Some(ScalarSource::Value(value)) => {
+                if !(0.0..=1.0).contains(value) {
+                    bail!("{} must be between 0 and 1, got {}", key, value);
+                }
+                Ok(Some(Principled::scalar(*value)))
+            }
+            Some(ScalarSource::Texture(name)) => self.texture_named(name).map(Some),
+        }
+    }
+
+    fn texture_named(&self, name: &str) -> Result<Arc<dyn Texture>> {
+        self.textures
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown texture '{}'", name))
+    }
+
+    fn material(&self, desc: &MaterialDesc) -> Result<Arc<dyn Material>> {
+        Ok(match desc {
+            MaterialDesc::Lambertian { albedo } => {
+                Arc::new(Lambertian::new_texture(self.color_source(albedo)?))
+            }
+            MaterialDesc::Metal { albedo, fuzz } => Arc::new(Metal::new(vec3(*albedo), *fuzz)),
+            MaterialDesc::Conductor {
+                preset,
+                eta,
+                k,
+                roughness,
+            } => {
+                check_roughness(*roughness)?;
+                match (preset, eta, k) {
+                    (Some(preset), None, None) => {
+                        Arc::new(Conductor::from_preset(preset.parse()?, *roughness))
+                    }
+                    (None, Some(eta), Some(k)) => {
+                        Arc::new(Conductor::new(vec3(*eta), vec3(*k), *roughness))
+                    }
+                    _ => bail!("conductor needs either a preset or both eta and k"),
+                }
+            }
+            MaterialDesc::Dielectric {
+                refraction_index,
+                roughness: None,
+            } => Arc::new(Dielectric::new(*refraction_index)),
+            MaterialDesc::Dielectric {
+                refraction_index,
+                roughness: Some(roughness),
+            } => {
+                check_roughness(*roughness)?;
+                Arc::new(RoughDielectric::new(*refraction_index, *roughness))
+            }
+            MaterialDesc::DiffuseLight { emit } => {
+                Arc::new(DiffuseLight::new(self.color_source(emit)?))
+            }
+            MaterialDesc::Isotropic { albedo } => {
+                Arc::new(Isotropic::new(self.color_source(albedo)?))
+            }
+            MaterialDesc::Principled {
+                base_color,
+                metallic,
+                roughness,
+                specular,
+                clearcoat,
+                clearcoat_roughness,
+                sheen,
+                sheen_tint,
+                transmission,
+                ior,
+            } => {
+                let mut principled = Principled::new_texture(self.color_source(base_color)?);
+                let parameters = [
+                    ("metallic", metallic, &mut principled.metallic),
+                    ("roughness", roughness, &mut principled.roughness),
+                    ("specular", specular, &mut principled.specular),
+                    ("clearcoat", clearcoat, &mut principled.clearcoat),
+                    (
+                        "clearcoat_roughness",
+                        clearcoat_roughness,
+                        &mut principled.clearcoat_roughness,
+                    ),
+                    ("sheen", sheen, &mut principled.sheen),
+                    ("sheen_tint", sheen_tint, &mut principled.sheen_tint),
+                    ("transmission", transmission, &mut principled.transmission),
+                ];
+                for (key, source, texture) in parameters {
+                    if let Some(source) = self.scalar_source(key, source)? {
+                        *texture = source;
+                    }
+                }
+                if let Some(ior) = ior {
+                    if *ior <= 0.0 {
+                        bail!("ior must be positive, got {}", ior);
+                    }
+                    principled.ior = *ior;
+                }
+                Arc::new(principled)
+            }
+        })
+    }
+
+    fn material_named(&self, name: &str) -> Result<Arc<dyn Material>> {
+        self.materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown material '{}'", name))
+    }
+
+    /// Builds the objects of a group into the BVH its instances share
+    fn group(&self, desc: &GroupDesc) -> Result<Arc<BVH>> {
+        let mut objects = Vec::with_capacity(desc.objects.len());
+        for object in &desc.objects {
+            let line = self.line(object.span().start);
+            if matches!(object.get_ref(), ObjectDesc::Instance { .. }) {
+                bail!(
+                    "object at line {} is an instance, groups can't be nested",
+                    line
+                );
+            }
+            let hittable = self
+                .object(object.get_ref())
+                .with_context(|| format!("in object at line {}", line))?;
+            objects.push(hittable);
+        }
+
+        Ok(Arc::new(BVH::new_from_objects(objects)))
+    }
+
+    fn group_named(&self, name: &str) -> Result<Arc<BVH>> {
+        self.groups
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown group '{}'", name))
+    }
+
+    /// Whether the object is an emitter whose surface can be sampled, which
+    /// moving objects can't be
+    fn is_light(&self, desc: &ObjectDesc) -> bool {
+        match desc {
+            ObjectDesc::Sphere {
+                material,
+                end_center: None,
+                end_transforms: None,
+                ..
+            }
+            | ObjectDesc::Quad {
+                material,
+                end_transforms: None,
+                ..
+            }
+            | ObjectDesc::Box {
+                material,
+                end_transforms: None,
+                ..
+            } => self.emissive.contains(material.as_str()),
+            ObjectDesc::Sphere { .. } | ObjectDesc::Quad { .. } | ObjectDesc::Box { .. } => false,
+            ObjectDesc::Obj { .. }
+            | ObjectDesc::ConstantMedium { .. }
+            | ObjectDesc::GridMedium { .. }
+            | ObjectDesc::Instance { .. } => false,
+        }
+    }
+
+    fn object(&self, desc: &ObjectDesc) -> Result<Arc<dyn Hittable>> {
+        let (object, transforms, end_transforms): (Arc<dyn Hittable>, _, _) = match desc {
+            ObjectDesc::Sphere {
+                center,
+                end_center,
+                radius,
+                material,
+                transforms,
+                end_transforms,
+            } => {
+                if *radius <= 0.0 {
+                    bail!("sphere radius must be positive, got {}", radius);
+                }
+                let mat = self.material_named(material)?;
+                let sphere = match end_center {
+                    Some(end_center) => {
+                        Sphere::new_moving(vec3(*center), vec3(*end_center), *radius, mat)
+                    }
+                    None => Sphere::new(vec3(*center), *radius, mat),
+                };
+                (Arc::new(sphere), transforms, end_transforms)
+            }
+            ObjectDesc::Quad {
+                q,
+                u,
+                v,
+                material,
+                transforms,
+                end_transforms,
+            } => {
+                let quad = Quad::new(vec3(*q), vec3(*u), vec3(*v), self.material_named(material)?);
+                (Arc::new(quad), transforms, end_transforms)
+            }
+            ObjectDesc::Box {
+                a,
+                b,
+                material,
+                transforms,
+                end_transforms,
+            } => {
+                let sides = create_box(vec3(*a), vec3(*b), self.material_named(material)?);
+                (Arc::new(sides), transforms, end_transforms)
+            }
+            ObjectDesc::Obj {
+                file,
+                material,
+                transforms,
+                end_transforms,
+            } => {
+                let default_material = match material {
+                    Some(name) => self.material_named(name)?,
+                    None => Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+                };
+                let meshes = obj::load(&self.dir.join(file), default_material)?;
+                (Arc::new(meshes), transforms, end_transforms)
+            }
+            ObjectDesc::ConstantMedium {
+                boundary,
+                density,
+                albedo,
+                phase,
+                transforms,
+                end_transforms,
+            } => {
+                if *density <= 0.0 {
+                    bail!("medium density must be positive, got {}", density);
+                }
+                let phase = phase_function(phase.as_ref())?;
+                let boundary = self.object(boundary).context("in medium boundary")?;
+                let medium = ConstantMedium::with_phase(
+                    boundary,
+                    *density,
+                    self.color_source(albedo)?,
+                    phase,
+                );
+                (Arc::new(medium), transforms, end_transforms)
+            }
+            ObjectDesc::GridMedium {
+                a,
+                b,
+                density,
+                file,
+                noise,
+                seed,
+                resolution,
+                albedo,
+                albedo_file,
+                emission_file,
+                emission_scale,
+                phase,
+                transforms,
+                end_transforms,
+            } => {
+                if *density <= 0.0 {
+                    bail!("medium density must be positive, got {}", density);
+                }
+                if resolution.is_some_and(|r| r.contains(&0)) {
+                    bail!("resolution must not be zero along any axis");
+                }
+                let mut grid = match (file, noise) {
+                    (Some(file), None) => VoxelGrid::from_file(&self.dir.join(file), *resolution)?,
+                    (None, Some(scale)) => {
+                        VoxelGrid::from_noise(resolution.unwrap_or([64, 64, 64]), *scale, *seed)
+                    }
+                    _ => bail!("grid medium needs either a file or a noise scale"),
+                };
+                if let Some(albedo_file) = albedo_file {
+                    let albedo = grid.read_colors(&self.dir.join(albedo_file))?;
+                    grid.set_albedo(albedo);
+                }
+                if let Some(emission_file) = emission_file {
+                    let scale = emission_scale.unwrap_or(1.0);
+                    if scale < 0.0 {
+                        bail!("emission_scale must not be negative, got {}", scale);
+                    }
+                    let emission = grid.read_colors(&self.dir.join(emission_file))?;
+                    grid.set_emission(emission.into_iter().map(|e| scale * e).collect());
+                }
+
+                let albedo = albedo.map_or(Color::new(1.0, 1.0, 1.0), vec3);
+                let mut medium = GridMedium::new(grid, vec3(*a), vec3(*b), *density, albedo);
+                medium.set_phase(phase_function(phase.as_ref())?);
+                (Arc::new(medium), transforms, end_transforms)
+            }
+            ObjectDesc::Instance {
+                group,
+                material,
+                transforms,
+            } => {
+                // The instance carries the transforms itself rather than
+                // being wrapped in another one
+                let prototype = self.group_named(group)?;
+                let matrix = Self::matrix(transforms)?;
+                let instance = match material {
+                    Some(name) => {
+                        Instance::with_material(prototype, matrix, self.material_named(name)?)
+                    }
+                    None => Instance::new(prototype, matrix),
+                };
+                return Ok(Arc::new(instance));
+            }
+        };
+
+        let Some(end_transforms) = end_transforms else {
+            return Self::apply_transforms(object, transforms);
+        };
+
+        let (start, end) = (Self::matrix(transforms)?, Self::matrix(end_transforms)?);
+        if (start.determinant3() < 0.0) != (end.determinant3() < 0.0) {
+            bail!("only one of transforms and end_transforms mirrors the object");
+        }
+
+        Ok(Arc::new(AnimatedTransform::new(object, start, end)))
+    }
+
+    /// Wraps `object` in a single transform combining `transforms`, the
+    /// first of which is applied first
+    fn apply_transforms(
+        object: Arc<dyn Hittable>,
+        transforms: &[TransformDesc],
+    ) -> Result<Arc<dyn Hittable>> {
+        if transforms.is_empty() {
+            return Ok(object);
+        }
+
+        Ok(Arc::new(Transform::new(object, Self::matrix(transforms)?)))
+    }
+
+    /// Single matrix for `transforms`, checked to be invertible
+    fn matrix(transforms: &[TransformDesc]) -> Result<Matrix4> {
+        let matrix = transforms
+            .iter()
+            .fold(Matrix4::IDENTITY, |matrix, transform| {
+                matrix.then(&match transform {
+                    TransformDesc::RotateX(angle) => Matrix4::rotate_x(*angle),
+                    TransformDesc::RotateY(angle) => Matrix4::rotate_y(*angle),
+                    TransformDesc::RotateZ(angle) => Matrix4::rotate_z(*angle),
+                    TransformDesc::Rotate { axis, angle } => Matrix4::rotate(*angle, vec3(*axis)),
+                    TransformDesc::Scale(factors) => Matrix4::scale(vec3(*factors)),
+                    TransformDesc::Translate(offset) => Matrix4::translate(vec3(*offset)),
+                })
+            });
+        if matrix.inverse().is_none() {
+            bail!("transforms collapse the object, a scale factor is zero");
+        }
+
+        Ok(matrix)
+    }
+}
+
+fn check_positive(key: &str, value: i32) -> Result<()> {
+    if value < 1 {
+        bail!("{} must be positive, got {}", key, value);
+    }
+    Ok(())
+}
+
+fn check_roughness(roughness: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&roughness) {
+        bail!("roughness must be between 0 and 1, got {}", roughness);
+    }
+    Ok(())
+}
+
+/// Phase function described by `desc`, isotropic when there is none
+fn phase_function(desc: Option<&PhaseDesc>) -> Result<PhaseFunction> {
+    let check_g = |key: &str, g: f64| {
+        if g.abs() >= 1.0 {
+            bail!("{} must be strictly between -1 and 1, got {}", key, g);
+        }
+        Ok(())
+    };
+
+    Ok(match desc {
+        None | Some(PhaseDesc::Isotropic) => PhaseFunction::Isotropic,
+        Some(PhaseDesc::HenyeyGreenstein { g }) => {
+            check_g("g", *g)?;
+            PhaseFunction::HenyeyGreenstein { g: *g }
+        }
+        Some(PhaseDesc::DoubleHenyeyGreenstein { g1, g2, weight }) => {
+            check_g("g1", *g1)?;
+            check_g("g2", *g2)?;
+            if !(0.0..=1.0).contains(weight) {
+                bail!("weight must be between 0 and 1, got {}", weight);
+            }
+            PhaseFunction::DoubleHenyeyGreenstein {
+                g1: *g1,
+                g2: *g2,
+                weight: *weight,
+            }
+        }
+        Some(PhaseDesc::Rayleigh) => PhaseFunction::Rayleigh,
+    })
+}
+
+fn check_power(key: &str, power: Vec3) -> Result<()> {
+    if power.iter().any(|&channel| channel < 0.0) {
+        bail!("{} must not be negative, got {:?}", key, power);
+    }
+    Ok(())
+}
+
+fn check_direction(direction: Vec3) -> Result<Vector3> {
+    let direction = vec3(direction);
+    if direction.near_zero() {
+        bail!("direction must not be zero");
+    }
+    Ok(direction.unit_vector())
+}
+
+fn vec3(v: Vec3) -> Vector3 {
+    Vector3::new(v[0], v[1], v[2])
+}
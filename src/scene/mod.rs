@@ -0,0 +1,40 @@
+pub mod builtin;
+pub mod file;
+
+use crate::camera::Camera;
+use crate::hittable::HittableList;
+use crate::light::LightList;
+use anyhow::Result;
+use std::path::Path;
+
+/// A world together with the camera that should be used to render it.
+/// `lights` holds the emitters of the world that are sampled directly, and
+/// `analytic_lights` the lights that have no surface at all.
+pub struct Scene {
+    pub world: HittableList,
+    pub lights: HittableList,
+    pub analytic_lights: LightList,
+    pub camera: Camera,
+}
+
+impl Scene {
+    pub fn new(world: HittableList, lights: HittableList, camera: Camera) -> Self {
+        Self {
+            world,
+            lights,
+            analytic_lights: LightList::new(),
+            camera,
+        }
+    }
+
+    /// Loads a scene from a TOML scene description, see `docs/scenes.md` for the format
+    pub fn from_file(path: &Path) -> Result<Self> {
+        file::load(path)
+    }
+
+    /// Renders the scene with its own camera settings
+    pub fn render(self) -> Result<()> {
+        let mut camera = self.camera;
+        camera.render(self.world, self.lights, self.analytic_lights)
+    }
+}
@@ -7,7 +7,7 @@ use crate::color::Color;
 use crate::vector::Point3;
 
 pub trait Texture: Send + Sync {
-    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
         Color::new(0.0, 0.0, 0.0)
     }
 }
@@ -0,0 +1,198 @@
+//! Mapping linear radiance to displayable values before quantization.
+//!
+//! Exposure scales the radiance by `2^ev`, an operator compresses it into
+//! [0, 1] and the sRGB transfer function encodes the result. Float formats
+//! skip this stage and store the radiance untouched.
+
+use crate::color::Color;
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::str::FromStr;
+
+/// Curve used to bring scene radiance into the displayable range
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Operator {
+    /// Cut off everything above 1
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`, which never reaches white
+    Reinhard,
+    /// Reinhard with a white point that maps to 1
+    ExtendedReinhard,
+    /// John Hable's filmic curve from Uncharted 2
+    Hable,
+    /// Stephen Hill's fit of the ACES reference and output transforms
+    Aces,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 5] = [
+        Self::Clamp,
+        Self::Reinhard,
+        Self::ExtendedReinhard,
+        Self::Hable,
+        Self::Aces,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Clamp => "clamp",
+            Self::Reinhard => "reinhard",
+            Self::ExtendedReinhard => "extended-reinhard",
+            Self::Hable => "hable",
+            Self::Aces => "aces",
+        }
+    }
+}
+
+impl FromStr for Operator {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|operator| operator.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|operator| operator.name()).collect();
+                anyhow!(
+                    "unknown tone mapping operator '{}', expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapper {
+    pub exposure: f64, // Exposure compensation in stops
+    pub operator: Operator,
+    pub white_point: f64, // Smallest radiance mapped to white by extended Reinhard
+}
+
+impl ToneMapper {
+    pub fn new() -> Self {
+        Self {
+            exposure: 0.0,
+            operator: Operator::Clamp,
+            white_point: 4.0,
+        }
+    }
+
+    /// Applies exposure and the operator, returning linear values in [0, 1]
+    pub fn map(&self, radiance: Color) -> Color {
+        let exposed = 2f64.powf(self.exposure) * radiance;
+        let c = Color::new(
+            sanitize(exposed.x()),
+            sanitize(exposed.y()),
+            sanitize(exposed.z()),
+        );
+
+        let mapped = match self.operator {
+            Operator::Clamp => c,
+            Operator::Reinhard => per_channel(c, |x| x / (1.0 + x)),
+            Operator::ExtendedReinhard => {
+                let white_squared = self.white_point * self.white_point;
+                per_channel(c, |x| x * (1.0 + x / white_squared) / (1.0 + x))
+            }
+            Operator::Hable => hable(c),
+            Operator::Aces => aces_fitted(c),
+        };
+
+        per_channel(mapped, |x| x.clamp(0.0, 1.0))
+    }
+
+    /// Maps radiance all the way to sRGB encoded values in [0, 1]
+    pub fn to_display(&self, radiance: Color) -> Color {
+        per_channel(self.map(radiance), srgb_encode)
+    }
+}
+
+impl Default for ToneMapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The sRGB transfer function, linear segment included
+pub fn srgb_encode(linear: f64) -> f64 {
+    if linear <= 0.0031308 {
+        12.92 * linear.max(0.0)
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of [`srgb_encode`]
+pub fn srgb_decode(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// NaNs and negative values from numerical trouble would otherwise spread
+// through the curves
+fn sanitize(x: f64) -> f64 {
+    if x.is_nan() { 0.0 } else { x.max(0.0) }
+}
+
+fn per_channel(c: Color, f: impl Fn(f64) -> f64) -> Color {
+    Color::new(f(c.x()), f(c.y()), f(c.z()))
+}
+
+fn hable(c: Color) -> Color {
+    fn partial(x: f64) -> f64 {
+        const A: f64 = 0.15; // Shoulder strength
+        const B: f64 = 0.50; // Linear strength
+        const C: f64 = 0.10; // Linear angle
+        const D: f64 = 0.20; // Toe strength
+        const E: f64 = 0.02; // Toe numerator
+        const F: f64 = 0.30; // Toe denominator
+        ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+    }
+
+    const EXPOSURE_BIAS: f64 = 2.0;
+    const WHITE: f64 = 11.2;
+
+    let white_scale = 1.0 / partial(WHITE);
+    per_channel(c, |x| partial(EXPOSURE_BIAS * x) * white_scale)
+}
+
+fn aces_fitted(c: Color) -> Color {
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    const INPUT: [[f64; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    const OUTPUT: [[f64; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+
+    let c = multiply(&INPUT, c);
+    let c = per_channel(c, |x| {
+        let a = x * (x + 0.0245786) - 0.000090537;
+        let b = x * (0.983729 * x + 0.4329510) + 0.238081;
+        a / b
+    });
+    multiply(&OUTPUT, c)
+}
+
+fn multiply(m: &[[f64; 3]; 3], c: Color) -> Color {
+    Color::new(
+        m[0][0] * c.x() + m[0][1] * c.y() + m[0][2] * c.z(),
+        m[1][0] * c.x() + m[1][1] * c.y() + m[1][2] * c.z(),
+        m[2][0] * c.x() + m[2][1] * c.y() + m[2][2] * c.z(),
+    )
+}
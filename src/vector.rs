@@ -238,34 +238,36 @@ impl Vector3 {
         *self / self.length()
     }
 
-    pub fn random() -> Vector3 {
+    pub fn random(sampler: &mut dyn Sampler) -> Vector3 {
         Vector3 {
-            x: random_range(0f64..1f64),
-            y: random_range(0f64..1f64),
-            z: random_range(0f64..1f64),
+            x: sampler.get_1d(),
+            y: sampler.get_1d(),
+            z: sampler.get_1d(),
         }
     }
 
-    pub fn random_range(min: f64, max: f64) -> Vector3 {
+    pub fn random_range(sampler: &mut dyn Sampler, min: f64, max: f64) -> Vector3 {
+        let p = Vector3::random(sampler);
         Vector3 {
-            x: random_range(min..max),
-            y: random_range(min..max),
-            z: random_range(min..max),
+            x: min + (max - min) * p.x,
+            y: min + (max - min) * p.y,
+            z: min + (max - min) * p.z,
         }
     }
 
-    pub fn random_unit_vector() -> Vector3 {
-        loop {
-            let p = Vector3::random_range(-1., 1.);
-            let lensq = p.length_squared();
-            if 1e-160 < lensq && lensq <= 1.0 {
-                return p / lensq.sqrt();
-            }
-        }
+    /// Uniform direction on the unit sphere, from two sampler dimensions
+    pub fn random_unit_vector(sampler: &mut dyn Sampler) -> Vector3 {
+        let (r1, r2) = sampler.get_2d();
+
+        let z = 1.0 - 2.0 * r1;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * r2;
+
+        Vector3::new(r * phi.cos(), r * phi.sin(), z)
     }
 
-    pub fn random_on_hemisphere(normal: &Vector3) -> Vector3 {
-        let on_unit_sphere = Vector3::random_unit_vector();
+    pub fn random_on_hemisphere(sampler: &mut dyn Sampler, normal: &Vector3) -> Vector3 {
+        let on_unit_sphere = Vector3::random_unit_vector(sampler);
         if Vector3::dot(&on_unit_sphere, normal) > 0.0 {
             on_unit_sphere
         } else {
@@ -273,13 +275,47 @@ impl Vector3 {
         }
     }
 
-    pub fn random_in_unit_disk() -> Vector3 {
-        loop {
-            let p = Vector3::new(random_range(-1f64..1f64), random_range(-1f64..1f64), 0.0);
-            if p.length_squared() < 1.0 {
-                return p;
-            }
+    /// Random direction around +z with density cos(theta) / pi
+    pub fn random_cosine_direction(sampler: &mut dyn Sampler) -> Vector3 {
+        let (r1, r2) = sampler.get_2d();
+
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        let x = phi.cos() * r2.sqrt();
+        let y = phi.sin() * r2.sqrt();
+        let z = (1.0 - r2).sqrt();
+
+        Vector3::new(x, y, z)
+    }
+
+    /// Uniform direction in the cone around +z whose half-angle has cosine
+    /// `cos_max`, with density 1 / (2 pi (1 - cos_max))
+    pub fn random_in_cone(sampler: &mut dyn Sampler, cos_max: f64) -> Vector3 {
+        let (r1, r2) = sampler.get_2d();
+
+        let z = 1.0 - r1 * (1.0 - cos_max);
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * r2;
+
+        Vector3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    /// Uniform point in the unit disk in the xy plane, using Shirley's
+    /// concentric mapping so that strata of the square stay compact
+    pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Vector3 {
+        let (r1, r2) = sampler.get_2d();
+        let (a, b) = (2.0 * r1 - 1.0, 2.0 * r2 - 1.0);
+        if a == 0.0 && b == 0.0 {
+            return Vector3::new(0.0, 0.0, 0.0);
         }
+
+        let quarter_pi = std::f64::consts::FRAC_PI_4;
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, quarter_pi * (b / a))
+        } else {
+            (b, 2.0 * quarter_pi - quarter_pi * (a / b))
+        };
+
+        Vector3::new(r * theta.cos(), r * theta.sin(), 0.0)
     }
 }
 
@@ -287,7 +323,7 @@ impl Vector3 {
 pub type Point3 = Vector3;
 
 // Display implementation
-use rand::random_range;
+use crate::sampler::Sampler;
 use std::fmt;
 
 impl fmt::Display for Vector3 {
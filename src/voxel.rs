@@ -0,0 +1,324 @@
+//! Values on a regular 3D grid of voxels, filling the unit cube and read back
+//! with trilinear interpolation. Grids come from NumPy `.npy` files, from raw
+//! files of little-endian 32-bit floats, or from Perlin noise.
+//!
+//! Voxels are stored with x varying fastest, then y, then z, so a NumPy array
+//! is indexed `[z, y, x]`, with an extra last axis of 3 for colours.
+
+use crate::color::Color;
+use crate::perlin::Perlin;
+use crate::vector::Point3;
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::Path;
+
+/// Density of a participating medium on a grid, with an optional albedo and
+/// emission for every voxel
+pub struct VoxelGrid {
+    resolution: [usize; 3], // Voxels along x, y and z
+    density: Vec<f64>,
+    albedo: Option<Vec<Color>>,
+    emission: Option<Vec<Color>>,
+}
+
+impl VoxelGrid {
+    /// Grid of `resolution` voxels holding `density`, x varying fastest
+    pub fn new(resolution: [usize; 3], density: Vec<f64>) -> Self {
+        let [nx, ny, nz] = resolution;
+        assert!(nx * ny * nz > 0, "grid is empty");
+        assert_eq!(density.len(), nx * ny * nz, "grid is not {nx}x{ny}x{nz}");
+
+        Self {
+            resolution,
+            density: density.into_iter().map(|d| d.max(0.0)).collect(),
+            albedo: None,
+            emission: None,
+        }
+    }
+
+    /// Loads densities from a `.npy` file, or from a raw file of floats when
+    /// its `resolution` is given
+    pub fn from_file(path: &Path, resolution: Option<[usize; 3]>) -> Result<Self> {
+        let (resolution, channels, values) = read_voxels(path, resolution)?;
+        if channels != 1 {
+            bail!(
+                "density grid '{}' has {} channels, expected 1",
+                path.display(),
+                channels
+            );
+        }
+        Ok(Self::new(resolution, values))
+    }
+
+    /// Cloud-like densities in [0, 1] baked from Perlin noise, whose features
+    /// are about 1 / `scale` of the grid across. They fade out towards the
+    /// faces of the grid so that the medium has no hard edges.
+    pub fn from_noise(resolution: [usize; 3], scale: f64, seed: u64) -> Self {
+        let noise = Perlin::with_seed(seed);
+        let [nx, ny, nz] = resolution;
+        let density = (0..nx * ny * nz)
+            .map(|i| {
+                let p = Point3::new(
+                    ((i % nx) as f64 + 0.5) / nx as f64,
+                    ((i / nx % ny) as f64 + 0.5) / ny as f64,
+                    ((i / (nx * ny)) as f64 + 0.5) / nz as f64,
+                );
+
+                let mut fbm = 0.0;
+                let mut weight = 0.5;
+                let mut frequency = scale;
+                for _ in 0..4 {
+                    fbm += weight * noise.noise(&(p * frequency));
+                    weight *= 0.5;
+                    frequency *= 2.0;
+                }
+
+                // The noise carves into a ball, leaving billows around its
+                // rim, and dies down with it towards the faces
+                let from_center = 2.0 * (p - Point3::new(0.5, 0.5, 0.5));
+                let falloff = (1.0 - from_center.length_squared()).max(0.0);
+                (2.0 * (falloff - 0.4 + 1.5 * fbm * falloff.sqrt())).clamp(0.0, 1.0)
+            })
+            .collect();
+        Self::new(resolution, density)
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    /// Gives every voxel its own albedo, x varying fastest
+    pub fn set_albedo(&mut self, albedo: Vec<Color>) {
+        assert_eq!(albedo.len(), self.density.len(), "albedo grid size differs");
+        self.albedo = Some(albedo);
+    }
+
+    /// Gives every voxel its own emitted radiance, x varying fastest
+    pub fn set_emission(&mut self, emission: Vec<Color>) {
+        assert_eq!(
+            emission.len(),
+            self.density.len(),
+            "emission grid size differs"
+        );
+        self.emission = Some(emission);
+    }
+
+    /// Loads per-voxel colours with the same resolution as the densities,
+    /// from a file with either one grey or three colour channels
+    pub fn read_colors(&self, path: &Path) -> Result<Vec<Color>> {
+        let (resolution, channels, values) = read_voxels(path, Some(self.resolution))?;
+        if resolution != self.resolution {
+            bail!(
+                "grid '{}' is {:?}, the densities are {:?}",
+                path.display(),
+                resolution,
+                self.resolution
+            );
+        }
+
+        Ok(match channels {
+            3 => values
+                .chunks(3)
+                .map(|c| Color::new(c[0], c[1], c[2]))
+                .collect(),
+            _ => values.iter().map(|&v| Color::new(v, v, v)).collect(),
+        })
+    }
+
+    /// Density at `p` in the unit cube
+    pub fn density(&self, p: &Point3) -> f64 {
+        self.corners(p)
+            .iter()
+            .map(|&(i, w)| w * self.density[i])
+            .sum()
+    }
+
+    /// Albedo at `p`, if the voxels have their own
+    pub fn albedo(&self, p: &Point3) -> Option<Color> {
+        self.albedo
+            .as_ref()
+            .map(|albedo| self.interpolate(albedo, p))
+    }
+
+    /// Emitted radiance at `p`, if the voxels have any
+    pub fn emission(&self, p: &Point3) -> Option<Color> {
+        self.emission
+            .as_ref()
+            .map(|emission| self.interpolate(emission, p))
+    }
+
+    /// Largest density anywhere in the box from `lo` to `hi`, corners of the
+    /// unit cube
+    pub fn max_density(&self, lo: &Point3, hi: &Point3) -> f64 {
+        // A point takes its value from the voxels whose centres are within
+        // one voxel of it
+        let range = |axis: usize| {
+            let n = self.resolution[axis];
+            let first = (lo[axis] * n as f64 - 0.5).floor().max(0.0) as usize;
+            let last = ((hi[axis] * n as f64 - 0.5).floor() + 1.0).max(0.0) as usize;
+            first.min(n - 1)..=last.min(n - 1)
+        };
+
+        let [nx, ny, _] = self.resolution;
+        let mut max: f64 = 0.0;
+        for z in range(2) {
+            for y in range(1) {
+                for x in range(0) {
+                    max = max.max(self.density[(z * ny + y) * nx + x]);
+                }
+            }
+        }
+        max
+    }
+
+    fn interpolate(&self, colors: &[Color], p: &Point3) -> Color {
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        for (i, w) in self.corners(p) {
+            color += w * colors[i];
+        }
+        color
+    }
+
+    /// The eight voxels around `p` with their trilinear weights, holding the
+    /// value of the outermost voxels out to the faces of the cube
+    fn corners(&self, p: &Point3) -> [(usize, f64); 8] {
+        let [nx, ny, _] = self.resolution;
+        let mut index = [[0usize; 2]; 3];
+        let mut weight = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            let x = (p[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i = (x as usize).min(n - 1);
+            index[axis] = [i, (i + 1).min(n - 1)];
+            weight[axis] = x - i as f64;
+        }
+
+        std::array::from_fn(|corner| {
+            let [dx, dy, dz] = [corner & 1, (corner >> 1) & 1, corner >> 2];
+            let w = [dx, dy, dz]
+                .iter()
+                .zip(weight)
+                .map(|(&d, w)| if d == 1 { w } else { 1.0 - w })
+                .product::<f64>();
+            let (x, y, z) = (index[0][dx], index[1][dy], index[2][dz]);
+            ((z * ny + y) * nx + x, w)
+        })
+    }
+}
+
+/// Reads a grid from a `.npy` file, or a raw file of `resolution` voxels.
+/// Returns the resolution, the number of channels and the values.
+fn read_voxels(
+    path: &Path,
+    resolution: Option<[usize; 3]>,
+) -> Result<([usize; 3], usize, Vec<f64>)> {
+    let bytes =
+        fs::read(path).with_context(|| format!("could not read grid '{}'", path.display()))?;
+
+    let (resolution, channels, values) = if bytes.starts_with(b"\x93NUMPY") {
+        read_npy(&bytes).with_context(|| format!("invalid NumPy file '{}'", path.display()))?
+    } else {
+        let Some(resolution) = resolution else {
+            bail!(
+                "raw grid '{}' needs a resolution, only .npy files carry their own",
+                path.display()
+            );
+        };
+        let voxels = resolution.iter().product::<usize>();
+        let channels = [1, 3]
+            .into_iter()
+            .find(|channels| bytes.len() == 4 * voxels * channels);
+        let Some(channels) = channels.filter(|_| voxels > 0) else {
+            bail!(
+                "raw grid '{}' has {} bytes, which is not {} voxels of 1 or 3 floats",
+                path.display(),
+                bytes.len(),
+                voxels
+            );
+        };
+        let values = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+            .collect();
+        (resolution, channels, values)
+    };
+
+    if resolution.contains(&0) {
+        bail!("grid '{}' is empty", path.display());
+    }
+    Ok((resolution, channels, values))
+}
+
+/// Parses a NumPy array of shape (nz, ny, nx) or (nz, ny, nx, channels)
+fn read_npy(bytes: &[u8]) -> Result<([usize; 3], usize, Vec<f64>)> {
+    let (header_len, header_start) = match bytes.get(6) {
+        Some(1) if bytes.len() >= 10 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        Some(2 | 3) if bytes.len() >= 12 => (
+            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+            12,
+        ),
+        _ => bail!("unsupported version"),
+    };
+    let Some(header) = bytes.get(header_start..header_start + header_len) else {
+        bail!("header is cut short");
+    };
+    let header = String::from_utf8_lossy(header);
+    let data = &bytes[header_start + header_len..];
+
+    let field = |key: &str| {
+        let start = header
+            .find(&format!("'{key}':"))
+            .with_context(|| format!("header has no '{key}'"))?;
+        Ok::<_, anyhow::Error>(header[start + key.len() + 3..].trim_start().to_string())
+    };
+
+    if field("fortran_order")?.starts_with("True") {
+        bail!("Fortran-ordered arrays are not supported");
+    }
+
+    let shape = field("shape")?;
+    let shape: Vec<usize> = shape
+        .trim_start_matches('(')
+        .split(')')
+        .next()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().context("shape is not a list of sizes"))
+        .collect::<Result<_>>()?;
+    let (resolution, channels) = match shape[..] {
+        [nz, ny, nx] => ([nx, ny, nz], 1),
+        [nz, ny, nx, channels @ (1 | 3)] => ([nx, ny, nz], channels),
+        _ => bail!("shape {:?} is not (z, y, x) with 1 or 3 channels", shape),
+    };
+
+    let descr = field("descr")?;
+    let descr = descr
+        .trim_start_matches('\'')
+        .split('\'')
+        .next()
+        .unwrap_or("");
+    let values: Vec<f64> = match descr {
+        "<f4" => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+            .collect(),
+        "<f8" => data
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect(),
+        "|u1" | "<u1" => data.iter().map(|&b| b as f64 / 255.0).collect(),
+        _ => bail!("element type '{}' is not f4, f8 or u1", descr),
+    };
+
+    let expected = resolution.iter().product::<usize>() * channels;
+    if values.len() < expected {
+        bail!(
+            "holds {} values, its shape needs {}",
+            values.len(),
+            expected
+        );
+    }
+    Ok((resolution, channels, values[..expected].to_vec()))
+}
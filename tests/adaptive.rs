@@ -0,0 +1,74 @@
+use raytracer::camera::Camera;
+use raytracer::color::Color;
+use raytracer::hittable::quad::Quad;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::{Hittable, HittableList};
+use raytracer::light::LightList;
+use raytracer::material::dielectric::DiffuseLight;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+/// A diffuse sphere lit from above, filling the middle of a black image
+fn render(adaptive_threshold: f64) -> Vec<u32> {
+    let light: Arc<dyn Hittable> = Arc::new(Quad::new(
+        Point3::new(-0.5, 3.0, -0.5),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+        Arc::new(DiffuseLight::from_color(Color::new(10.0, 10.0, 10.0))),
+    ));
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7))),
+    )));
+    world.add(Arc::clone(&light));
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    let mut camera = Camera::new();
+    camera.image_width = 16;
+    camera.samples_per_pixel = 256;
+    camera.min_samples_per_pixel = 8;
+    camera.adaptive_threshold = adaptive_threshold;
+    camera.max_depth = 3;
+    camera.vfov = 60.0;
+    camera.lookfrom = Point3::new(0.0, 0.0, 4.0);
+    camera.lookat = Point3::new(0.0, 0.0, 0.0);
+    camera.background = Color::new(0.0, 0.0, 0.0);
+
+    let (_, sample_counts) = camera
+        .render_with_sample_counts(world, lights, LightList::new())
+        .unwrap();
+    sample_counts
+}
+
+#[test]
+fn adaptive_sampling_spends_samples_where_the_noise_is() {
+    let counts = render(0.02);
+
+    // The corners only see the black background, which has no variance
+    for corner in [0, 15, 240, 255] {
+        assert_eq!(counts[corner], 8);
+    }
+    assert!(counts.iter().all(|&count| (8..=256).contains(&count)));
+    assert!(counts.contains(&256));
+}
+
+#[test]
+fn without_a_threshold_every_pixel_takes_every_sample() {
+    assert!(render(0.0).iter().all(|&count| count == 256));
+}
+
+#[test]
+fn minimum_samples_never_exceed_the_maximum() {
+    let mut camera = Camera::new();
+    camera.initialize();
+    assert!(camera.min_samples() <= camera.samples_per_pixel);
+
+    camera.samples_per_pixel = 4;
+    camera.min_samples_per_pixel = 16;
+    camera.initialize();
+    assert_eq!(camera.min_samples(), 4);
+}
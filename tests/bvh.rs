@@ -0,0 +1,71 @@
+mod common;
+
+use common::{closest_t, grey, random_point};
+use raytracer::hittable::HittableList;
+use raytracer::hittable::bvh::BVH;
+use raytracer::hittable::bvh_node::BVHNode;
+use raytracer::hittable::quad::Quad;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::triangle::Triangle;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+fn random_world(rng: &mut Rng) -> HittableList {
+    let mat = grey();
+    let mut world = HittableList::new();
+
+    for _ in 0..300 {
+        let a = random_point(rng, 10.0);
+        world.add(Arc::new(Triangle::new(
+            a,
+            a + random_point(rng, 1.0),
+            a + random_point(rng, 1.0),
+            mat.clone(),
+        )));
+    }
+    for _ in 0..50 {
+        world.add(Arc::new(Sphere::new(
+            random_point(rng, 10.0),
+            rng.random_range(0.1, 1.0),
+            mat.clone(),
+        )));
+    }
+    // Axis-aligned quads have flat bounding boxes
+    world.add(Arc::new(Quad::new(
+        Point3::new(-10.0, -10.0, 0.0),
+        Vector3::new(5.0, 0.0, 0.0),
+        Vector3::new(0.0, 5.0, 0.0),
+        mat,
+    )));
+
+    world
+}
+
+#[test]
+fn sah_bvh_finds_the_same_hits_as_a_linear_scan() {
+    let mut rng = Rng::new(7);
+    let world = random_world(&mut rng);
+    let bvh = BVH::new(&world);
+    let bvh_node = BVHNode::new(&world);
+
+    assert!(bvh.node_count() > 1);
+
+    for _ in 0..5000 {
+        let ray = Ray::new(random_point(&mut rng, 15.0), random_point(&mut rng, 1.0));
+        let expected = closest_t(&world, &ray);
+
+        assert_eq!(closest_t(&bvh, &ray), expected);
+        assert_eq!(closest_t(&bvh_node, &ray), expected);
+    }
+}
+
+#[test]
+fn empty_bvh_never_hits() {
+    let bvh = BVH::new(&HittableList::new());
+    let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+
+    assert_eq!(bvh.node_count(), 0);
+    assert!(closest_t(&bvh, &ray).is_none());
+}
@@ -0,0 +1,61 @@
+//! Fixtures shared by the integration tests. Each test crate uses only some
+//! of them.
+#![allow(dead_code)]
+
+use raytracer::color::Color;
+use raytracer::hittable::{HitRecord, Hittable};
+use raytracer::interval::Interval;
+use raytracer::material::Material;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::vector::Point3;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub fn grey() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+}
+
+/// The nearest hit of `ray` on `object`
+pub fn closest(object: &dyn Hittable, ray: &Ray) -> Option<HitRecord> {
+    let mut rec = HitRecord::new();
+    let mut t = Interval::new(0.001, f64::INFINITY);
+    object
+        .hit(ray, &mut t, &mut rec, &mut Rng::new(0))
+        .then_some(rec)
+}
+
+/// Ray parameter of the nearest hit of `ray` on `object`
+pub fn closest_t(object: &dyn Hittable, ray: &Ray) -> Option<f64> {
+    closest(object, ray).map(|rec| rec.t)
+}
+
+/// Point in the cube reaching `extent` from the origin along every axis
+pub fn random_point(rng: &mut Rng, extent: f64) -> Point3 {
+    Point3::new(
+        rng.random_range(-extent, extent),
+        rng.random_range(-extent, extent),
+        rng.random_range(-extent, extent),
+    )
+}
+
+/// Path in the system's temporary directory, unique to this test process
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("raytracer-{}-{}", std::process::id(), name))
+}
+
+/// Creates the directory `temp_path(name)`
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = temp_path(name);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes `bytes` to the file `temp_path(name)`
+pub fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = temp_path(name);
+    fs::write(&path, bytes).unwrap();
+    path
+}
@@ -0,0 +1,200 @@
+use raytracer::camera::Camera;
+use raytracer::color::Color;
+use raytracer::distribution::Distribution1D;
+use raytracer::environment::Environment;
+use raytracer::environment::map::EnvironmentMap;
+use raytracer::environment::sky::Sky;
+use raytracer::hittable::HittableList;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::light::LightList;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// A dim map with one small, very bright patch, like a sun in the sky
+fn sunny_map() -> EnvironmentMap {
+    let (width, height) = (32, 16);
+    let mut pixels = vec![Color::new(0.2, 0.3, 0.5); width * height];
+    pixels[5 * width + 20] = Color::new(500.0, 480.0, 450.0);
+    EnvironmentMap::new(width, height, pixels)
+}
+
+/// Midpoint rule over the sphere of directions
+fn integrate(f: impl Fn(&Vector3) -> f64) -> f64 {
+    let (steps_theta, steps_phi) = (400, 800);
+    let mut sum = 0.0;
+    for i in 0..steps_theta {
+        let theta = PI * (i as f64 + 0.5) / steps_theta as f64;
+        for j in 0..steps_phi {
+            let phi = 2.0 * PI * (j as f64 + 0.5) / steps_phi as f64;
+            let direction = Vector3::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            );
+            sum += f(&direction) * theta.sin();
+        }
+    }
+    sum * (PI / steps_theta as f64) * (2.0 * PI / steps_phi as f64)
+}
+
+#[test]
+fn distribution_follows_its_steps() {
+    let distribution = Distribution1D::new(vec![1.0, 3.0, 0.0, 4.0]);
+    assert_eq!(distribution.integral(), 2.0);
+
+    let n = 8000;
+    let mut counts = [0; 4];
+    for i in 0..n {
+        let (x, pdf, index) = distribution.sample((i as f64 + 0.5) / n as f64);
+        assert_eq!((x * 4.0) as usize, index);
+        assert_eq!(pdf, distribution.pdf(x));
+        counts[index] += 1;
+    }
+    assert_eq!(counts, [1000, 3000, 0, 4000]);
+}
+
+#[test]
+fn map_samples_match_its_density() {
+    let map = sunny_map();
+    let integral = integrate(|direction| map.pdf(direction));
+    assert!((integral - 1.0).abs() < 0.01, "{}", integral);
+
+    // Estimating the total radiance by sampling the map agrees with
+    // integrating it, and most samples head for the sun
+    let expected = integrate(|direction| map.radiance(direction).y());
+    let mut rng = Rng::new(21);
+    let n = 20000;
+    let (mut estimate, mut towards_sun) = (0.0, 0);
+    for _ in 0..n {
+        let direction = map.sample(&mut rng);
+        assert!((direction.length() - 1.0).abs() < 1e-9);
+        let radiance = map.radiance(&direction).y();
+        estimate += radiance / map.pdf(&direction) / n as f64;
+        if radiance > 100.0 {
+            towards_sun += 1;
+        }
+    }
+    assert!(
+        (estimate - expected).abs() < 0.01 * expected,
+        "{} != {}",
+        estimate,
+        expected
+    );
+    assert!(towards_sun > n / 2, "{}", towards_sun);
+}
+
+#[test]
+fn rotation_turns_the_map_about_y() {
+    let (width, height) = (4, 2);
+    let mut pixels = vec![Color::new(0.0, 0.0, 0.0); width * height];
+    // Right of the middle, which looks along +x, the image turns towards -z
+    pixels[width + width / 2] = Color::new(1.0, 1.0, 1.0);
+    let mut map = EnvironmentMap::new(width, height, pixels);
+
+    let below_x = Vector3::new(1.0, -0.1, -0.1);
+    let below_minus_z = Vector3::new(-0.1, -0.1, -1.0);
+    assert_eq!(map.radiance(&below_x).x(), 1.0);
+    assert_eq!(map.radiance(&below_minus_z).x(), 0.0);
+
+    map.rotation = 90.0;
+    map.intensity = 2.0;
+    assert_eq!(map.radiance(&below_x).x(), 0.0);
+    assert_eq!(map.radiance(&below_minus_z).x(), 2.0);
+}
+
+#[test]
+fn sampled_environment_passes_the_furnace_test() {
+    // Lit evenly from every side, a convex sphere reflects exactly its
+    // albedo, which only holds if light samples and BSDF samples of the
+    // environment are weighted against each other correctly
+    let (width, height) = (16, 8);
+    let map = EnvironmentMap::new(
+        width,
+        height,
+        vec![Color::new(1.0, 1.0, 1.0); width * height],
+    );
+
+    let mut camera = Camera::new();
+    camera.image_width = 8;
+    camera.samples_per_pixel = 64;
+    camera.vfov = 10.0;
+    camera.lookfrom = Point3::new(0.0, 0.0, 5.0);
+    camera.background = Color::new(0.0, 0.0, 0.0);
+    camera.environment = Some(Arc::new(map));
+
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    let framebuffer = camera
+        .render_to_framebuffer(world, HittableList::new(), LightList::new())
+        .unwrap();
+
+    let pixels = framebuffer.pixels();
+    let mean = pixels.iter().map(|pixel| pixel.y()).sum::<f64>() / pixels.len() as f64;
+    assert!((mean - 0.5).abs() < 0.02, "{}", mean);
+}
+
+#[test]
+fn sky_is_brightest_around_the_sun_and_black_below_the_horizon() {
+    let noon = Sky::new(60.0, 90.0, 3.0);
+    let sun = noon.sun_direction();
+    assert!((sun - Vector3::new(0.0, 0.866, 0.5)).length() < 1e-3);
+
+    let near_sun = noon.radiance(&(sun + Vector3::new(0.05, 0.0, 0.0)));
+    let away = noon.radiance(&Vector3::new(0.0, 0.5, -1.0));
+    assert!(near_sun.y() > 2.0 * away.y());
+    assert!(noon.radiance(&sun).y() > 1000.0 * near_sun.y());
+    assert_eq!(noon.radiance(&Vector3::new(0.0, -0.1, 1.0)).y(), 0.0);
+    // A clear sky is bluer overhead than along the horizon
+    let zenith = noon.radiance(&Vector3::new(0.0, 1.0, 0.0));
+    assert!(zenith.z() > zenith.x());
+
+    // Through more air the sun is dimmer and redder
+    let sunset = Sky::new(3.0, 90.0, 3.0);
+    let low_sun = sunset.radiance(&sunset.sun_direction());
+    let high_sun = noon.radiance(&sun);
+    assert!(low_sun.y() < high_sun.y());
+    assert!(low_sun.x() / low_sun.z() > 2.0 * high_sun.x() / high_sun.z());
+}
+
+#[test]
+fn sky_samples_match_its_radiance() {
+    // The irradiance on the ground estimated by sampling the sky agrees with
+    // integrating the sky and adding the sun's disk
+    let sky = Sky::new(40.0, 30.0, 4.0);
+    let sun = sky.sun_direction();
+    let sun_radius = 0.2665f64.to_radians();
+    let sun_disk = sky.radiance(&sun).y()
+        - sky
+            .radiance(&(sun + 2.0 * sun_radius * Vector3::new(0.0, 1.0, 0.0)))
+            .y();
+    let expected = integrate(|direction| {
+        if Vector3::dot(direction, &sun) > (2.0 * sun_radius).cos() {
+            0.0
+        } else {
+            sky.radiance(direction).y() * direction.y().max(0.0)
+        }
+    }) + sun_disk * 2.0 * PI * (1.0 - sun_radius.cos()) * sun.y();
+
+    let mut rng = Rng::new(23);
+    let n = 20000;
+    let mut estimate = 0.0;
+    for _ in 0..n {
+        let direction = sky.sample(&mut rng);
+        assert!(direction.y() > -1e-9);
+        estimate +=
+            sky.radiance(&direction).y() * direction.y().max(0.0) / sky.pdf(&direction) / n as f64;
+    }
+    assert!(
+        (estimate - expected).abs() < 0.01 * expected,
+        "{} != {}",
+        estimate,
+        expected
+    );
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::{closest, closest_t, grey};
+use raytracer::color::Color;
+use raytracer::hittable::bvh::BVH;
+use raytracer::hittable::instance::Instance;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::{Hittable, HittableList};
+use raytracer::material::Material;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::matrix::Matrix4;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+/// A small cluster of spheres around the origin
+fn cluster() -> [(Point3, f64); 3] {
+    [
+        (Point3::new(0.0, 0.0, 0.0), 0.5),
+        (Point3::new(0.8, 0.2, 0.0), 0.3),
+        (Point3::new(-0.6, 0.4, 0.3), 0.25),
+    ]
+}
+
+#[test]
+fn instances_match_copied_geometry() {
+    let mat = grey();
+    let spheres = cluster();
+    let prototype = Arc::new(BVH::new_from_objects(
+        spheres
+            .iter()
+            .map(|&(center, radius)| {
+                Arc::new(Sphere::new(center, radius, Arc::clone(&mat))) as Arc<dyn Hittable>
+            })
+            .collect(),
+    ));
+
+    let mut rng = Rng::new(17);
+    let mut instances: Vec<Arc<dyn Hittable>> = Vec::new();
+    let mut copies = HittableList::new();
+    for _ in 0..200 {
+        let offset = Vector3::new(
+            rng.random_range(-20.0, 20.0),
+            rng.random_range(-20.0, 20.0),
+            rng.random_range(-20.0, 20.0),
+        );
+        instances.push(Arc::new(Instance::new(
+            Arc::clone(&prototype),
+            Matrix4::translate(offset),
+        )));
+        for &(center, radius) in &spheres {
+            copies.add(Arc::new(Sphere::new(
+                center + offset,
+                radius,
+                Arc::clone(&mat),
+            )));
+        }
+    }
+
+    // Every instance refers to the same bottom-level BVH
+    assert_eq!(Arc::strong_count(&prototype), 201);
+    let top_level = BVH::new_from_objects(instances);
+
+    for _ in 0..2000 {
+        let origin = Point3::new(
+            rng.random_range(-25.0, 25.0),
+            rng.random_range(-25.0, 25.0),
+            -30.0,
+        );
+        let target = Point3::new(
+            rng.random_range(-20.0, 20.0),
+            rng.random_range(-20.0, 20.0),
+            rng.random_range(-20.0, 20.0),
+        );
+        let ray = Ray::new(origin, target - origin);
+
+        let expected = closest_t(&copies, &ray);
+        let actual = closest_t(&top_level, &ray);
+        match (expected, actual) {
+            (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9, "{} != {}", a, b),
+            (None, None) => {}
+            _ => panic!("hit mismatch: {:?} != {:?}", expected, actual),
+        }
+    }
+}
+
+#[test]
+fn material_override_replaces_prototype_materials() {
+    let mat = grey();
+    let red: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.9, 0.1, 0.1)));
+    let prototype = Arc::new(BVH::new_from_objects(vec![Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::clone(&mat),
+    ))]));
+
+    let plain = Instance::new(Arc::clone(&prototype), Matrix4::IDENTITY);
+    let painted = Instance::with_material(prototype, Matrix4::IDENTITY, Arc::clone(&red));
+    let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+
+    assert!(Arc::ptr_eq(&closest(&plain, &ray).unwrap().mat, &mat));
+    assert!(Arc::ptr_eq(&closest(&painted, &ray).unwrap().mat, &red));
+}
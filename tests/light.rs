@@ -0,0 +1,129 @@
+use raytracer::camera::Camera;
+use raytracer::color::Color;
+use raytracer::hittable::HittableList;
+use raytracer::hittable::quad::Quad;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::light::directional::DirectionalLight;
+use raytracer::light::point::PointLight;
+use raytracer::light::spot::SpotLight;
+use raytracer::light::{Light, LightList};
+use raytracer::material::lambertian::Lambertian;
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Mean brightness of a grey wall in the z = 0 plane, seen head on from
+/// far away with nothing else lighting it
+fn lit_wall(albedo: f64, blocker: bool, light: Arc<dyn Light>) -> f64 {
+    let mut camera = Camera::new();
+    camera.image_width = 8;
+    camera.samples_per_pixel = 16;
+    camera.vfov = 2.0;
+    camera.lookfrom = Point3::new(0.0, 0.0, 5.0);
+    camera.lookat = Point3::new(0.0, 0.0, 0.0);
+    camera.background = Color::new(0.0, 0.0, 0.0);
+
+    let mut world = HittableList::new();
+    world.add(Arc::new(Quad::new(
+        Point3::new(-10.0, -10.0, 0.0),
+        Vector3::new(20.0, 0.0, 0.0),
+        Vector3::new(0.0, 20.0, 0.0),
+        Arc::new(Lambertian::new(Color::new(albedo, albedo, albedo))),
+    )));
+    if blocker {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(3.0, 0.0, 1.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
+        )));
+    }
+
+    let mut lights = LightList::new();
+    lights.add(light);
+    let framebuffer = camera
+        .render_to_framebuffer(world, HittableList::new(), lights)
+        .unwrap();
+
+    let pixels = framebuffer.pixels();
+    pixels.iter().map(|pixel| pixel.y()).sum::<f64>() / pixels.len() as f64
+}
+
+#[test]
+fn point_light_follows_the_inverse_square_law() {
+    // Irradiance I cos / d^2 on a Lambertian surface leaves as albedo / pi times it
+    let light = |distance: f64| {
+        Arc::new(PointLight::new(
+            Point3::new(0.0, 0.0, distance),
+            Color::new(4.0 * PI, 4.0 * PI, 4.0 * PI),
+        ))
+    };
+    let near = lit_wall(0.5, false, light(2.0));
+    assert!((near - 0.5).abs() < 0.005, "{}", near);
+    let far = lit_wall(0.5, false, light(4.0));
+    assert!((far - 0.125).abs() < 0.002, "{}", far);
+}
+
+#[test]
+fn occluded_lights_cast_hard_shadows() {
+    // Light from (6, 0, 2) to the middle of the wall passes (3, 0, 1)
+    let light = || {
+        Arc::new(PointLight::new(
+            Point3::new(6.0, 0.0, 2.0),
+            Color::new(10.0, 10.0, 10.0),
+        ))
+    };
+    assert!(lit_wall(0.5, false, light()) > 0.01);
+    assert_eq!(lit_wall(0.5, true, light()), 0.0);
+}
+
+#[test]
+fn spot_light_fades_between_its_cones() {
+    let spot = SpotLight::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, -2.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+        10.0,
+        30.0,
+    );
+    let mut rng = Rng::new(22);
+    let mut received = |degrees: f64| {
+        let (sin, cos) = f64::to_radians(degrees).sin_cos();
+        spot.sample(&Point3::new(sin, -cos, 0.0), &mut rng)
+            .map_or(0.0, |sample| sample.radiance.x())
+    };
+
+    assert_eq!(received(0.0), 1.0);
+    assert_eq!(received(9.0), 1.0);
+    let half_way = received(20.0);
+    assert!(half_way > 0.3 && half_way < 0.7, "{}", half_way);
+    assert!(received(25.0) < half_way);
+    assert_eq!(received(31.0), 0.0);
+    assert_eq!(received(180.0), 0.0);
+}
+
+#[test]
+fn directional_light_comes_from_a_disk_in_the_sky() {
+    let direction = Vector3::new(1.0, -1.0, 0.0);
+    let towards_sun = -direction.unit_vector();
+    let irradiance = Color::new(3.0, 2.0, 1.0);
+    let mut rng = Rng::new(22);
+
+    let point = DirectionalLight::new(direction, irradiance, 0.0);
+    let sample = point.sample(&Point3::new(5.0, 0.0, 5.0), &mut rng).unwrap();
+    assert!((sample.direction - towards_sun).length() < 1e-12);
+    assert_eq!(sample.distance, f64::INFINITY);
+
+    let disk = DirectionalLight::new(direction, irradiance, 5.0);
+    let cos_radius = 5f64.to_radians().cos();
+    let mut spread = 0.0f64;
+    for _ in 0..1000 {
+        let sample = disk.sample(&Point3::new(0.0, 0.0, 0.0), &mut rng).unwrap();
+        let cos_theta = Vector3::dot(&sample.direction, &towards_sun);
+        assert!(cos_theta >= cos_radius - 1e-12, "{}", cos_theta);
+        assert_eq!(sample.radiance.x(), irradiance.x());
+        spread = spread.max(1.0 - cos_theta);
+    }
+    // The samples reach out to the edge of the disk
+    assert!(spread > 0.9 * (1.0 - cos_radius), "{}", spread);
+}
@@ -0,0 +1,404 @@
+mod common;
+
+use common::temp_file;
+use raytracer::camera::Camera;
+use raytracer::color::Color;
+use raytracer::hittable::bvh::BVH;
+use raytracer::hittable::constant_medium::ConstantMedium;
+use raytracer::hittable::grid_medium::GridMedium;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::{HitRecord, Hittable, HittableList};
+use raytracer::interval::Interval;
+use raytracer::light::LightList;
+use raytracer::light::point::PointLight;
+use raytracer::material::Material;
+use raytracer::material::isotropic::Isotropic;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::material::phase::PhaseFunction;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::texture::solid::SolidTexture;
+use raytracer::vector::{Point3, Vector3};
+use raytracer::voxel::VoxelGrid;
+use std::f64::consts::PI;
+use std::fs;
+use std::sync::Arc;
+
+/// A 2 unit cube whose density climbs from 0 to `scale` along x, so that a
+/// ray straight across it meets an optical depth of `scale`
+fn ramp(scale: f64) -> GridMedium {
+    GridMedium::new(
+        VoxelGrid::new([2, 1, 1], vec![0.0, 1.0]),
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(2.0, 2.0, 2.0),
+        scale,
+        Color::new(1.0, 1.0, 1.0),
+    )
+}
+
+#[test]
+fn delta_and_ratio_tracking_follow_beer_lambert() {
+    let medium = ramp(1.5);
+    let expected = (-1.5f64).exp();
+    let ray = Ray::new(Point3::new(-1.0, 1.0, 1.0), Vector3::new(2.0, 0.0, 0.0));
+    let t = Interval::new(0.001, f64::INFINITY);
+
+    let mut rng = Rng::new(24);
+    let n = 40000;
+    let (mut escaped, mut ratio) = (0, 0.0);
+    for _ in 0..n {
+        let mut rec = HitRecord::new();
+        if !medium.hit(&ray, &mut t.clone(), &mut rec, &mut rng) {
+            escaped += 1;
+        } else {
+            assert!(rec.p.x() > 0.5 && rec.p.x() < 2.0, "{}", rec.p);
+        }
+        ratio += medium.transmittance(&ray, &t, &mut rng) / n as f64;
+    }
+
+    let delta = escaped as f64 / n as f64;
+    assert!((delta - expected).abs() < 0.01, "{} != {}", delta, expected);
+    assert!((ratio - expected).abs() < 0.01, "{} != {}", ratio, expected);
+
+    // Stopping the ray halfway leaves only the thinner half to cross
+    let half = Interval::new(0.001, 1.0);
+    assert_eq!(
+        medium.transmittance(&ray, &Interval::new(0.001, 0.5), &mut rng),
+        1.0
+    );
+    let thin = (0..1000)
+        .map(|_| medium.transmittance(&ray, &half, &mut rng))
+        .sum::<f64>()
+        / 1000.0;
+    let expected = (-0.1875f64).exp();
+    assert!((thin - expected).abs() < 0.03, "{} != {}", thin, expected);
+}
+
+#[test]
+fn shadow_rays_multiply_what_each_object_lets_through() {
+    let fog = |center: Point3| -> Arc<dyn Hittable> {
+        let boundary = Arc::new(Sphere::new(
+            center,
+            1.0,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ));
+        Arc::new(ConstantMedium::from_color(
+            boundary,
+            0.5,
+            Color::new(1.0, 1.0, 1.0),
+        ))
+    };
+    let mut world = HittableList::new();
+    world.add(fog(Point3::new(0.0, -3.0, 0.0)));
+    world.add(fog(Point3::new(4.0, -3.0, 0.0)));
+    world.add(Arc::new(ramp(1.5)) as Arc<dyn Hittable>);
+    let bvh = BVH::new(&world);
+
+    // Through both fog balls, each 2 units thick, but not the ramp
+    let ray = Ray::new(Point3::new(-2.0, -3.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    let t = Interval::new(0.001, 8.0);
+    let mut rng = Rng::new(24);
+    let expected = (-2.0f64).exp();
+    for object in [&world as &dyn Hittable, &bvh] {
+        let transmittance = object.transmittance(&ray, &t, &mut rng);
+        assert!((transmittance - expected).abs() < 1e-9, "{}", transmittance);
+    }
+
+    // A surface in the way blocks everything
+    world.add(Arc::new(Sphere::new(
+        Point3::new(2.0, -3.0, 0.0),
+        0.5,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    let bvh = BVH::new(&world);
+    for object in [&world as &dyn Hittable, &bvh] {
+        assert_eq!(object.transmittance(&ray, &t, &mut rng), 0.0);
+    }
+}
+
+#[test]
+fn grids_load_from_numpy_and_raw_files() {
+    let values = [0.0f32, 1.0, 2.0, 3.0, 4.0, 5.0];
+    let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+    // Shape (z, y, x) = (2, 1, 3)
+    let mut header = b"{'descr': '<f4', 'fortran_order': False, 'shape': (2, 1, 3), }".to_vec();
+    header.resize(118, b' ');
+    header.push(b'\n');
+    let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+    npy.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    npy.extend_from_slice(&header);
+    npy.extend_from_slice(&data);
+
+    let npy_path = temp_file("grid.npy", &npy);
+    let from_npy = VoxelGrid::from_file(&npy_path, None).unwrap();
+    let raw_path = temp_file("grid.raw", &data);
+    let from_raw = VoxelGrid::from_file(&raw_path, Some([3, 1, 2])).unwrap();
+
+    for grid in [&from_npy, &from_raw] {
+        assert_eq!(grid.resolution(), [3, 1, 2]);
+        for (i, &value) in values.iter().enumerate() {
+            let center = Point3::new(
+                ((i % 3) as f64 + 0.5) / 3.0,
+                0.5,
+                ((i / 3) as f64 + 0.5) / 2.0,
+            );
+            assert!((grid.density(&center) - value as f64).abs() < 1e-9);
+        }
+    }
+    // Halfway between two voxel centres
+    assert!((from_raw.density(&Point3::new(1.0 / 3.0, 0.5, 0.25)) - 0.5).abs() < 1e-9);
+
+    // Colours need three values per voxel, or one for grey
+    let grey = from_raw.read_colors(&raw_path).unwrap();
+    assert_eq!(grey[4].x(), 4.0);
+    let colors: Vec<u8> = (0..18).flat_map(|v| (v as f32).to_le_bytes()).collect();
+    let colors_path = temp_file("colors.raw", &colors);
+    let colors = from_raw.read_colors(&colors_path).unwrap();
+    assert_eq!(
+        (colors[1].x(), colors[1].y(), colors[1].z()),
+        (3.0, 4.0, 5.0)
+    );
+
+    let message = format!("{:#}", VoxelGrid::from_file(&raw_path, None).err().unwrap());
+    assert!(message.contains("needs a resolution"), "{}", message);
+    let message = format!(
+        "{:#}",
+        VoxelGrid::from_file(&raw_path, Some([4, 1, 2]))
+            .err()
+            .unwrap()
+    );
+    assert!(message.contains("is not 8 voxels"), "{}", message);
+
+    for path in [npy_path, raw_path, colors_path] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn emissive_medium_glows_with_its_emission() {
+    // A thick, purely absorbing medium shows its emission where it is seen
+    let mut grid = VoxelGrid::new([1, 1, 1], vec![1.0]);
+    grid.set_albedo(vec![Color::new(0.0, 0.0, 0.0)]);
+    grid.set_emission(vec![Color::new(2.0, 1.0, 0.5)]);
+    let mut world = HittableList::new();
+    world.add(Arc::new(GridMedium::new(
+        grid,
+        Point3::new(-1.0, -1.0, -1.0),
+        Point3::new(1.0, 1.0, 1.0),
+        20.0,
+        Color::new(1.0, 1.0, 1.0),
+    )));
+
+    let mut camera = Camera::new();
+    camera.image_width = 4;
+    camera.samples_per_pixel = 16;
+    camera.vfov = 5.0;
+    camera.lookfrom = Point3::new(0.0, 0.0, 5.0);
+    camera.background = Color::new(0.0, 0.0, 0.0);
+    let framebuffer = camera
+        .render_to_framebuffer(world, HittableList::new(), LightList::new())
+        .unwrap();
+
+    for pixel in framebuffer.pixels() {
+        assert!((pixel.x() - 2.0).abs() < 1e-6, "{}", pixel);
+        assert!((pixel.z() - 0.5).abs() < 1e-6, "{}", pixel);
+    }
+}
+
+#[test]
+fn per_voxel_colours_are_read_at_each_collision() {
+    // Red on the left half, blue on the right, seen by one shared material
+    let mut grid = VoxelGrid::new([2, 1, 1], vec![1.0, 1.0]);
+    grid.set_albedo(vec![Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0)]);
+    let medium = GridMedium::new(
+        grid,
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(2.0, 2.0, 2.0),
+        50.0,
+        Color::new(1.0, 1.0, 1.0),
+    );
+
+    let mut rng = Rng::new(8);
+    let mut hit = |x: f64| {
+        let ray = Ray::new(Point3::new(x, 1.0, -1.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut rec = HitRecord::new();
+        assert!(medium.hit(
+            &ray,
+            &mut Interval::new(0.001, f64::INFINITY),
+            &mut rec,
+            &mut rng
+        ));
+        let mut attenuation = Color::new(0.0, 0.0, 0.0);
+        let mut scattered = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(
+            rec.mat
+                .scatter(&ray, &rec, &mut attenuation, &mut scattered, &mut rng)
+        );
+        (rec.mat, attenuation)
+    };
+    let (left, red) = hit(0.25);
+    let (right, blue) = hit(1.75);
+    assert!(Arc::ptr_eq(&left, &right));
+    assert!(red.x() > 0.7 && red.z() < 0.3, "{}", red);
+    assert!(blue.z() > 0.7 && blue.x() < 0.3, "{}", blue);
+}
+
+#[test]
+fn noise_grids_are_repeatable_and_fade_at_the_edges() {
+    let a = VoxelGrid::from_noise([16, 16, 16], 3.0, 5);
+    let b = VoxelGrid::from_noise([16, 16, 16], 3.0, 5);
+    let center = Point3::new(0.5, 0.5, 0.5);
+    assert_eq!(a.density(&center), b.density(&center));
+    assert!(a.density(&center) > 0.0);
+    assert_eq!(a.density(&Point3::new(0.0, 0.0, 0.0)), 0.0);
+    assert!(a.density(&Point3::new(1.0, 0.5, 0.5)) < 0.05);
+}
+
+#[test]
+fn phase_functions_are_normalised_and_sampled_exactly() {
+    let direction = Vector3::new(1.0, 2.0, -0.5);
+    let mut rng = Rng::new(25);
+    for phase in [
+        PhaseFunction::Isotropic,
+        PhaseFunction::HenyeyGreenstein { g: 0.7 },
+        PhaseFunction::HenyeyGreenstein { g: -0.4 },
+        PhaseFunction::DoubleHenyeyGreenstein {
+            g1: 0.8,
+            g2: -0.3,
+            weight: 0.6,
+        },
+        PhaseFunction::Rayleigh,
+    ] {
+        // Integrated over the sphere, in slices of equal cosine
+        let n = 20000;
+        let integral = (0..n)
+            .map(|i| phase.value(-1.0 + 2.0 * (i as f64 + 0.5) / n as f64))
+            .sum::<f64>()
+            * 4.0
+            * PI
+            / n as f64;
+        assert!((integral - 1.0).abs() < 1e-3, "{:?}: {}", phase, integral);
+
+        // The sampled cosines fall into bins as the density says
+        let bins = 8;
+        let samples = 40000;
+        let mut counts = vec![0; bins];
+        for _ in 0..samples {
+            let scattered = phase.sample(&direction, &mut rng);
+            assert!((scattered.length() - 1.0).abs() < 1e-9);
+            let cos_theta = Vector3::dot(&direction.unit_vector(), &scattered);
+            let bin = ((cos_theta + 1.0) / 2.0 * bins as f64) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        for (bin, &count) in counts.iter().enumerate() {
+            let steps = 1000;
+            let expected = (0..steps)
+                .map(|i| {
+                    let cos_theta =
+                        -1.0 + 2.0 * (bin as f64 + (i as f64 + 0.5) / steps as f64) / bins as f64;
+                    phase.value(cos_theta)
+                })
+                .sum::<f64>()
+                * 4.0
+                * PI
+                / (bins * steps) as f64;
+            let found = count as f64 / samples as f64;
+            assert!(
+                (found - expected).abs() < 0.01,
+                "{:?} bin {}: {} != {}",
+                phase,
+                bin,
+                found,
+                expected
+            );
+        }
+    }
+
+    // The mean cosine of a Henyey-Greenstein lobe is its g
+    let phase = PhaseFunction::HenyeyGreenstein { g: 0.6 };
+    let mean = (0..40000)
+        .map(|_| {
+            Vector3::dot(
+                &direction.unit_vector(),
+                &phase.sample(&direction, &mut rng),
+            )
+        })
+        .sum::<f64>()
+        / 40000.0;
+    assert!((mean - 0.6).abs() < 0.01, "{}", mean);
+}
+
+#[test]
+fn isotropic_material_scatters_its_albedo_evenly() {
+    let albedo = Color::new(0.2, 0.4, 0.6);
+    let material = Isotropic::from_color(albedo);
+    let rec = HitRecord::new();
+    let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+
+    let mut attenuation = Color::new(0.0, 0.0, 0.0);
+    let mut scattered = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert!(material.scatter(
+        &ray,
+        &rec,
+        &mut attenuation,
+        &mut scattered,
+        &mut Rng::new(5)
+    ));
+    assert!((attenuation - albedo).length() < 1e-12, "{}", attenuation);
+
+    let eval = material.eval(&ray, &rec, &scattered);
+    assert!((eval.y() - 0.4 / (4.0 * PI)).abs() < 1e-12, "{}", eval);
+}
+
+#[test]
+fn fog_lit_by_a_light_glows_by_its_phase_function() {
+    // A fog ball seen from +z, with a point light on the axis at `light_z`
+    let glow = |phase: PhaseFunction, light_z: f64| {
+        let boundary = Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ));
+        let mut world = HittableList::new();
+        world.add(Arc::new(ConstantMedium::with_phase(
+            boundary,
+            0.5,
+            Arc::new(SolidTexture::new(Color::new(1.0, 1.0, 1.0))),
+            phase,
+        )));
+        let mut lights = LightList::new();
+        lights.add(Arc::new(PointLight::new(
+            Point3::new(0.0, 0.0, light_z),
+            Color::new(10.0, 10.0, 10.0),
+        )));
+
+        let mut camera = Camera::new();
+        camera.image_width = 8;
+        camera.samples_per_pixel = 64;
+        camera.vfov = 20.0;
+        camera.lookfrom = Point3::new(0.0, 0.0, 5.0);
+        camera.lookat = Point3::new(0.0, 0.0, 0.0);
+        camera.background = Color::new(0.0, 0.0, 0.0);
+        let framebuffer = camera
+            .render_to_framebuffer(world, HittableList::new(), lights)
+            .unwrap();
+        let pixels = framebuffer.pixels();
+        pixels.iter().map(|pixel| pixel.y()).sum::<f64>() / pixels.len() as f64
+    };
+
+    let forward = PhaseFunction::HenyeyGreenstein { g: 0.8 };
+    let backward = PhaseFunction::HenyeyGreenstein { g: -0.8 };
+
+    // Behind the fog, forward scattering makes a halo around the light
+    let halo = glow(forward, -3.0);
+    let isotropic = glow(PhaseFunction::Isotropic, -3.0);
+    assert!(halo > 2.0 * isotropic, "{} {}", halo, isotropic);
+
+    // In front of it, light mostly comes back from backward scattering
+    let isotropic = glow(PhaseFunction::Isotropic, 3.0);
+    let back = glow(backward, 3.0);
+    let through = glow(forward, 3.0);
+    assert!(back > 2.0 * isotropic, "{} {}", back, isotropic);
+    assert!(isotropic > 2.0 * through, "{} {}", isotropic, through);
+}
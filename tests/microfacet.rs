@@ -0,0 +1,228 @@
+use raytracer::color::Color;
+use raytracer::hittable::HitRecord;
+use raytracer::material::Material;
+use raytracer::material::conductor::{Conductor, ConductorPreset};
+use raytracer::material::dielectric::RoughDielectric;
+use raytracer::material::microfacet::{fresnel_conductor, fresnel_dielectric};
+use raytracer::material::principled::Principled;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::texture::checker::CheckerTexture;
+use raytracer::vector::{Point3, Vector3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// A hit on the xy plane seen from above, `front_face` picking which side
+fn hit(front_face: bool) -> HitRecord {
+    let mut rec = HitRecord::new();
+    rec.p = Point3::new(0.0, 0.0, 0.0);
+    rec.normal = Vector3::new(0.0, 0.0, 1.0);
+    rec.front_face = front_face;
+    rec
+}
+
+/// Ray arriving at the origin from `theta` degrees off the normal
+fn incoming(theta: f64) -> Ray {
+    let (sin, cos) = theta.to_radians().sin_cos();
+    Ray::new(Point3::new(sin, 0.0, cos), Vector3::new(-sin, 0.0, -cos))
+}
+
+/// A principled material exercising every lobe at once
+fn principled(metallic: f64, roughness: f64, transmission: f64) -> Principled {
+    let mut material = Principled::new(Color::new(0.8, 0.6, 0.4));
+    material.metallic = Principled::scalar(metallic);
+    material.roughness = Principled::scalar(roughness);
+    material.transmission = Principled::scalar(transmission);
+    material.clearcoat = Principled::scalar(0.5);
+    material.sheen = Principled::scalar(0.5);
+    material
+}
+
+fn sample(
+    material: &dyn Material,
+    ray: &Ray,
+    rec: &HitRecord,
+    rng: &mut Rng,
+) -> Option<(Color, Ray)> {
+    let mut attenuation = Color::new(0.0, 0.0, 0.0);
+    let mut scattered = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
+    material
+        .scatter(ray, rec, &mut attenuation, &mut scattered, rng)
+        .then_some((attenuation, scattered))
+}
+
+/// Sampled weights must equal the evaluated BSDF over its density, or light
+/// sampling and BSDF sampling would disagree
+fn assert_consistent(material: &dyn Material, rec: &HitRecord) {
+    let mut rng = Rng::new(19);
+    for theta in [0.0, 30.0, 60.0, 80.0] {
+        let ray = incoming(theta);
+        for _ in 0..200 {
+            let Some((attenuation, scattered)) = sample(material, &ray, rec, &mut rng) else {
+                continue;
+            };
+            let pdf = material.scattering_pdf(&ray, rec, &scattered);
+            assert!(pdf > 0.0);
+            let expected = material.eval(&ray, rec, &scattered) / pdf;
+            assert!(
+                (attenuation - expected).length() < 1e-6 * (1.0 + expected.length()),
+                "{} != {} at {} degrees",
+                attenuation,
+                expected,
+                theta
+            );
+        }
+    }
+}
+
+/// Mean weight of a ray leaving the surface, the fraction of energy kept
+fn albedo(material: &dyn Material, rec: &HitRecord, theta: f64) -> Color {
+    let mut rng = Rng::new(7);
+    let ray = incoming(theta);
+    let n = 20000;
+    let mut sum = Color::new(0.0, 0.0, 0.0);
+    for _ in 0..n {
+        if let Some((attenuation, _)) = sample(material, &ray, rec, &mut rng) {
+            sum += attenuation;
+        }
+    }
+    sum / n as f64
+}
+
+/// Integral of the scattering density over the sphere of directions
+fn pdf_integral(material: &dyn Material, rec: &HitRecord, theta: f64) -> f64 {
+    let ray = incoming(theta);
+    let (steps_theta, steps_phi) = (400, 200);
+    let mut sum = 0.0;
+    for i in 0..steps_theta {
+        let t = PI * (i as f64 + 0.5) / steps_theta as f64;
+        for j in 0..steps_phi {
+            let phi = 2.0 * PI * (j as f64 + 0.5) / steps_phi as f64;
+            let direction = Vector3::new(t.sin() * phi.cos(), t.sin() * phi.sin(), t.cos());
+            let scattered = Ray::new(rec.p, direction);
+            sum += material.scattering_pdf(&ray, rec, &scattered) * t.sin();
+        }
+    }
+    sum * (PI / steps_theta as f64) * (2.0 * PI / steps_phi as f64)
+}
+
+#[test]
+fn fresnel_matches_normal_incidence() {
+    assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+    assert_eq!(fresnel_dielectric(0.1, 1.0 / 1.5), 1.0);
+
+    let (eta, k) = ConductorPreset::Gold.eta_k();
+    let reflectance = fresnel_conductor(1.0, &eta, &k);
+    for axis in 0..3 {
+        let (n, k) = (eta[axis], k[axis]);
+        let expected = ((n - 1.0) * (n - 1.0) + k * k) / ((n + 1.0) * (n + 1.0) + k * k);
+        assert!((reflectance[axis] - expected).abs() < 1e-12);
+    }
+    // Gold reflects red more than blue
+    assert!(reflectance.x() > 0.9 && reflectance.z() < 0.5);
+}
+
+#[test]
+fn samples_agree_with_eval_and_pdf() {
+    for roughness in [0.2, 0.5, 0.9] {
+        assert_consistent(
+            &Conductor::from_preset(ConductorPreset::Copper, roughness),
+            &hit(true),
+        );
+        assert_consistent(&RoughDielectric::new(1.5, roughness), &hit(true));
+        assert_consistent(&RoughDielectric::new(1.5, roughness), &hit(false));
+        assert_consistent(&principled(0.3, roughness, 0.4), &hit(true));
+        assert_consistent(&principled(0.3, roughness, 0.4), &hit(false));
+    }
+}
+
+#[test]
+fn densities_integrate_to_at_most_one() {
+    for roughness in [0.3, 0.7] {
+        for theta in [0.0, 45.0, 75.0] {
+            let conductor = Conductor::from_preset(ConductorPreset::Aluminum, roughness);
+            let integral = pdf_integral(&conductor, &hit(true), theta);
+            assert!(integral > 0.8 && integral < 1.01, "conductor: {}", integral);
+
+            for front_face in [true, false] {
+                let glass = RoughDielectric::new(1.5, roughness);
+                let integral = pdf_integral(&glass, &hit(front_face), theta);
+                assert!(integral > 0.8 && integral < 1.01, "glass: {}", integral);
+
+                let material = principled(0.3, roughness, 0.4);
+                let integral = pdf_integral(&material, &hit(front_face), theta);
+                assert!(
+                    integral > 0.8 && integral < 1.01,
+                    "principled: {}",
+                    integral
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn materials_do_not_create_energy() {
+    for roughness in [0.0, 0.3, 1.0] {
+        for theta in [0.0, 60.0, 85.0] {
+            let gold = albedo(
+                &Conductor::from_preset(ConductorPreset::Gold, roughness),
+                &hit(true),
+                theta,
+            );
+            assert!(gold.x() <= 1.0 && gold.y() <= 1.0 && gold.z() <= 1.0);
+
+            // Smooth glass keeps everything, rough glass only loses the
+            // light masked by its own microfacets
+            let glass = albedo(&RoughDielectric::new(1.5, roughness), &hit(true), theta);
+            assert!(glass.x() <= 1.0 && glass.x() > 0.6, "{}", glass);
+        }
+    }
+
+    let smooth = albedo(&RoughDielectric::new(1.5, 0.0), &hit(true), 30.0);
+    assert_eq!(smooth.x(), 1.0);
+}
+
+#[test]
+fn principled_does_not_create_energy() {
+    for (metallic, transmission) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (0.5, 0.5)] {
+        for roughness in [0.0, 0.5, 1.0] {
+            for theta in [0.0, 60.0, 85.0] {
+                let material = principled(metallic, roughness, transmission);
+                let albedo = albedo(&material, &hit(true), theta);
+                assert!(
+                    albedo.x() <= 1.0 && albedo.y() <= 1.0 && albedo.z() <= 1.0,
+                    "{} with metallic {}, roughness {}, transmission {} at {} degrees",
+                    albedo,
+                    metallic,
+                    roughness,
+                    transmission,
+                    theta
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn principled_parameters_follow_their_textures() {
+    // Alternating cells of plastic and copper-coloured metal
+    let mut material = Principled::new(Color::new(0.9, 0.5, 0.3));
+    material.metallic = Arc::new(CheckerTexture::new_colors(
+        1.0,
+        Color::new(0.0, 0.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+    ));
+    material.roughness = Principled::scalar(0.1);
+
+    let mut plastic = hit(true);
+    plastic.p = Point3::new(0.5, 0.5, 0.5);
+    let mut metal = hit(true);
+    metal.p = Point3::new(1.5, 0.5, 0.5);
+
+    // Off the mirror direction, only the diffuse lobe of the plastic is left
+    let ray = incoming(30.0);
+    let off_specular = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.6, 0.8));
+    assert!(material.eval(&ray, &plastic, &off_specular).x() > 0.1);
+    assert!(material.eval(&ray, &metal, &off_specular).x() < 1e-3);
+}
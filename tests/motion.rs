@@ -0,0 +1,128 @@
+mod common;
+
+use common::{closest_t, grey, random_point};
+use raytracer::hittable::bvh::BVH;
+use raytracer::hittable::quad::create_box;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::transform::{AnimatedTransform, Transform};
+use raytracer::hittable::{Hittable, HittableList};
+use raytracer::matrix::{AnimatedMatrix, Matrix4};
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+#[test]
+fn moving_sphere_follows_the_ray_time() {
+    let sphere = Sphere::new_moving(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 2.0, 0.0),
+        0.5,
+        grey(),
+    );
+    let origin = Point3::new(0.0, 1.0, -5.0);
+    let direction = Vector3::new(0.0, 0.0, 1.0);
+
+    assert_eq!(
+        closest_t(&sphere, &Ray::new_at_time(origin, direction, 0.0)),
+        None
+    );
+    assert_eq!(
+        closest_t(&sphere, &Ray::new_at_time(origin, direction, 0.5)),
+        Some(4.5)
+    );
+    assert_eq!(
+        closest_t(&sphere, &Ray::new_at_time(origin, direction, 1.0)),
+        None
+    );
+
+    let bbox = sphere.bbox();
+    assert_eq!((bbox.min().y(), bbox.max().y()), (-0.5, 2.5));
+}
+
+#[test]
+fn animation_interpolates_rotation_rigidly() {
+    let start = Matrix4::scale(Vector3::new(2.0, 1.0, 1.0))
+        .then(&Matrix4::translate(Vector3::new(1.0, 0.0, 0.0)));
+    let end = Matrix4::scale(Vector3::new(2.0, 1.0, 1.0))
+        .then(&Matrix4::rotate_y(90.0))
+        .then(&Matrix4::translate(Vector3::new(1.0, 4.0, 0.0)));
+    let halfway = Matrix4::scale(Vector3::new(2.0, 1.0, 1.0))
+        .then(&Matrix4::rotate_y(45.0))
+        .then(&Matrix4::translate(Vector3::new(1.0, 2.0, 0.0)));
+    let motion = AnimatedMatrix::new(start, end);
+
+    for (time, expected) in [(0.0, start), (0.5, halfway), (1.0, end)] {
+        let actual = motion.at(time);
+        for row in 0..4 {
+            for column in 0..4 {
+                assert!(
+                    (actual.get(row, column) - expected.get(row, column)).abs() < 1e-9,
+                    "{:?} != {:?} at time {}",
+                    actual,
+                    expected,
+                    time
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn bvh_over_moving_objects_misses_nothing() {
+    let mut rng = Rng::new(18);
+    let mut world = HittableList::new();
+
+    for _ in 0..40 {
+        let center = random_point(&mut rng, 8.0);
+        world.add(Arc::new(Sphere::new_moving(
+            center,
+            center + random_point(&mut rng, 2.0),
+            rng.random_range(0.2, 1.0),
+            grey(),
+        )));
+    }
+    for _ in 0..40 {
+        let a = random_point(&mut rng, 1.0);
+        let object = Arc::new(create_box(a, a + Vector3::new(1.0, 2.0, 0.5), grey()));
+        let axis = random_point(&mut rng, 1.0);
+        let start = Matrix4::translate(random_point(&mut rng, 8.0));
+        let end = Matrix4::rotate(rng.random_range(0.0, 720.0), axis)
+            .then(&Matrix4::translate(random_point(&mut rng, 8.0)));
+        world.add(Arc::new(AnimatedTransform::new(object, start, end)));
+    }
+
+    let bvh = BVH::new(&world);
+    for _ in 0..5000 {
+        let origin = random_point(&mut rng, 12.0);
+        let ray = Ray::new_at_time(
+            origin,
+            random_point(&mut rng, 8.0) - origin,
+            rng.random_f64(),
+        );
+        assert_eq!(closest_t(&bvh, &ray), closest_t(&world, &ray));
+    }
+}
+
+#[test]
+fn still_animation_matches_static_transform() {
+    let matrix = Matrix4::rotate(30.0, Vector3::new(1.0, 1.0, 0.0))
+        .then(&Matrix4::translate(Vector3::new(0.0, 1.0, 2.0)));
+    let object = Arc::new(create_box(
+        Point3::new(-1.0, -1.0, -1.0),
+        Point3::new(1.0, 1.0, 1.0),
+        grey(),
+    ));
+    let animated = AnimatedTransform::new(object.clone(), matrix, matrix);
+    let fixed = Transform::new(object, matrix);
+    let mut rng = Rng::new(3);
+
+    for _ in 0..500 {
+        let origin = random_point(&mut rng, 6.0);
+        let ray = Ray::new_at_time(origin, random_point(&mut rng, 1.5) - origin, 0.3);
+        match (closest_t(&animated, &ray), closest_t(&fixed, &ray)) {
+            (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9),
+            (a, b) => assert_eq!(a, b),
+        }
+    }
+}
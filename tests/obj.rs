@@ -0,0 +1,170 @@
+mod common;
+
+use common::{grey, temp_dir};
+use raytracer::color::Color;
+use raytracer::hittable::HitRecord;
+use raytracer::interval::Interval;
+use raytracer::obj;
+use raytracer::obj::mtl;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::fs;
+use std::path::Path;
+
+const QUAD: &str = "
+# unit square in the xy plane, written as one polygon
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+v -1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f -4/-4/1 -3/-3/1 -2/-2/1 -1/-1/1
+";
+
+#[test]
+fn polygon_faces_are_triangulated_with_uvs() {
+    let meshes = obj::parse(QUAD, Path::new(""), grey()).unwrap();
+    assert_eq!(meshes.objects.len(), 1);
+
+    let mut rec = HitRecord::new();
+    let ray = Ray::new(Point3::new(0.5, 0.5, 2.0), Vector3::new(0.0, 0.0, -1.0));
+    assert!(meshes.hit(
+        &ray,
+        Interval::new(0.001, f64::INFINITY),
+        &mut rec,
+        &mut Rng::new(0)
+    ));
+    assert!((rec.u - 0.75).abs() < 1e-9);
+    assert!((rec.v - 0.75).abs() < 1e-9);
+    assert!((rec.normal.z() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn materials_split_faces_into_meshes() {
+    let dir = temp_dir("materials");
+    fs::write(
+        dir.join("scene.mtl"),
+        "newmtl red\nKd 0.8 0.1 0.1\n\nnewmtl lamp\nKe 4 4 4\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("scene.obj"),
+        "mtllib scene.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nusemtl red\nf 1 2 3\nusemtl lamp\nf 2 4 3\n",
+    )
+    .unwrap();
+
+    let meshes = obj::load(&dir.join("scene.obj"), grey()).unwrap();
+    assert_eq!(meshes.objects.len(), 2);
+
+    let mut rec = HitRecord::new();
+    let ray = Ray::new(Point3::new(0.8, 0.8, 1.0), Vector3::new(0.0, 0.0, -1.0));
+    assert!(meshes.hit(
+        &ray,
+        Interval::new(0.001, f64::INFINITY),
+        &mut rec,
+        &mut Rng::new(0)
+    ));
+    let emitted = rec.mat.emitted(rec.u, rec.v, &rec.p);
+    assert_eq!(emitted.x(), 4.0);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn malformed_files_report_line_numbers() {
+    let out_of_range = "v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+    let message = format!(
+        "{:#}",
+        obj::parse(out_of_range, Path::new(""), grey())
+            .err()
+            .unwrap()
+    );
+    assert!(message.contains("line 3"), "{}", message);
+
+    let bad_number = "v 0 0 0\nv 1 zero 0\n";
+    let message = format!(
+        "{:#}",
+        obj::parse(bad_number, Path::new(""), grey()).err().unwrap()
+    );
+    assert!(message.contains("line 2"), "{}", message);
+
+    let unknown_material = "v 0 0 0\nusemtl missing\n";
+    let message = format!(
+        "{:#}",
+        obj::parse(unknown_material, Path::new(""), grey())
+            .err()
+            .unwrap()
+    );
+    assert!(
+        message.contains("unknown material 'missing'"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn pbr_keys_give_a_principled_material() {
+    let source =
+        "newmtl plastic\nKd 0.8 0.8 0.8\nPr 0.2\n\nnewmtl chrome\nKd 0.8 0.8 0.8\nPm 1\nPr 0.2\n";
+    let materials = mtl::parse(source, Path::new("")).unwrap();
+
+    let mut rec = HitRecord::new();
+    rec.normal = Vector3::new(0.0, 0.0, 1.0);
+    rec.front_face = true;
+    let ray = Ray::new(Point3::new(0.5, 0.0, 1.0), Vector3::new(-0.5, 0.0, -1.0));
+    let off_specular = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.6, 0.8));
+
+    // The plastic keeps its diffuse base, the metal only reflects specularly
+    let plastic = materials["plastic"].eval(&ray, &rec, &off_specular);
+    let chrome = materials["chrome"].eval(&ray, &rec, &off_specular);
+    assert!(plastic.x() > 0.1);
+    assert!(chrome.x() < 0.1 * plastic.x(), "{} vs {}", chrome, plastic);
+}
+
+#[test]
+fn bad_mtl_values_are_errors_naming_the_file_and_line() {
+    let dir = temp_dir("bad-mtl");
+    let path = dir.join("missing.mtl");
+    fs::write(&path, "newmtl painted\nKd 1 1 1\nmap_Kd nowhere.png\n").unwrap();
+    let message = format!("{:#}", mtl::load(&path).err().unwrap());
+    assert!(message.contains("missing.mtl"), "{}", message);
+    assert!(message.contains("line 3"), "{}", message);
+    assert!(
+        message.contains(&dir.join("nowhere.png").display().to_string()),
+        "{}",
+        message
+    );
+
+    let rough = "newmtl rough\nKd 1 1 1\nPr 1.5\n";
+    let message = format!("{:#}", mtl::parse(rough, Path::new("")).err().unwrap());
+    assert!(message.contains("line 3"), "{}", message);
+    assert!(
+        message.contains("Pr must be between 0 and 1"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn glass_without_an_index_still_refracts() {
+    let materials = mtl::parse("newmtl glass\nKd 1 1 1\nd 0.5\n", Path::new("")).unwrap();
+
+    let mut rec = HitRecord::new();
+    rec.normal = Vector3::new(0.0, 0.0, 1.0);
+    rec.front_face = true;
+    let ray = Ray::new(Point3::new(-1.0, 0.0, 1.0), Vector3::new(1.0, 0.0, -1.0));
+    let mut rng = Rng::new(3);
+    for _ in 0..32 {
+        let mut attenuation = Color::new(0.0, 0.0, 0.0);
+        let mut scattered = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!(materials["glass"].scatter(&ray, &rec, &mut attenuation, &mut scattered, &mut rng));
+        let direction = scattered.get_direction().unit_vector();
+        let straight = ray.get_direction().unit_vector();
+        assert!((direction - straight).length() > 0.1, "{}", direction);
+    }
+}
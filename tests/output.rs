@@ -0,0 +1,108 @@
+mod common;
+
+use common::temp_path;
+use raytracer::color::Color;
+use raytracer::output::{self, BitDepth, Framebuffer, ImageFormat};
+use raytracer::tonemap::ToneMapper;
+use std::path::Path;
+
+fn gradient() -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(4, 3);
+    for y in 0..3 {
+        for x in 0..4 {
+            // Includes values well above 1 that only float formats keep
+            framebuffer.set(x, y, Color::new(x as f64 * 2.5, y as f64 * 0.25, 0.125));
+        }
+    }
+    framebuffer
+}
+
+#[test]
+fn format_follows_extension() {
+    assert_eq!(
+        ImageFormat::from_path(Path::new("a.png")).unwrap(),
+        ImageFormat::Png
+    );
+    assert_eq!(
+        ImageFormat::from_path(Path::new("a.EXR")).unwrap(),
+        ImageFormat::Exr
+    );
+    assert_eq!(
+        ImageFormat::from_path(Path::new("a.hdr")).unwrap(),
+        ImageFormat::Hdr
+    );
+    assert_eq!(
+        ImageFormat::from_path(Path::new("a.ppm")).unwrap(),
+        ImageFormat::Ppm
+    );
+    assert!(ImageFormat::from_path(Path::new("a.jpg")).is_err());
+    assert!(ImageFormat::from_path(Path::new("image")).is_err());
+}
+
+#[test]
+fn exr_keeps_linear_values() {
+    let framebuffer = gradient();
+    let path = temp_path("linear.exr");
+    output::write(&framebuffer, &path, BitDepth::Eight, &ToneMapper::new()).unwrap();
+
+    let image = image::open(&path).unwrap().to_rgb32f();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(image.dimensions(), (4, 3));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let expected = framebuffer.get(x, y);
+        for i in 0..3 {
+            assert_eq!(pixel[i], expected[i] as f32);
+        }
+    }
+}
+
+#[test]
+fn hdr_keeps_values_above_one() {
+    let framebuffer = gradient();
+    let path = temp_path("linear.hdr");
+    output::write(&framebuffer, &path, BitDepth::Eight, &ToneMapper::new()).unwrap();
+
+    let image = image::open(&path).unwrap().to_rgb32f();
+    std::fs::remove_file(&path).unwrap();
+
+    // RGBE shares one exponent between the channels, so compare loosely
+    let pixel = image.get_pixel(3, 2);
+    assert!((pixel[0] - 7.5).abs() < 0.1);
+    assert!((pixel[1] - 0.5).abs() < 0.01);
+}
+
+#[test]
+fn png_is_srgb_encoded_and_clamped() {
+    let framebuffer = gradient();
+
+    let path = temp_path("eight.png");
+    output::write(&framebuffer, &path, BitDepth::Eight, &ToneMapper::new()).unwrap();
+    let eight = image::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(eight.color(), image::ColorType::Rgb8);
+    let eight = eight.to_rgb8();
+    assert_eq!(eight.get_pixel(3, 0)[0], 255);
+    assert_eq!(eight.get_pixel(0, 1)[1], 137);
+
+    let path = temp_path("sixteen.png");
+    output::write(&framebuffer, &path, BitDepth::Sixteen, &ToneMapper::new()).unwrap();
+    let sixteen = image::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(sixteen.color(), image::ColorType::Rgb16);
+    let sixteen = sixteen.to_rgb16();
+    assert_eq!(sixteen.get_pixel(0, 1)[1], 35199);
+    assert_eq!(sixteen.get_pixel(0, 0)[2], 25465);
+}
+
+#[test]
+fn heatmap_runs_from_black_to_pale_yellow() {
+    let heatmap = output::heatmap(&[0, 50, 100], 3, 1, 100);
+
+    let black = heatmap.get(0, 0);
+    assert_eq!((black.x(), black.y(), black.z()), (0.0, 0.0, 0.0));
+    let middle = heatmap.get(1, 0);
+    assert!(middle.x() > middle.y() && middle.x() > middle.z());
+    let full = heatmap.get(2, 0);
+    assert!(full.x() > 0.9 && full.y() > 0.9);
+}
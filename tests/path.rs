@@ -0,0 +1,58 @@
+use raytracer::camera::Camera;
+use raytracer::color::Color;
+use raytracer::hittable::HittableList;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::light::LightList;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::vector::Point3;
+use std::sync::Arc;
+
+fn grey_sphere(albedo: f64) -> HittableList {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, 0.0),
+        1.0,
+        Arc::new(Lambertian::new(Color::new(albedo, albedo, albedo))),
+    )));
+    world
+}
+
+#[test]
+fn russian_roulette_keeps_the_furnace_test_unbiased() {
+    // A convex sphere under a uniform white sky reflects exactly its albedo
+    let mut camera = Camera::new();
+    camera.image_width = 8;
+    camera.samples_per_pixel = 64;
+    camera.roulette_depth = 0;
+    camera.vfov = 10.0;
+    camera.lookfrom = Point3::new(0.0, 0.0, 5.0);
+    camera.background = Color::new(1.0, 1.0, 1.0);
+
+    let framebuffer = camera
+        .render_to_framebuffer(grey_sphere(0.5), HittableList::new(), LightList::new())
+        .unwrap();
+
+    let pixels = framebuffer.pixels();
+    let mean = pixels.iter().map(|pixel| pixel.y()).sum::<f64>() / pixels.len() as f64;
+    assert!((mean - 0.5).abs() < 0.02, "{}", mean);
+    // Individual paths were either ended or boosted to make up for it
+    assert!(pixels.iter().any(|pixel| pixel.y() != 0.5));
+}
+
+#[test]
+fn very_long_paths_do_not_grow_the_stack() {
+    // From inside a closed sphere no path ever escapes
+    let mut camera = Camera::new();
+    camera.image_width = 1;
+    camera.samples_per_pixel = 1;
+    camera.max_depth = 100_000;
+    camera.roulette_depth = camera.max_depth;
+    camera.threads = 1;
+
+    let framebuffer = camera
+        .render_to_framebuffer(grey_sphere(0.9), HittableList::new(), LightList::new())
+        .unwrap();
+
+    let pixel = framebuffer.get(0, 0);
+    assert_eq!((pixel.x(), pixel.y(), pixel.z()), (0.0, 0.0, 0.0));
+}
@@ -0,0 +1,141 @@
+use raytracer::color::Color;
+use raytracer::hittable::quad::Quad;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::transform::Transform;
+use raytracer::hittable::{Hittable, HittableList};
+use raytracer::material::dielectric::DiffuseLight;
+use raytracer::matrix::Matrix4;
+use raytracer::pdf::{CosinePdf, Pdf, power_heuristic};
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+const SAMPLES: usize = 200_000;
+
+fn light() -> Arc<DiffuseLight> {
+    Arc::new(DiffuseLight::from_color(Color::new(1.0, 1.0, 1.0)))
+}
+
+/// Monte Carlo estimate of the integral of the shape's density over all directions
+fn integrate(object: &dyn Hittable, origin: Point3) -> f64 {
+    let mut rng = Rng::new(1);
+    let sum: f64 = (0..SAMPLES)
+        .map(|_| object.pdf_value(&origin, &Vector3::random_unit_vector(&mut rng)))
+        .sum();
+    4.0 * PI * sum / SAMPLES as f64
+}
+
+fn assert_sampled_directions_hit(object: &dyn Hittable, origin: Point3) {
+    let mut rng = Rng::new(2);
+    for _ in 0..1000 {
+        let direction = object.random(&origin, &mut rng);
+        assert!(object.pdf_value(&origin, &direction) > 0.0);
+    }
+}
+
+#[test]
+fn quad_pdf_integrates_to_one() {
+    let quad = Quad::new(
+        Point3::new(-1.0, 1.0, -1.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 2.0),
+        light(),
+    );
+
+    let origin = Point3::new(0.2, 0.0, 0.1);
+    assert!((integrate(&quad, origin) - 1.0).abs() < 0.03);
+    assert_sampled_directions_hit(&quad, origin);
+}
+
+#[test]
+fn sphere_pdf_integrates_to_one() {
+    let sphere = Sphere::new(Point3::new(0.0, 0.0, -3.0), 1.5, light());
+
+    let outside = Point3::new(0.5, 0.0, 0.0);
+    assert!((integrate(&sphere, outside) - 1.0).abs() < 0.03);
+    assert_sampled_directions_hit(&sphere, outside);
+
+    let inside = Point3::new(0.0, 0.5, -3.0);
+    assert!((integrate(&sphere, inside) - 1.0).abs() < 0.03);
+    assert_sampled_directions_hit(&sphere, inside);
+}
+
+#[test]
+fn transformed_lights_keep_their_density() {
+    let quad: Arc<dyn Hittable> = Arc::new(Quad::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(0.0, 2.0, 0.0),
+        light(),
+    ));
+    let matrix = Matrix4::rotate_y(30.0).then(&Matrix4::translate(Vector3::new(-1.0, -1.0, -1.5)));
+    let placed = Transform::new(quad, matrix);
+
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    assert!((integrate(&placed, origin) - 1.0).abs() < 0.03);
+    assert_sampled_directions_hit(&placed, origin);
+}
+
+#[test]
+fn scaled_lights_keep_their_density() {
+    let sphere: Arc<dyn Hittable> = Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, light()));
+    let matrix = Matrix4::scale(Vector3::new(2.0, 0.5, 1.0))
+        .then(&Matrix4::rotate(40.0, Vector3::new(1.0, 1.0, 0.0)))
+        .then(&Matrix4::translate(Vector3::new(0.0, 0.0, -3.0)));
+    let ellipsoid = Transform::new(sphere, matrix);
+
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    assert!((integrate(&ellipsoid, origin) - 1.0).abs() < 0.03);
+    assert_sampled_directions_hit(&ellipsoid, origin);
+}
+
+#[test]
+fn light_list_averages_its_members() {
+    let mut lights = HittableList::new();
+    lights.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, -3.0),
+        1.0,
+        light(),
+    )));
+    lights.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 3.0, 0.0),
+        1.0,
+        light(),
+    )));
+
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    assert!((integrate(&lights, origin) - 1.0).abs() < 0.03);
+    assert_sampled_directions_hit(&lights, origin);
+    assert_eq!(
+        HittableList::new().pdf_value(&origin, &Vector3::new(0.0, 0.0, -1.0)),
+        0.0
+    );
+}
+
+#[test]
+fn cosine_pdf_matches_its_samples() {
+    let normal = Vector3::new(1.0, 2.0, -0.5).unit_vector();
+    let pdf = CosinePdf::new(&normal);
+
+    let mut rng = Rng::new(3);
+    let mut mean_cosine = 0.0;
+    for _ in 0..SAMPLES {
+        let direction = pdf.generate(&mut rng);
+        let cosine = Vector3::dot(&direction, &normal);
+        assert!(cosine >= 0.0);
+        assert!((pdf.value(&direction) - cosine / PI).abs() < 1e-9);
+        mean_cosine += cosine / SAMPLES as f64;
+    }
+
+    // E[cos] under a cos / pi density is 2/3
+    assert!((mean_cosine - 2.0 / 3.0).abs() < 0.01);
+}
+
+#[test]
+fn power_heuristic_weights_sum_to_one() {
+    assert_eq!(power_heuristic(0.7, 0.0), 1.0);
+    assert_eq!(power_heuristic(0.0, 0.0), 0.0);
+    let sum = power_heuristic(0.3, 1.2) + power_heuristic(1.2, 0.3);
+    assert!((sum - 1.0).abs() < 1e-12);
+}
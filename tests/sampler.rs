@@ -0,0 +1,130 @@
+use raytracer::camera::Camera;
+use raytracer::color::Color;
+use raytracer::hittable::quad::Quad;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::{Hittable, HittableList};
+use raytracer::light::LightList;
+use raytracer::material::dielectric::DiffuseLight;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::output::Framebuffer;
+use raytracer::sampler::SamplerKind;
+use raytracer::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+#[test]
+fn samples_stay_in_the_unit_interval() {
+    for kind in SamplerKind::ALL {
+        let mut sampler = kind.build(9, 3);
+        for index in 0..20 {
+            sampler.start_pixel_sample(5, 7, index);
+            for _ in 0..40 {
+                let u = sampler.get_1d();
+                let (a, b) = sampler.get_2d();
+                for value in [u, a, b] {
+                    assert!((0.0..1.0).contains(&value), "{}: {}", kind, value);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn stratified_and_sobol_fill_every_stratum() {
+    for kind in [SamplerKind::Stratified, SamplerKind::Sobol] {
+        let mut sampler = kind.build(16, 0);
+        let mut strata_1d = [[false; 16]; 6];
+        let mut strata_2d = [[false; 16]; 6];
+
+        for index in 0..16 {
+            sampler.start_pixel_sample(2, 3, index);
+            for dimension in 0..6 {
+                let u = sampler.get_1d();
+                let (a, b) = sampler.get_2d();
+                strata_1d[dimension][(u * 16.0) as usize] = true;
+                strata_2d[dimension][(a * 4.0) as usize * 4 + (b * 4.0) as usize] = true;
+            }
+        }
+
+        assert!(strata_1d.iter().flatten().all(|&filled| filled), "{}", kind);
+        assert!(strata_2d.iter().flatten().all(|&filled| filled), "{}", kind);
+    }
+}
+
+/// A sphere casting a soft shadow from a quad light, seen through a lens
+fn render_soft_shadow(sampler: SamplerKind, samples_per_pixel: i32) -> Framebuffer {
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let light: Arc<dyn Hittable> = Arc::new(Quad::new(
+        Point3::new(-1.0, 3.0, -1.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 2.0),
+        Arc::new(DiffuseLight::from_color(Color::new(4.0, 4.0, 4.0))),
+    ));
+
+    let mut world = HittableList::new();
+    world.add(Arc::new(Quad::new(
+        Point3::new(-4.0, 0.0, -4.0),
+        Vector3::new(8.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 8.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        0.6,
+        white,
+    )));
+    world.add(Arc::clone(&light));
+    let mut lights = HittableList::new();
+    lights.add(light);
+
+    let mut camera = Camera::new();
+    camera.image_width = 24;
+    camera.samples_per_pixel = samples_per_pixel;
+    camera.max_depth = 2;
+    camera.vfov = 50.0;
+    camera.lookfrom = Point3::new(0.0, 4.0, 5.0);
+    camera.lookat = Point3::new(0.0, 0.5, 0.0);
+    camera.defocus_angle = 2.0;
+    camera.focus_dist = 5.5;
+    camera.background = Color::new(0.0, 0.0, 0.0);
+    camera.sampler = sampler;
+    camera.seed = 11;
+
+    camera
+        .render_to_framebuffer(world, lights, LightList::new())
+        .unwrap()
+}
+
+fn rmse(image: &Framebuffer, reference: &Framebuffer) -> f64 {
+    let squared: f64 = image
+        .pixels()
+        .iter()
+        .zip(reference.pixels())
+        .map(|(a, b)| (*a - *b).length_squared())
+        .sum();
+    (squared / image.pixels().len() as f64).sqrt()
+}
+
+#[test]
+fn low_discrepancy_samplers_beat_independent_sampling() {
+    let reference = render_soft_shadow(SamplerKind::Independent, 2048);
+    let independent = rmse(
+        &render_soft_shadow(SamplerKind::Independent, 16),
+        &reference,
+    );
+
+    // Same sample count, so only the placement of the samples differs
+    for kind in [
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+    ] {
+        let error = rmse(&render_soft_shadow(kind, 16), &reference);
+        assert!(
+            error < 0.9 * independent,
+            "{}: {} vs {}",
+            kind,
+            error,
+            independent
+        );
+    }
+}
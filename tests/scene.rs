@@ -0,0 +1,93 @@
+use raytracer::camera::Camera;
+use raytracer::color::Color;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::{HitRecord, HittableList};
+use raytracer::interval::Interval;
+use raytracer::light::LightList;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::scene::{Scene, builtin};
+use raytracer::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+#[test]
+fn world_built_through_public_api_is_hittable() {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 0.0, -1.0),
+        0.5,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+
+    let scene = Scene::new(world, HittableList::new(), Camera::new());
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+    let mut rec = HitRecord::new();
+    assert!(scene.world.hit(
+        &ray,
+        Interval::new(0.001, f64::INFINITY),
+        &mut rec,
+        &mut Rng::new(0)
+    ));
+    assert!((rec.t - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn builtin_cornell_box_has_walls_light_and_boxes() {
+    let scene = builtin::cornell_box();
+
+    // Five walls, one light and two boxes of six sides each
+    assert_eq!(scene.world.objects.len(), 18);
+    assert_eq!(scene.camera.image_width, 400);
+}
+
+#[test]
+fn tiles_cover_every_pixel() {
+    let mut camera = Camera::new();
+    camera.image_width = 50;
+    camera.aspect_ratio = 1.5;
+    camera.samples_per_pixel = 1;
+    camera.background = Color::new(0.25, 0.5, 0.75);
+    // Neither side of the image is a multiple of the tile size
+    camera.tile_size = 7;
+    camera.threads = 3;
+
+    let framebuffer = camera
+        .render_to_framebuffer(HittableList::new(), HittableList::new(), LightList::new())
+        .unwrap();
+
+    assert_eq!((framebuffer.width(), framebuffer.height()), (50, 33));
+    for pixel in framebuffer.pixels() {
+        assert_eq!((pixel.x(), pixel.y(), pixel.z()), (0.25, 0.5, 0.75));
+    }
+}
+
+fn render_smoke_box(threads: usize, tile_size: i32, seed: u64) -> Vec<(f64, f64, f64)> {
+    let mut scene = builtin::cornell_box_smoke();
+    scene.camera.image_width = 24;
+    scene.camera.samples_per_pixel = 4;
+    scene.camera.max_depth = 6;
+    scene.camera.threads = threads;
+    scene.camera.tile_size = tile_size;
+    scene.camera.seed = seed;
+
+    let framebuffer = scene
+        .camera
+        .render_to_framebuffer(scene.world, scene.lights, scene.analytic_lights)
+        .unwrap();
+    framebuffer
+        .pixels()
+        .iter()
+        .map(|pixel| (pixel.x(), pixel.y(), pixel.z()))
+        .collect()
+}
+
+#[test]
+fn render_is_identical_for_any_thread_count() {
+    let reference = render_smoke_box(1, 32, 42);
+
+    assert!(render_smoke_box(4, 5, 42) == reference);
+    assert!(render_smoke_box(2, 1, 42) == reference);
+    assert!(render_smoke_box(1, 32, 43) != reference);
+}
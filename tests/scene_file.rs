@@ -0,0 +1,456 @@
+use raytracer::sampler::SamplerKind;
+use raytracer::scene::Scene;
+use raytracer::scene::file;
+use raytracer::tonemap::Operator;
+use std::path::Path;
+
+#[test]
+fn example_scene_file_loads() {
+    let scene = Scene::from_file(Path::new("scenes/cornell_box_smoke.toml")).unwrap();
+
+    assert_eq!(scene.world.objects.len(), 8);
+    assert_eq!(scene.lights.objects.len(), 1);
+    assert_eq!(scene.camera.samples_per_pixel, 250);
+    assert_eq!(scene.camera.lookfrom.z(), -800.0);
+}
+
+#[test]
+fn unknown_material_reports_object_line() {
+    let source = r#"
+[materials.white]
+type = "lambertian"
+albedo = [0.73, 0.73, 0.73]
+
+[[objects]]
+type = "sphere"
+center = [0, 0, 0]
+radius = 1
+material = "whte"
+"#;
+
+    let message = format!("{:#}", file::parse(source).err().unwrap());
+    assert!(message.contains("line 6"), "{}", message);
+    assert!(message.contains("unknown material 'whte'"), "{}", message);
+}
+
+#[test]
+fn syntax_errors_report_line() {
+    let source = "[camera]\nimage_width = 400\nvfov = \n";
+
+    let message = format!("{:#}", file::parse(source).err().unwrap());
+    assert!(message.contains("line 3"), "{}", message);
+}
+
+#[test]
+fn camera_reads_tone_mapping() {
+    let source = "[camera]\nexposure = -1.5\ntone_map = \"aces\"\n";
+    let scene = file::parse(source).unwrap();
+    assert_eq!(scene.camera.tone_mapper.exposure, -1.5);
+    assert_eq!(scene.camera.tone_mapper.operator, Operator::Aces);
+
+    let message = format!(
+        "{:#}",
+        file::parse("[camera]\ntone_map = \"filmic\"\n")
+            .err()
+            .unwrap()
+    );
+    assert!(
+        message.contains("unknown tone mapping operator 'filmic'"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn camera_reads_sampler() {
+    let scene = file::parse("[camera]\nsampler = \"halton\"\n").unwrap();
+    assert_eq!(scene.camera.sampler, SamplerKind::Halton);
+
+    assert!(file::parse("[camera]\nsampler = \"random\"\n").is_err());
+}
+
+#[test]
+fn camera_rejects_sizes_and_sample_counts_out_of_range() {
+    let source = "[camera]\nsamples_per_pixel = 10\nmin_samples_per_pixel = 16\n";
+    let message = format!("{:#}", file::parse(source).err().unwrap());
+    assert!(
+        message.contains("min_samples_per_pixel 16 is more than samples_per_pixel 10"),
+        "{}",
+        message
+    );
+
+    for key in ["image_width", "samples_per_pixel", "max_depth"] {
+        let source = format!("[camera]\n{} = 0\n", key);
+        let message = format!("{:#}", file::parse(&source).err().unwrap());
+        assert!(message.contains("must be positive, got 0"), "{}", message);
+    }
+}
+
+#[test]
+fn transforms_are_combined_and_checked() {
+    let source = |scale: &str| {
+        format!(
+            r#"
+[materials.white]
+type = "lambertian"
+albedo = [0.73, 0.73, 0.73]
+
+[[objects]]
+type = "sphere"
+center = [0, 0, 0]
+radius = 1
+material = "white"
+transforms = [{{ scale = {} }}, {{ rotate = {{ axis = [1, 1, 0], angle = 30 }} }}, {{ translate = [0, 5, 0] }}]
+"#,
+            scale
+        )
+    };
+
+    let scene = file::parse(&source("[2, 1, 1]")).unwrap();
+    let bbox = scene.world.objects[0].bbox();
+    assert!(bbox.min().y() > 3.0 && bbox.max().y() < 7.0);
+
+    let message = format!("{:#}", file::parse(&source("[0, 1, 1]")).err().unwrap());
+    assert!(message.contains("scale factor is zero"), "{}", message);
+}
+
+#[test]
+fn instances_share_their_group() {
+    let source = |group: &str| {
+        format!(
+            r#"
+[materials.white]
+type = "lambertian"
+albedo = [0.73, 0.73, 0.73]
+
+[materials.red]
+type = "lambertian"
+albedo = [0.65, 0.05, 0.05]
+
+[[groups.pillar.objects]]
+type = "box"
+a = [0, 0, 0]
+b = [1, 4, 1]
+material = "white"
+
+[[objects]]
+type = "instance"
+group = "pillar"
+transforms = [{{ translate = [-3, 0, 0] }}]
+
+[[objects]]
+type = "instance"
+group = "{}"
+material = "red"
+transforms = [{{ scale = [1, 2, 1] }}, {{ translate = [3, 0, 0] }}]
+"#,
+            group
+        )
+    };
+
+    let scene = file::parse(&source("pillar")).unwrap();
+    assert_eq!(scene.world.objects.len(), 2);
+    assert!((scene.world.objects[0].bbox().max().y() - 4.0).abs() < 1e-3);
+    assert!((scene.world.objects[1].bbox().max().y() - 8.0).abs() < 1e-3);
+
+    let message = format!("{:#}", file::parse(&source("column")).err().unwrap());
+    assert!(message.contains("line 21"), "{}", message);
+    assert!(message.contains("unknown group 'column'"), "{}", message);
+}
+
+#[test]
+fn moving_objects_are_read_but_not_sampled_as_lights() {
+    let source = r#"
+[camera]
+shutter_open = 0.25
+shutter_close = 0.75
+
+[materials.lamp]
+type = "diffuse_light"
+emit = [4, 4, 4]
+
+[[objects]]
+type = "sphere"
+center = [0, 0, 0]
+end_center = [0, 1, 0]
+radius = 1
+material = "lamp"
+
+[[objects]]
+type = "quad"
+q = [0, 0, 0]
+u = [1, 0, 0]
+v = [0, 1, 0]
+material = "lamp"
+end_transforms = [{ translate = [0, 0, 2] }]
+
+[[objects]]
+type = "quad"
+q = [0, 0, 0]
+u = [1, 0, 0]
+v = [0, 1, 0]
+material = "lamp"
+"#;
+
+    let scene = file::parse(source).unwrap();
+    assert_eq!(scene.camera.shutter_open, 0.25);
+    assert_eq!(scene.camera.shutter_close, 0.75);
+    assert_eq!(scene.world.objects.len(), 3);
+    assert_eq!(scene.lights.objects.len(), 1);
+    assert!(scene.world.objects[0].bbox().max().y() > 1.9);
+    assert!(scene.world.objects[1].bbox().max().z() > 1.9);
+}
+
+#[test]
+fn microfacet_materials_are_checked() {
+    let scene = Scene::from_file(Path::new("scenes/microfacet.toml")).unwrap();
+    assert_eq!(scene.world.objects.len(), 7);
+    assert_eq!(scene.lights.objects.len(), 1);
+
+    let material = |body: &str| format!("[materials.metal]\n{}\n", body);
+    assert!(
+        file::parse(&material(
+            "type = \"conductor\"\neta = [1, 1, 1]\nk = [2, 2, 2]"
+        ))
+        .is_ok()
+    );
+
+    let message = format!(
+        "{:#}",
+        file::parse(&material("type = \"conductor\"\neta = [1, 1, 1]"))
+            .err()
+            .unwrap()
+    );
+    assert!(
+        message.contains("either a preset or both eta and k"),
+        "{}",
+        message
+    );
+
+    let message = format!(
+        "{:#}",
+        file::parse(&material("type = \"conductor\"\npreset = \"brass\""))
+            .err()
+            .unwrap()
+    );
+    assert!(message.contains("unknown conductor 'brass'"), "{}", message);
+
+    let message = format!(
+        "{:#}",
+        file::parse(&material(
+            "type = \"dielectric\"\nrefraction_index = 1.5\nroughness = 2"
+        ))
+        .err()
+        .unwrap()
+    );
+    assert!(
+        message.contains("roughness must be between 0 and 1"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn principled_parameters_take_values_or_textures() {
+    let scene = Scene::from_file(Path::new("scenes/principled.toml")).unwrap();
+    assert_eq!(scene.world.objects.len(), 7);
+
+    let material = |body: &str| {
+        format!(
+            "[textures.mask]\ntype = \"noise\"\nscale = 1\n\n[materials.paint]\ntype = \"principled\"\n{}\n",
+            body
+        )
+    };
+    assert!(
+        file::parse(&material(
+            "base_color = \"mask\"\nroughness = \"mask\"\nior = 1.33"
+        ))
+        .is_ok()
+    );
+
+    for (body, expected) in [
+        (
+            "base_color = [1, 1, 1]\nmetallic = 2",
+            "metallic must be between 0 and 1",
+        ),
+        (
+            "base_color = [1, 1, 1]\nsheen = \"velvet\"",
+            "unknown texture 'velvet'",
+        ),
+        ("base_color = [1, 1, 1]\nior = 0", "ior must be positive"),
+    ] {
+        let message = format!("{:#}", file::parse(&material(body)).err().unwrap());
+        assert!(message.contains(expected), "{}", message);
+    }
+}
+
+#[test]
+fn environment_is_loaded_next_to_the_scene_file() {
+    let scene = Scene::from_file(Path::new("scenes/environment.toml")).unwrap();
+    assert!(scene.camera.environment.is_some());
+
+    let message = format!(
+        "{:#}",
+        file::parse("[environment]\ntype = \"image\"\nfile = \"missing.hdr\"\n")
+            .err()
+            .unwrap()
+    );
+    assert!(
+        message.contains("could not read environment map 'missing.hdr'"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn image_textures_are_loaded_next_to_the_scene_file() {
+    let source = "[textures.map]\ntype = \"image\"\nfile = \"../earthmap.jpg\"\n";
+    assert!(file::parse_in(source, Path::new("scenes")).is_ok());
+
+    let message = format!("{:#}", file::parse(source).err().unwrap());
+    assert!(
+        message.contains("in texture 'map' at line 1"),
+        "{}",
+        message
+    );
+    assert!(
+        message.contains("could not load texture image '../earthmap.jpg'"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn sky_takes_the_sun_position_and_haze() {
+    let scene = Scene::from_file(Path::new("scenes/sky.toml")).unwrap();
+    assert!(scene.camera.environment.is_some());
+
+    for (sky, expected) in [
+        ("elevation = 95", "elevation must be between 0 and 90"),
+        (
+            "elevation = 30\nturbidity = 1",
+            "turbidity must be between 2 and 10",
+        ),
+        (
+            "elevation = 30\nintensity = -1",
+            "intensity must not be negative",
+        ),
+    ] {
+        let source = format!("[environment]\ntype = \"sky\"\n{}\n", sky);
+        let message = format!("{:#}", file::parse(&source).err().unwrap());
+        assert!(message.contains("in environment"), "{}", message);
+        assert!(message.contains(expected), "{}", message);
+    }
+}
+
+#[test]
+fn lights_are_read_and_checked() {
+    let scene = Scene::from_file(Path::new("scenes/lights.toml")).unwrap();
+    assert_eq!(scene.analytic_lights.len(), 3);
+    assert_eq!(scene.lights.objects.len(), 0);
+
+    for (light, expected) in [
+        (
+            "type = \"point\"\nposition = [0, 1, 0]\nintensity = [1, -1, 1]",
+            "intensity must not be negative",
+        ),
+        (
+            "type = \"spot\"\nposition = [0, 1, 0]\ndirection = [0, 0, 0]\n\
+             intensity = [1, 1, 1]\ninner_angle = 10\nouter_angle = 20",
+            "direction must not be zero",
+        ),
+        (
+            "type = \"spot\"\nposition = [0, 1, 0]\ndirection = [0, -1, 0]\n\
+             intensity = [1, 1, 1]\ninner_angle = 30\nouter_angle = 20",
+            "inner_angle must be between 0 and outer_angle",
+        ),
+        (
+            "type = \"directional\"\ndirection = [0, -1, 0]\nirradiance = [1, 1, 1]\n\
+             angular_radius = 90",
+            "angular_radius must be at least 0 and below 90",
+        ),
+    ] {
+        let source = format!("[camera]\nimage_width = 100\n\n[[lights]]\n{}\n", light);
+        let message = format!("{:#}", file::parse(&source).err().unwrap());
+        assert!(message.contains("in light at line 4"), "{}", message);
+        assert!(message.contains(expected), "{}", message);
+    }
+}
+
+#[test]
+fn grid_media_take_a_file_or_noise() {
+    let scene = Scene::from_file(Path::new("scenes/cloud.toml")).unwrap();
+    assert_eq!(scene.world.objects.len(), 2);
+
+    let medium = |source: &str| {
+        format!(
+            "[[objects]]\ntype = \"grid_medium\"\na = [0, 0, 0]\nb = [1, 1, 1]\n{}\n",
+            source
+        )
+    };
+    for (source, expected) in [
+        ("density = 1", "needs either a file or a noise scale"),
+        (
+            "density = 1\nnoise = 2\nfile = \"smoke.npy\"",
+            "needs either a file or a noise scale",
+        ),
+        ("density = 0\nnoise = 2", "medium density must be positive"),
+        (
+            "density = 1\nfile = \"missing.raw\"\nresolution = [4, 4, 4]",
+            "could not read grid 'missing.raw'",
+        ),
+    ] {
+        let message = format!("{:#}", file::parse(&medium(source)).err().unwrap());
+        assert!(message.contains("in object at line 1"), "{}", message);
+        assert!(message.contains(expected), "{}", message);
+    }
+}
+
+#[test]
+fn media_take_a_phase_function() {
+    let scene = Scene::from_file(Path::new("scenes/fog.toml")).unwrap();
+    assert_eq!(scene.analytic_lights.len(), 2);
+
+    let medium = |phase: &str| {
+        format!(
+            "[materials.glass]\ntype = \"dielectric\"\nrefraction_index = 1.5\n\n\
+             [[objects]]\ntype = \"constant_medium\"\ndensity = 0.5\nalbedo = [1, 1, 1]\n\
+             boundary = {{ type = \"sphere\", center = [0, 0, 0], radius = 1, material = \"glass\" }}\n\
+             phase = {}\n",
+            phase
+        )
+    };
+    for phase in [
+        "{ type = \"isotropic\" }",
+        "{ type = \"henyey_greenstein\", g = -0.5 }",
+        "{ type = \"double_henyey_greenstein\", g1 = 0.9, g2 = -0.2, weight = 0.7 }",
+        "{ type = \"rayleigh\" }",
+    ] {
+        let scene = file::parse(&medium(phase)).unwrap();
+        assert_eq!(scene.world.objects.len(), 1);
+    }
+
+    for (phase, expected) in [
+        (
+            "{ type = \"henyey_greenstein\", g = 1 }",
+            "g must be strictly between -1 and 1",
+        ),
+        (
+            "{ type = \"double_henyey_greenstein\", g1 = 0.9, g2 = -1.5, weight = 0.7 }",
+            "g2 must be strictly between -1 and 1",
+        ),
+        (
+            "{ type = \"double_henyey_greenstein\", g1 = 0.9, g2 = -0.2, weight = 2 }",
+            "weight must be between 0 and 1",
+        ),
+        ("{ type = \"mie\" }", "unknown variant `mie`"),
+    ] {
+        let message = format!("{:#}", file::parse(&medium(phase)).err().unwrap());
+        assert!(message.contains(expected), "{}", message);
+    }
+
+    let source = "[[objects]]\ntype = \"grid_medium\"\na = [0, 0, 0]\nb = [1, 1, 1]\n\
+                  density = 1\nnoise = 2\nresolution = [8, 8, 8]\n\
+                  phase = { type = \"henyey_greenstein\", g = 0.8 }\n";
+    assert_eq!(file::parse(source).unwrap().world.objects.len(), 1);
+}
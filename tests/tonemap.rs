@@ -0,0 +1,87 @@
+use raytracer::color::Color;
+use raytracer::tonemap::{Operator, ToneMapper, srgb_decode, srgb_encode};
+
+fn grey(value: f64) -> Color {
+    Color::new(value, value, value)
+}
+
+fn mapper(operator: Operator) -> ToneMapper {
+    ToneMapper {
+        operator,
+        ..ToneMapper::new()
+    }
+}
+
+#[test]
+fn operators_are_monotonic_and_bounded() {
+    for operator in Operator::ALL {
+        let mapper = mapper(operator);
+        let mut previous = 0.0;
+        for i in 0..2000 {
+            let value = mapper.map(grey(i as f64 * 0.01)).x();
+            assert!((0.0..=1.0).contains(&value), "{} gave {}", operator, value);
+            assert!(value >= previous - 1e-12, "{} is not monotonic", operator);
+            previous = value;
+        }
+        assert!(mapper.map(grey(0.0)).x() < 0.01, "{} lifts black", operator);
+    }
+}
+
+#[test]
+fn curves_hit_their_reference_points() {
+    assert_eq!(mapper(Operator::Clamp).map(grey(15.0)).x(), 1.0);
+    assert_eq!(mapper(Operator::Reinhard).map(grey(1.0)).x(), 0.5);
+
+    let extended = ToneMapper {
+        white_point: 15.0,
+        ..mapper(Operator::ExtendedReinhard)
+    };
+    assert!((extended.map(grey(15.0)).x() - 1.0).abs() < 1e-12);
+    assert!(extended.map(grey(7.0)).x() < 1.0);
+
+    // Hable is normalized so that its white point of 11.2 maps to 1 at
+    // its built-in exposure bias of 2
+    assert!((mapper(Operator::Hable).map(grey(5.6)).x() - 1.0).abs() < 1e-9);
+    assert!(mapper(Operator::Aces).map(grey(0.18)).x() > 0.1);
+}
+
+#[test]
+fn exposure_is_measured_in_stops() {
+    let brighter = ToneMapper {
+        exposure: 1.0,
+        ..ToneMapper::new()
+    };
+    let darker = ToneMapper {
+        exposure: -2.0,
+        ..ToneMapper::new()
+    };
+
+    assert!((brighter.map(grey(0.2)).x() - 0.4).abs() < 1e-12);
+    assert!((darker.map(grey(0.2)).x() - 0.05).abs() < 1e-12);
+}
+
+#[test]
+fn bad_values_map_to_black() {
+    let mapped = ToneMapper::new().to_display(Color::new(f64::NAN, -1.0, 0.5));
+    assert_eq!(mapped.x(), 0.0);
+    assert_eq!(mapped.y(), 0.0);
+}
+
+#[test]
+fn srgb_transfer_round_trips() {
+    assert_eq!(srgb_encode(0.0), 0.0);
+    assert!((srgb_encode(1.0) - 1.0).abs() < 1e-12);
+    assert!((srgb_encode(0.18) - 0.4614).abs() < 1e-4);
+    for i in 0..=100 {
+        let linear = i as f64 / 100.0;
+        assert!((srgb_decode(srgb_encode(linear)) - linear).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn operators_parse_from_their_names() {
+    for operator in Operator::ALL {
+        assert_eq!(operator.name().parse::<Operator>().unwrap(), operator);
+    }
+    assert!("filmic".parse::<Operator>().is_err());
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::grey;
+use raytracer::hittable::quad::create_box;
+use raytracer::hittable::sphere::Sphere;
+use raytracer::hittable::transform::Transform;
+use raytracer::hittable::{HitRecord, Hittable};
+use raytracer::interval::Interval;
+use raytracer::matrix::Matrix4;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+fn assert_close(a: Vector3, b: Vector3) {
+    assert!((a - b).length() < 1e-9, "{} != {}", a, b);
+}
+
+#[test]
+fn inverse_undoes_the_transform() {
+    let matrix = Matrix4::scale(Vector3::new(2.0, 3.0, 0.5))
+        .then(&Matrix4::rotate(70.0, Vector3::new(1.0, -2.0, 0.5)))
+        .then(&Matrix4::translate(Vector3::new(4.0, 5.0, 6.0)));
+    let inverse = matrix.inverse().unwrap();
+    let p = Point3::new(0.3, -1.2, 7.0);
+
+    assert_close(inverse.transform_point(&matrix.transform_point(&p)), p);
+    assert_close(inverse.transform_vector(&matrix.transform_vector(&p)), p);
+    assert!(
+        Matrix4::scale(Vector3::new(1.0, 0.0, 1.0))
+            .inverse()
+            .is_none()
+    );
+}
+
+#[test]
+fn then_applies_transforms_in_order() {
+    let p = Point3::new(1.0, 0.0, 0.0);
+    let rotate_then_move =
+        Matrix4::rotate_z(90.0).then(&Matrix4::translate(Vector3::new(1.0, 0.0, 0.0)));
+    let move_then_rotate =
+        Matrix4::translate(Vector3::new(1.0, 0.0, 0.0)).then(&Matrix4::rotate_z(90.0));
+
+    assert_close(
+        rotate_then_move.transform_point(&p),
+        Point3::new(1.0, 1.0, 0.0),
+    );
+    assert_close(
+        move_then_rotate.transform_point(&p),
+        Point3::new(0.0, 2.0, 0.0),
+    );
+}
+
+#[test]
+fn look_at_turns_z_towards_the_target() {
+    let from = Point3::new(1.0, 2.0, 3.0);
+    let matrix = Matrix4::look_at(
+        from,
+        Point3::new(1.0, 2.0, 10.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+
+    assert_close(matrix.transform_point(&Point3::new(0.0, 0.0, 0.0)), from);
+    assert_close(
+        matrix.transform_vector(&Vector3::new(0.0, 0.0, 1.0)),
+        Vector3::new(0.0, 0.0, 1.0),
+    );
+    assert_close(
+        matrix.transform_vector(&Vector3::new(0.0, 1.0, 0.0)),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+}
+
+#[test]
+fn scaled_sphere_is_an_ellipsoid_with_perpendicular_normals() {
+    let sphere = Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, grey()));
+    let ellipsoid = Transform::new(sphere, Matrix4::scale(Vector3::new(4.0, 1.0, 1.0)));
+
+    // Hit from the side where the surface is slanted
+    let origin = Point3::new(2.0, 5.0, 0.0);
+    let mut rec = HitRecord::new();
+    let hit = ellipsoid.hit(
+        &Ray::new(origin, Vector3::new(0.0, -1.0, 0.0)),
+        &mut Interval::new(0.001, f64::INFINITY),
+        &mut rec,
+        &mut Rng::new(0),
+    );
+
+    assert!(hit);
+    let y = (1.0 - 0.25f64).sqrt();
+    assert!((rec.t - (5.0 - y)).abs() < 1e-9);
+    assert_close(rec.p, Point3::new(2.0, y, 0.0));
+    // The gradient of x^2/16 + y^2 is (x/8, 2y)
+    assert_close(
+        rec.normal,
+        Vector3::new(2.0 / 8.0, 2.0 * y, 0.0).unit_vector(),
+    );
+    assert!((rec.normal.length() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn bbox_encloses_the_rotated_object() {
+    let cube = Arc::new(create_box(
+        Point3::new(-1.0, -1.0, -1.0),
+        Point3::new(1.0, 1.0, 1.0),
+        grey(),
+    ));
+    let rotated = Transform::new(cube, Matrix4::rotate_y(45.0));
+
+    // Quads pad their boxes a little in the flat direction
+    let half_diagonal = 2f64.sqrt();
+    let bbox = rotated.bbox();
+    assert!((bbox.max().x() - half_diagonal).abs() < 1e-3);
+    assert!((bbox.min().z() + half_diagonal).abs() < 1e-3);
+    assert!((bbox.max().y() - 1.0).abs() < 1e-3);
+}
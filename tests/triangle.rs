@@ -0,0 +1,156 @@
+use raytracer::color::Color;
+use raytracer::hittable::bvh_node::BVHNode;
+use raytracer::hittable::mesh::{MeshData, TriangleMesh};
+use raytracer::hittable::transform::Transform;
+use raytracer::hittable::triangle::Triangle;
+use raytracer::hittable::{HitRecord, Hittable, HittableList};
+use raytracer::interval::Interval;
+use raytracer::material::lambertian::Lambertian;
+use raytracer::matrix::Matrix4;
+use raytracer::ray::Ray;
+use raytracer::rng::Rng;
+use raytracer::vector::{Point3, Vector3};
+use std::sync::Arc;
+
+fn white() -> Arc<Lambertian> {
+    Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)))
+}
+
+fn cast(object: &dyn Hittable, origin: Point3, direction: Vector3) -> Option<HitRecord> {
+    let mut rec = HitRecord::new();
+    let mut t = Interval::new(0.001, f64::INFINITY);
+    object
+        .hit(
+            &Ray::new(origin, direction),
+            &mut t,
+            &mut rec,
+            &mut Rng::new(0),
+        )
+        .then_some(rec)
+}
+
+#[test]
+fn triangle_hit_reports_barycentric_coordinates() {
+    let triangle = Triangle::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        white(),
+    );
+
+    let rec = cast(
+        &triangle,
+        Point3::new(0.25, 0.5, 1.0),
+        Vector3::new(0.0, 0.0, -1.0),
+    )
+    .unwrap();
+    assert!((rec.t - 1.0).abs() < 1e-9);
+    assert!((rec.u - 0.25).abs() < 1e-9);
+    assert!((rec.v - 0.5).abs() < 1e-9);
+    assert!(rec.front_face);
+
+    assert!(
+        cast(
+            &triangle,
+            Point3::new(0.75, 0.75, 1.0),
+            Vector3::new(0.0, 0.0, -1.0)
+        )
+        .is_none()
+    );
+}
+
+#[test]
+fn mesh_interpolates_vertex_normals() {
+    let normals = vec![
+        Vector3::new(-1.0, 0.0, 1.0).unit_vector(),
+        Vector3::new(1.0, 0.0, 1.0).unit_vector(),
+        Vector3::new(1.0, 0.0, 1.0).unit_vector(),
+        Vector3::new(-1.0, 0.0, 1.0).unit_vector(),
+    ];
+    let data = MeshData::new(
+        vec![
+            Point3::new(-1.0, -1.0, 0.0),
+            Point3::new(1.0, -1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(-1.0, 1.0, 0.0),
+        ],
+        normals,
+        Vec::new(),
+        vec![[0, 1, 2], [0, 2, 3]],
+    );
+    let mesh = TriangleMesh::new(Arc::new(data), white());
+
+    let centre = cast(
+        &mesh,
+        Point3::new(0.0, 0.0, 5.0),
+        Vector3::new(0.0, 0.0, -1.0),
+    )
+    .unwrap();
+    assert!((centre.normal.x()).abs() < 1e-9);
+    assert!((centre.normal.z() - 1.0).abs() < 1e-9);
+
+    let right = cast(
+        &mesh,
+        Point3::new(0.5, 0.0, 5.0),
+        Vector3::new(0.0, 0.0, -1.0),
+    )
+    .unwrap();
+    assert!(right.normal.x() > 0.0);
+}
+
+#[test]
+fn triangles_work_with_bvh_and_rotation() {
+    let mut list = HittableList::new();
+    for i in 0..10 {
+        let x = i as f64 * 2.0;
+        list.add(Arc::new(Triangle::new(
+            Point3::new(x, 0.0, 0.0),
+            Point3::new(x + 1.0, 0.0, 0.0),
+            Point3::new(x, 1.0, 0.0),
+            white(),
+        )));
+    }
+
+    let bvh = BVHNode::new(&list);
+    let rec = cast(
+        &bvh,
+        Point3::new(8.2, 0.2, 3.0),
+        Vector3::new(0.0, 0.0, -1.0),
+    )
+    .unwrap();
+    assert!((rec.t - 3.0).abs() < 1e-9);
+
+    // Rotated half a turn about y the triangles face -z and lie at negative x
+    let rotated = Transform::new(Arc::new(bvh), Matrix4::rotate_y(180.0));
+    let rec = cast(
+        &rotated,
+        Point3::new(-8.2, 0.2, -3.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    )
+    .unwrap();
+    assert!((rec.t - 3.0).abs() < 1e-9);
+    assert!(rec.front_face);
+}
+
+#[test]
+fn degenerate_triangles_are_never_hit() {
+    let collinear = Triangle::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(2.0, 0.0, 0.0),
+        white(),
+    );
+    let point = Triangle::new(
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        white(),
+    );
+
+    for triangle in [&collinear, &point] {
+        for direction in [Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, -1.0, -1.0)] {
+            let origin = Point3::new(1.0, 0.0, 0.0) - direction;
+            assert!(cast(triangle, origin, direction).is_none());
+        }
+    }
+}